@@ -1,4 +1,5 @@
 use crate::token:: { TokenKind, Token };
+use std::rc::{ Rc };
 
 #[derive(Debug, Clone)]
 pub enum Ast {
@@ -16,8 +17,9 @@ pub enum Ast {
     },
     
     LetStatement {
-        token: Token,
+        token: Token,               // 'let' or 'const' token
         ident: Box<Ast>,
+        annotation: Box<Ast>,       // Ast::TypeExpression, Ast::Expression with Illegal token if omitted
         value: Box<Ast>,
     },
 
@@ -25,6 +27,71 @@ pub enum Ast {
         token: Token,
         return_value: Box<Ast>,
     },
+
+    ThrowStatement {
+        token: Token,
+        value: Box<Ast>,
+    },
+
+    YieldStatement {
+        token: Token,               // 'yield' token
+        value: Box<Ast>,
+    },
+
+    StructDeclaration {
+        token: Token,               // 'struct' token
+        name: Box<Ast>,             // Ast::Identifier
+        fields: Vec<Box<Ast>>,      // Ast::Identifier
+    },
+
+    EnumDeclaration {
+        token: Token,               // 'enum' token
+        name: Box<Ast>,             // Ast::Identifier
+        variants: Vec<Box<Ast>>,    // Ast::EnumVariant
+    },
+
+    EnumVariant {
+        token: Token,               // variant name token
+        name: String,
+        fields: Vec<Box<Ast>>,      // Ast::Identifier, empty for a unit variant
+    },
+
+    ClassDeclaration {
+        token: Token,               // 'class' token
+        name: Box<Ast>,             // Ast::Identifier
+        superclass: Box<Ast>,       // Ast::Identifier, Ast::Expression with Illegal token if omitted
+        methods: Vec<Box<Ast>>,     // Ast::MethodDefinition
+    },
+
+    MethodDefinition {
+        token: Token,               // method name token
+        name: String,
+        function: Box<Ast>,         // Ast::FunctionLiteral
+    },
+
+    ImportStatement {
+        token: Token,               // 'import' token
+        path: Box<Ast>,             // Ast::StringLiteral
+        alias: Box<Ast>,            // Ast::Identifier
+    },
+
+    ImportExpression {
+        token: Token,               // 'import' token
+        path: Box<Ast>,
+    },
+
+    ExportStatement {
+        token: Token,               // 'export' token
+        statement: Box<Ast>,        // let, const, struct, enum or class declaration
+    },
+
+    InfixDeclaration {
+        token: Token,               // 'infix' token
+        operator: String,
+        precedence: String,         // "equals", "lessgreater", "sum" or "product"
+        associativity: String,      // "left" or "right"
+        value: Box<Ast>,
+    },
     
     ExpressionStatement {
         token: Token,
@@ -54,6 +121,10 @@ pub enum Ast {
         value: bool,
     },
 
+    NullLiteral {
+        token: Token,
+    },
+
     IfExpression {
         token: Token,
         condition: Box<Ast>,
@@ -63,13 +134,21 @@ pub enum Ast {
 
     BlockStatement {
         token: Token,
-        statements: Vec<Box<Ast>>,
+        statements: Rc<Vec<Box<Ast>>>,  // shared with the generators running the block
     },
 
     FunctionLiteral {
         token: Token,
-        parameters: Vec<Box<Ast>>,  // Ast::Identifier
-        body: Box<Ast>,             // Ast::BlockStatement
+        parameters: Vec<Box<Ast>>,  // Ast::Identifier, the last one may be an Ast::SpreadExpression
+        parameter_types: Vec<Box<Ast>>, // one per parameter, Ast::TypeExpression or Ast::Expression with Illegal token
+        return_type: Box<Ast>,      // Ast::TypeExpression, Ast::Expression with Illegal token if omitted
+        body: Rc<Ast>,              // Ast::BlockStatement, shared with the functions evaluated from it
+    },
+
+    TypeExpression {
+        token: Token,
+        name: String,               // "array" for `[T]`, "fn" for `fn(T..) -> R`, otherwise the type name
+        arguments: Vec<Box<Ast>>,   // element type of an array; parameter types then return type of a function
     },
 
     CallExpression {
@@ -89,9 +168,67 @@ pub enum Ast {
     },
 
     IndexExpression {
-        token: Token,
+        token: Token,               // '[' token, or '?[' for optional indexing
         left: Box<Ast>,
         index: Box<Ast>,
+    },
+
+    TryExpression {
+        token: Token,               // 'try' token
+        block: Box<Ast>,            // Ast::BlockStatement
+        parameter: Box<Ast>,        // Ast::Identifier, Ast::Expression with Illegal token if no catch
+        catch: Box<Ast>,            // Ast::BlockStatement, Ast::Expression with Illegal token if omitted
+        finally: Box<Ast>,          // Ast::BlockStatement, Ast::Expression with Illegal token if omitted
+    },
+
+    ForExpression {
+        token: Token,               // 'for' token
+        variable: Box<Ast>,         // Ast::Identifier
+        iterable: Box<Ast>,
+        body: Box<Ast>,             // Ast::BlockStatement
+    },
+
+    SpreadExpression {
+        token: Token,               // '...' token
+        value: Box<Ast>,            // array to spread; Ast::Identifier for a variadic parameter
+    },
+
+    RangeExpression {
+        token: Token,               // '..' or '..=' token
+        start: Box<Ast>,
+        end: Box<Ast>,
+        step: Box<Ast>,             // Ast::Expression with Illegal token if omitted
+    },
+
+    ListComprehension {
+        token: Token,               // '[' token
+        element: Box<Ast>,
+        clauses: Vec<Box<Ast>>,     // Ast::ComprehensionClause, the first one a `for` clause
+    },
+
+    ComprehensionClause {
+        token: Token,               // 'for' or 'if' token
+        variable: Box<Ast>,         // Ast::Identifier, Ast::Expression with Illegal token for an `if` clause
+        value: Box<Ast>,            // iterable of a `for` clause, condition of an `if` clause
+    },
+
+    AssignExpression {
+        token: Token,               // '=' token
+        target: Box<Ast>,           // Ast::Identifier or Ast::MemberExpression
+        value: Box<Ast>,
+    },
+
+    MemberExpression {
+        token: Token,               // '.' token, or '?.' for optional access
+        object: Box<Ast>,
+        member: Box<Ast>,           // Ast::Identifier
+    },
+
+    SliceExpression {
+        token: Token,               // '[' or '?[' token
+        left: Box<Ast>,
+        start: Box<Ast>,            // Ast::Expression with Illegal token if omitted
+        end: Box<Ast>,              // Ast::Expression with Illegal token if omitted
     }
 }
 
@@ -107,13 +244,64 @@ impl Ast {
             Ast::Identifier { value, .. } => {
                 string = format!("{}", value);
             },
-            Ast::LetStatement {token, ident, value} => {
-                string = format!("{} {} = {};",
-                                 token.literal, ident.to_string(), value.to_string());
+            Ast::LetStatement {token, ident, annotation, value} => {
+                if let Ast::TypeExpression { .. } = **annotation {
+                    string = format!("{} {}: {} = {};",
+                                     token.literal, ident.to_string(), annotation.to_string(), value.to_string());
+                }
+                else {
+                    string = format!("{} {} = {};",
+                                     token.literal, ident.to_string(), value.to_string());
+                }
             },
             Ast::ReturnStatement { token, return_value } => {
                 string = format!("{} {};", token.literal, return_value.to_string());
             },
+            Ast::ThrowStatement { token, value } |
+            Ast::YieldStatement { token, value } => {
+                string = format!("{} {};", token.literal, value.to_string());
+            },
+            Ast::StructDeclaration { token, name, fields } => {
+                let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+                string = format!("{} {} {{ {} }}", token.literal, name.to_string(), fields.join(", "));
+            },
+            Ast::EnumDeclaration { token, name, variants } => {
+                let variants: Vec<String> = variants.iter().map(|v| v.to_string()).collect();
+                string = format!("{} {} {{ {} }}", token.literal, name.to_string(), variants.join(", "));
+            },
+            Ast::EnumVariant { name, fields, .. } => {
+                let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+                string = name.to_string();
+                if fields.len() != 0 {
+                    string = format!("{}({})", string, fields.join(", "));
+                }
+            },
+            Ast::ClassDeclaration { token, name, superclass, methods } => {
+                string = format!("{} {}", token.literal, name.to_string());
+                if let Ast::Identifier { .. } = **superclass {
+                    string = format!("{} extends {}", string, superclass.to_string());
+                }
+                let methods: Vec<String> = methods.iter().map(|m| m.to_string()).collect();
+                string = format!("{} {{ {} }}", string, methods.join(" "));
+            },
+            Ast::MethodDefinition { name, function, .. } => {
+                if let Ast::FunctionLiteral { ref parameters, ref parameter_types, ref return_type, ref body, .. } = **function {
+                    string = format!("{}{} {{{}}}", name, signature(parameters, parameter_types, return_type), body.to_string());
+                }
+            },
+            Ast::ImportStatement { token, path, alias } => {
+                string = format!("{} \"{}\" as {};", token.literal, path.to_string(), alias.to_string());
+            },
+            Ast::ImportExpression { token, path } => {
+                string = format!("{}({})", token.literal, path.to_string());
+            },
+            Ast::ExportStatement { token, statement } => {
+                string = format!("{} {}", token.literal, statement.to_string());
+            },
+            Ast::InfixDeclaration { token, operator, precedence, associativity, value } => {
+                string = format!("{} \"{}\" {} {} = {};",
+                                 token.literal, operator, precedence, associativity, value.to_string());
+            },
             Ast::ExpressionStatement { expression,.. } => {
                 string = format!("{}", expression.to_string());
             },
@@ -132,6 +320,7 @@ impl Ast {
             Ast::Boolean { value, ..} => {
                 string = format!("{}", value.to_string());
             },
+            Ast::NullLiteral { token } => string = token.literal.to_string(),
             Ast::IfExpression { token, condition, consequence, alternative } => {
                 string = format!("{}({}) {{ {} }}", token.literal, condition.to_string(), consequence.to_string());
                 if  let Ast::BlockStatement { ref token, ..} = **alternative {
@@ -142,27 +331,29 @@ impl Ast {
                 };
             },
             Ast::BlockStatement { statements, .. } => {
-                for statement in statements {
+                for statement in statements.iter() {
                     string = format!("{}{}", string, statement.to_string());
                 }
             },
-            Ast::FunctionLiteral { token, parameters, body } => {
-                string = format!("{}(", token.literal);
-                for (i, parameter ) in parameters.iter().enumerate() {
-                    if i == 0 {
-                        string = format!("{}{}", string, parameter.to_string());
-                    }
-                    else {
-                        string = format!("{}, {}", string, parameter.to_string());
-                    }
-                }
-                string = format!("{}) {{{}}}", string, body.to_string());
+            Ast::FunctionLiteral { token, parameters, parameter_types, return_type, body } => {
+                string = format!("{}{} {{{}}}", token.literal, signature(parameters, parameter_types, return_type), body.to_string());
             }
+            Ast::TypeExpression { name, arguments, .. } => {
+                let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+                match name.as_ref() {
+                    "array" => string = format!("[{}]", arguments.join("")),
+                    "fn"    => {
+                        let (result, parameters) = arguments.split_last().unwrap();
+                        string = format!("fn({}) -> {}", parameters.join(", "), result);
+                    },
+                    _       => string = name.to_string(),
+                }
+            },
             Ast::CallExpression { ref function, arguments, .. } => {
                 match **function {
                     Ast::Identifier            { ref value, .. } => string = format!("{}", value.to_string()),
                     Ast::FunctionLiteral { .. }            => string = format!("{}", function.to_string()),
-                    _ => (),
+                    _ => string = function.to_string(),
                 }
                 string = format!("{}(", string);
 
@@ -189,7 +380,43 @@ impl Ast {
                 }
                 string = format!("{}]", string);
             },
-            Ast::IndexExpression { left, index, .. } => string = format!("({}[{}])", left.to_string(), index.to_string()),
+            Ast::IndexExpression { token, left, index } => string = format!("({}{}{}])", left.to_string(), token.literal, index.to_string()),
+            Ast::TryExpression { token, block, parameter, catch, finally } => {
+                string = format!("{} {{ {} }}", token.literal, block.to_string());
+                if let Ast::BlockStatement { .. } = **catch {
+                    string = format!("{} catch ({}) {{ {} }}", string, parameter.to_string(), catch.to_string());
+                }
+                if let Ast::BlockStatement { .. } = **finally {
+                    string = format!("{} finally {{ {} }}", string, finally.to_string());
+                }
+            },
+            Ast::ForExpression { token, variable, iterable, body } => {
+                string = format!("{} ({} in {}) {{ {} }}", token.literal, variable.to_string(), iterable.to_string(), body.to_string());
+            },
+            Ast::SpreadExpression { token, value } => string = format!("{}{}", token.literal, value.to_string()),
+            Ast::RangeExpression { token, start, end, step } => {
+                string = format!("({}{}{}", start.to_string(), token.literal, end.to_string());
+                if let Ast::Expression { .. } = **step {
+                    string = format!("{})", string);
+                }
+                else {
+                    string = format!("{} step {})", string, step.to_string());
+                }
+            },
+            Ast::ListComprehension { element, clauses, .. } => {
+                let clauses: Vec<String> = clauses.iter().map(|c| c.to_string()).collect();
+                string = format!("[{} {}]", element.to_string(), clauses.join(" "));
+            },
+            Ast::ComprehensionClause { token, variable, value } => {
+                match token.kind {
+                    TokenKind::For => string = format!("{} {} in {}", token.literal, variable.to_string(), value.to_string()),
+                    _              => string = format!("{} {}", token.literal, value.to_string()),
+                }
+            },
+            Ast::AssignExpression { target, value, .. } => string = format!("({} = {})", target.to_string(), value.to_string()),
+            Ast::MemberExpression { token, object, member } => string = format!("({}{}{})", object.to_string(), token.literal, member.to_string()),
+            Ast::SliceExpression { token, left, start, end } =>
+                string = format!("({}{}{}:{}])", left.to_string(), token.literal, start.to_string(), end.to_string()),
         }
 
         string
@@ -201,12 +428,14 @@ impl Ast {
             Ast::Identifier           {..} => "Identifier".to_string(),
             Ast::LetStatement         {..} => "LetStatement".to_string(),
             Ast::ReturnStatement      {..} => "ReturnStatement".to_string(),
+            Ast::InfixDeclaration     {..} => "InfixDeclaration".to_string(),
             Ast::ExpressionStatement  {..} => "ExpressionStatement".to_string(),
             Ast::Expression           {..} => "Expression".to_string(),
             Ast::IntegerLiteral       {..} => "IntegerLiteral".to_string(),
             Ast::PrefixExpression     {..} => "PrefixExpression".to_string(),
             Ast::InfixExpression      {..} => "InfixExpression".to_string(),
             Ast::Boolean              {..} => "Boolean".to_string(),
+            Ast::NullLiteral          {..} => "NullLiteral".to_string(),
             Ast::IfExpression         {..} => "IfExpression".to_string(),
             Ast::BlockStatement       {..} => "BlockStatement".to_string(),
             Ast::FunctionLiteral      {..} => "FunctionLiteral".to_string(),
@@ -214,36 +443,170 @@ impl Ast {
             Ast::StringLiteral        {..} => "StringLiteral".to_string(),
             Ast::ArrayLiteral         {..} => "ArrayLiteral".to_string(),
             Ast::IndexExpression      {..} => "IndexExpression".to_string(),
+            Ast::SliceExpression      {..} => "SliceExpression".to_string(),
+            Ast::MemberExpression     {..} => "MemberExpression".to_string(),
+            Ast::AssignExpression     {..} => "AssignExpression".to_string(),
+            Ast::ThrowStatement       {..} => "ThrowStatement".to_string(),
+            Ast::TryExpression        {..} => "TryExpression".to_string(),
+            Ast::YieldStatement       {..} => "YieldStatement".to_string(),
+            Ast::ForExpression        {..} => "ForExpression".to_string(),
+            Ast::SpreadExpression     {..} => "SpreadExpression".to_string(),
+            Ast::RangeExpression      {..} => "RangeExpression".to_string(),
+            Ast::ListComprehension    {..} => "ListComprehension".to_string(),
+            Ast::ComprehensionClause  {..} => "ComprehensionClause".to_string(),
+            Ast::StructDeclaration    {..} => "StructDeclaration".to_string(),
+            Ast::EnumDeclaration      {..} => "EnumDeclaration".to_string(),
+            Ast::EnumVariant          {..} => "EnumVariant".to_string(),
+            Ast::TypeExpression       {..} => "TypeExpression".to_string(),
+            Ast::ClassDeclaration     {..} => "ClassDeclaration".to_string(),
+            Ast::MethodDefinition     {..} => "MethodDefinition".to_string(),
+            Ast::ImportStatement      {..} => "ImportStatement".to_string(),
+            Ast::ImportExpression     {..} => "ImportExpression".to_string(),
+            Ast::ExportStatement      {..} => "ExportStatement".to_string(),
+        }
+    }
+
+    /// Direct child nodes in source order
+    pub fn children(&self) -> Vec<&Ast> {
+        let mut children: Vec<&Ast> = Vec::new();
+        match self {
+            Ast::Program { statements } => children.extend(statements.iter().map(|s| &**s)),
+            Ast::BlockStatement { statements, .. } => children.extend(statements.iter().map(|s| &**s)),
+            Ast::Expression { .. } |
+            Ast::Identifier { .. } |
+            Ast::IntegerLiteral { .. } |
+            Ast::Boolean { .. } |
+            Ast::NullLiteral { .. } |
+            Ast::StringLiteral { .. } => (),
+            Ast::LetStatement { ident, annotation, value, .. } => children.extend(vec![&**ident, &**annotation, &**value]),
+            Ast::TypeExpression { arguments, .. } => children.extend(arguments.iter().map(|a| &**a)),
+            Ast::ReturnStatement { return_value, .. } => children.push(return_value),
+            Ast::InfixDeclaration { value, .. } |
+            Ast::ThrowStatement { value, .. } |
+            Ast::YieldStatement { value, .. } |
+            Ast::SpreadExpression { value, .. } => children.push(value),
+            Ast::ForExpression { variable, iterable, body, .. } =>
+                children.extend(vec![&**variable, &**iterable, &**body]),
+            Ast::RangeExpression { start, end, step, .. } => children.extend(vec![&**start, &**end, &**step]),
+            Ast::ListComprehension { element, clauses, .. } => {
+                children.push(element);
+                children.extend(clauses.iter().map(|c| &**c));
+            },
+            Ast::ComprehensionClause { variable, value, .. } => children.extend(vec![&**variable, &**value]),
+            Ast::TryExpression { block, parameter, catch, finally, .. } =>
+                children.extend(vec![&**block, &**parameter, &**catch, &**finally]),
+            Ast::StructDeclaration { name, fields, .. } => {
+                children.push(name);
+                children.extend(fields.iter().map(|f| &**f));
+            },
+            Ast::EnumDeclaration { name, variants, .. } => {
+                children.push(name);
+                children.extend(variants.iter().map(|v| &**v));
+            },
+            Ast::EnumVariant { fields, .. } => children.extend(fields.iter().map(|f| &**f)),
+            Ast::ClassDeclaration { name, superclass, methods, .. } => {
+                children.extend(vec![&**name, &**superclass]);
+                children.extend(methods.iter().map(|m| &**m));
+            },
+            Ast::MethodDefinition { function, .. } => children.push(function),
+            Ast::ImportStatement { path, alias, .. } => children.extend(vec![&**path, &**alias]),
+            Ast::ImportExpression { path, .. } => children.push(path),
+            Ast::ExportStatement { statement, .. } => children.push(statement),
+            Ast::ExpressionStatement { expression, .. } => children.push(expression),
+            Ast::PrefixExpression { right, .. } => children.push(right),
+            Ast::InfixExpression { left, right, .. } => children.extend(vec![&**left, &**right]),
+            Ast::IfExpression { condition, consequence, alternative, .. } =>
+                children.extend(vec![&**condition, &**consequence, &**alternative]),
+            Ast::FunctionLiteral { parameters, parameter_types, return_type, body, .. } => {
+                children.extend(parameters.iter().map(|p| &**p));
+                children.extend(parameter_types.iter().map(|p| &**p));
+                children.push(return_type);
+                children.push(body);
+            },
+            Ast::CallExpression { function, arguments, .. } => {
+                children.push(function);
+                children.extend(arguments.iter().map(|a| &**a));
+            },
+            Ast::ArrayLiteral { elements, .. } => children.extend(elements.iter().map(|e| &**e)),
+            Ast::IndexExpression { left, index, .. } => children.extend(vec![&**left, &**index]),
+            Ast::SliceExpression { left, start, end, .. } => children.extend(vec![&**left, &**start, &**end]),
+            Ast::MemberExpression { object, member, .. } => children.extend(vec![&**object, &**member]),
+            Ast::AssignExpression { target, value, .. } => children.extend(vec![&**target, &**value]),
         }
+
+        children
     }
     
 }
 
+/// Name bound by a function parameter, `rest` for a variadic parameter `...rest`
+pub fn parameter_name(parameter: &Ast) -> String {
+    match parameter {
+        Ast::SpreadExpression { value, .. } => value.to_string(),
+        _                                   => parameter.to_string(),
+    }
+}
+
+pub fn is_spread(node: &Ast) -> bool {
+    match node {
+        Ast::SpreadExpression { .. } => true,
+        _                            => false,
+    }
+}
+
+/// Check whether the last of parameters is variadic
+pub fn is_variadic(parameters: &[Box<Ast>]) -> bool {
+    match parameters.last() {
+        Some(parameter) => is_spread(parameter),
+        None            => false,
+    }
+}
+
+/// Parameter list with annotations and the return type of a function: `(a: int, b) -> bool`
+fn signature(parameters: &[Box<Ast>], parameter_types: &[Box<Ast>], return_type: &Ast) -> String {
+    let mut string = String::from("(");
+    for (i, parameter) in parameters.iter().enumerate() {
+        if i != 0 {
+            string = format!("{}, ", string);
+        }
+
+        string = format!("{}{}", string, parameter.to_string());
+        if let Some(annotation) = parameter_types.get(i) {
+            if let Ast::TypeExpression { .. } = **annotation {
+                string = format!("{}: {}", string, annotation.to_string());
+            }
+        }
+    }
+    string = format!("{})", string);
+
+    if let Ast::TypeExpression { .. } = return_type {
+        string = format!("{} -> {}", string, return_type.to_string());
+    }
+
+    string
+}
+
 #[test]
 fn test_ast_string() {
     let program = Ast::Program {
         statements: vec![
             Box::new(
                 Ast::LetStatement {
-                    token: Token {
-                        kind: TokenKind::Let,
-                        literal: "let".to_string()
-                    },
+                    token: Token::new(TokenKind::Let, "let".to_string()),
                     ident: Box::new(
                         Ast::Identifier {
-                            token: Token {
-                                kind: TokenKind::Identifier,
-                                literal: "myVar".to_string()
-                        },
+                            token: Token::new(TokenKind::Identifier, "myVar".to_string()),
                             value: "myVar".to_string()
                         }
                     ),
+                    annotation: Box::new(
+                        Ast::Expression {
+                            token: Token::new(TokenKind::Illegal, "".to_string())
+                        }
+                    ),
                     value: Box::new(
                         Ast::Expression {
-                            token: Token {
-                                kind: TokenKind::Identifier,
-                                literal: "anotherVar".to_string()
-                            }
+                            token: Token::new(TokenKind::Identifier, "anotherVar".to_string())
                         }
                     )
                 }
@@ -1,21 +1,27 @@
 use crate::ast::{ Ast };
-use crate::object::{ Object, new_error };
+use crate::context::{ Context, size };
+use crate::generator::{ Iter };
+use crate::object::{ Object, new_error_with_kind };
+use crate::range::{ Range };
+use std::cell::{ RefCell };
+use std::mem;
 
-fn len(args: Vec<Object>) -> Object {
+fn len(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
     if args.len() != 1 {
-        return new_error(format!("wrong number of arguments. got={}, want=1", args.len()));
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
     }
 
     match &args[0] {
-        Object::String { value } => return Object::Integer { value: value.len() as i64 },
+        Object::String { value } => return Object::Integer { value: value.chars().count() as i64 },
         Object::Array { elements } => return Object::Integer { value: elements.len() as i64 },
-        _ => new_error(format!("argument to 'len' not supported, got {}", args[0].kind())),
+        Object::Range { range } => return Object::Integer { value: range.len() },
+        _ => new_error_with_kind("ArgumentError", format!("argument to 'len' not supported, got {}", args[0].kind())),
     }
 }
 
-fn first(args: Vec<Object>) -> Object {
+fn first(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
     if args.len() != 1 {
-        return new_error(format!("wrong number of arguments. got={}, want=1", args.len()));
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
     }
 
     match &args[0] {
@@ -23,7 +29,7 @@ fn first(args: Vec<Object>) -> Object {
             if value.len() == 0 {
                 return Object::Null;
             }
-            return Object::String { value: value[0..1].to_string() }
+            return Object::String { value: value.chars().take(1).collect() }
         },
         Object::Array { elements } => {
             if elements.len() == 0 {
@@ -31,13 +37,19 @@ fn first(args: Vec<Object>) -> Object {
             }
             return elements[0].clone()
         },
-        _ => new_error(format!("argument to 'first' not supported, got {}", args[0].kind())),
+        Object::Range { range } => {
+            if range.is_empty() {
+                return Object::Null;
+            }
+            return Object::Integer { value: range.get(0) }
+        },
+        _ => new_error_with_kind("ArgumentError", format!("argument to 'first' not supported, got {}", args[0].kind())),
     }    
 }
 
-fn last(args: Vec<Object>) -> Object {
+fn last(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
     if args.len() != 1 {
-        return new_error(format!("wrong number of arguments. got={}, want=1", args.len()));
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
     }
 
     match &args[0] {
@@ -45,7 +57,7 @@ fn last(args: Vec<Object>) -> Object {
             if value.len() == 0 {
                 return Object::Null;
             }
-            return Object::String { value: value[value.len()-1..].to_string() }
+            return Object::String { value: value.chars().last().map(|c| c.to_string()).unwrap_or_default() }
         },
         Object::Array { elements } => {
             if elements.len() == 0 {
@@ -53,13 +65,19 @@ fn last(args: Vec<Object>) -> Object {
             }
             return elements[elements.len()-1].clone()
         },
-        _ => new_error(format!("argument to 'last' not supported, got {}", args[0].kind())),
+        Object::Range { range } => {
+            if range.is_empty() {
+                return Object::Null;
+            }
+            return Object::Integer { value: range.get(range.len()-1) }
+        },
+        _ => new_error_with_kind("ArgumentError", format!("argument to 'last' not supported, got {}", args[0].kind())),
     }
 }
 
-fn rest(args: Vec<Object>) -> Object {
+fn rest(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
     if args.len() != 1 {
-        return new_error(format!("wrong number of arguments. got={}, want=1", args.len()));
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
     }
 
     match &args[0] {
@@ -67,7 +85,7 @@ fn rest(args: Vec<Object>) -> Object {
             if value.len() == 0 {
                 return Object::Null;
             }
-            return Object::String { value: value[1..].to_string() }
+            return Object::String { value: value.chars().skip(1).collect() }
         },
         Object::Array { elements } => {
             if elements.len() == 0 {
@@ -75,14 +93,22 @@ fn rest(args: Vec<Object>) -> Object {
             }
             return Object::Array { elements: elements[1..].to_vec() }
         },
-        _ => new_error(format!("argument to 'rest' not supported, got {}", args[0].kind())),
+        Object::Range { range } => {
+            match range.len() {
+                0 => return Object::Null,
+                // start + step may overflow past the only element
+                1 => return Object::Range { range: Range::new(range.end, range.end, range.step, false) },
+                _ => return Object::Range { range: Range { start: range.start + range.step, ..range.clone() } },
+            }
+        },
+        _ => new_error_with_kind("ArgumentError", format!("argument to 'rest' not supported, got {}", args[0].kind())),
     }
 }
 
-fn push(args: Vec<Object>) -> Object {
+fn push(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
 
     if args.len() != 2 {
-        return new_error(format!("wrong number of arguments. got={}, want=1", args.len()));
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
     }
 
     match &args[0] {
@@ -93,7 +119,7 @@ fn push(args: Vec<Object>) -> Object {
                     tmp.extend(str.to_string().chars());
                     return Object::String { value: tmp };
                     },
-                _ => return new_error(format!("argument to 'push' not String. got={}", &args[1].kind())),
+                _ => return new_error_with_kind("ArgumentError", format!("argument to 'push' not String. got={}", &args[1].kind())),
             }
         },
         Object::Array { elements } => {
@@ -101,8 +127,106 @@ fn push(args: Vec<Object>) -> Object {
             tmp.push(args[1].clone());
             return Object::Array { elements: tmp };
         },
-        _ => new_error(format!("argument to 'push' not supported, got {}", args[0].kind())),
+        _ => new_error_with_kind("ArgumentError", format!("argument to 'push' not supported, got {}", args[0].kind())),
+    }
+}
+
+/// error(kind, msg) creates an exception value to be thrown
+fn error(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
+    if args.len() != 2 {
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=2", args.len()));
     }
+
+    match (&args[0], &args[1]) {
+        (Object::String { value: kind }, Object::String { value: msg }) => Object::Exception { kind: kind.to_string(), msg: msg.to_string() },
+        _ => new_error_with_kind("ArgumentError", format!("arguments to 'error' must be String, got {} and {}", args[0].kind(), args[1].kind())),
+    }
+}
+
+/// variant(value) returns the variant name of an enum value
+fn variant(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
+    if args.len() != 1 {
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
+    }
+
+    match &args[0] {
+        Object::Variant { name, .. } => Object::String { value: name.to_string() },
+        _ => new_error_with_kind("ArgumentError", format!("argument to 'variant' must be an enum value, got {}", args[0].kind())),
+    }
+}
+
+/// puts(args..) prints each argument on its own line
+fn puts(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
+    for arg in args {
+        println!("{}", arg.inspect());
+    }
+
+    Object::Null
+}
+
+/// next(generator) resumes the generator and returns the value it yields, or null once it has finished
+fn next(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
+    if args.len() != 1 {
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
+    }
+
+    match &args[0] {
+        Object::Generator { generator } => match Iter::Generator(generator.clone()).next() {
+            Some(value) => value,
+            None        => Object::Null,
+        },
+        _ => new_error_with_kind("ArgumentError", format!("argument to 'next' must be Generator, got {}", args[0].kind())),
+    }
+}
+
+/// take(iterable, n) returns an array of the first n elements, consuming no more than that
+fn take(args: Vec<Object>, context: &RefCell<Context>) -> Object {
+    if args.len() != 2 {
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=2", args.len()));
+    }
+
+    let n = match args[1] {
+        Object::Integer { value } if value >= 0 => value as usize,
+        _ => return new_error_with_kind("ArgumentError", format!("count of 'take' must be a non-negative Integer, got {}", args[1].inspect())),
+    };
+
+    collect_elements(args[0].clone(), Some(n), context)
+}
+
+/// collect(iterable) returns an array of all elements
+fn collect(args: Vec<Object>, context: &RefCell<Context>) -> Object {
+    if args.len() != 1 {
+        return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want=1", args.len()));
+    }
+
+    collect_elements(args[0].clone(), None, context)
+}
+
+/// Array of the elements of iterable, up to limit. Each element is charged to the limits of
+/// context as it is taken, so that an endless range or generator stops.
+fn collect_elements(iterable: Object, limit: Option<usize>, context: &RefCell<Context>) -> Object {
+    let mut iter = match Iter::new(iterable) {
+        Ok(value)  => value,
+        Err(error) => return error,
+    };
+
+    let mut elements = Vec::new();
+    let mut bytes: usize = 0;
+    while limit.is_none_or(|n| elements.len() < n) {
+        if let Some(error) = context.borrow_mut().grow(bytes.saturating_add(mem::size_of::<Object>())) {
+            return error;
+        }
+        match iter.next() {
+            Some(error @ Object::Error { .. }) => return error,
+            Some(element)                      => {
+                bytes = bytes.saturating_add(mem::size_of::<Object>() + size(&element));
+                elements.push(element);
+            },
+            None                               => break,
+        }
+    }
+
+    Object::Array { elements: elements }
 }
 
 pub fn builtins(name: String) -> Object{
@@ -112,6 +236,12 @@ pub fn builtins(name: String) -> Object{
         "last" => Object::Builtin { function: last },
         "rest" => Object::Builtin { function: rest },
         "push" => Object::Builtin { function: push },
+        "error" => Object::Builtin { function: error },
+        "variant" => Object::Builtin { function: variant },
+        "puts" => Object::Builtin { function: puts },
+        "next" => Object::Builtin { function: next },
+        "take" => Object::Builtin { function: take },
+        "collect" => Object::Builtin { function: collect },
         _     => Object::Null,
     }
 }
@@ -0,0 +1,634 @@
+//! Gradual type checking.
+//!
+//! The checker walks the `Ast` before evaluation and reports operations whose operand
+//! types are known to be wrong. Types come from annotations and from literals; a
+//! binding without an annotation takes the type of its initializer, unless the program
+//! assigns to it somewhere, in which case it is `any`. Anything of type `any` is
+//! accepted everywhere.
+//!
+//! Only a mismatch that involves an annotated type is an error. One between types taken
+//! from literals alone is a warning, which does not stop the program from running, so
+//! unannotated code still runs as before.
+
+use crate::ast::{ Ast, is_spread, is_variadic, parameter_name };
+use crate::generator::{ contains_yield };
+use crate::token::{ Token, TokenKind };
+use crate::types::{ Type };
+use std::collections::{ HashMap, HashSet };
+
+/// Check program and return its type errors as "line:column: message"
+pub fn check_types(program: &Ast) -> Vec<String> {
+    Checker::new().check(program)
+}
+
+struct Binding {
+    typ: Type,
+    annotated: bool,
+    declared: bool,         // typ comes from an annotation, directly or through the initializer
+}
+
+/// Type checker that keeps the bindings of the programs it has checked,
+/// so that the REPL can check each input in the context of the previous ones
+pub struct Checker {
+    scopes: Vec<HashMap<String, Binding>>,
+    type_names: HashSet<String>,    // structs, enums and classes declared anywhere in the program
+    assigned: HashSet<String>,      // names that are the target of an assignment
+    returns: Vec<Type>,             // declared return types of the enclosing functions
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Default for Checker {
+    fn default() -> Checker {
+        Checker::new()
+    }
+}
+
+impl Checker {
+    pub fn new() -> Checker {
+        Checker {
+            scopes: vec![HashMap::new()],
+            type_names: HashSet::new(),
+            assigned: HashSet::new(),
+            returns: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Check program and return its type errors as "line:column: message"
+    pub fn check(&mut self, program: &Ast) -> Vec<String> {
+        self.warnings.clear();
+        self.collect(program);
+        self.infer(program);
+
+        self.errors.drain(..).collect()
+    }
+
+    fn collect(&mut self, node: &Ast) {
+        match node {
+            Ast::StructDeclaration { name, .. } |
+            Ast::EnumDeclaration { name, .. } |
+            Ast::ClassDeclaration { name, .. } => { self.type_names.insert(name.to_string()); },
+            Ast::AssignExpression { target, .. } => {
+                if let Ast::Identifier { ref value, .. } = **target {
+                    self.assigned.insert(value.to_string());
+                }
+            },
+            _ => (),
+        }
+
+        for child in node.children() {
+            self.collect(child);
+        }
+    }
+
+    /// Warnings of the last check, as "line:column: warning: message"
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    fn error(&mut self, token: &Token, msg: String) {
+        self.errors.push(format!("{}: {}", token.location(), msg));
+    }
+
+    /// Report a mismatch as an error if it involves an annotated type, or else as a warning
+    fn report(&mut self, token: &Token, msg: String, declared: bool) {
+        if declared {
+            self.error(token, msg);
+        }
+        else {
+            self.warnings.push(format!("{}: warning: {}", token.location(), msg));
+        }
+    }
+
+    fn bind(&mut self, name: &str, typ: Type, annotated: bool) {
+        let scope = self.scopes.last_mut().unwrap();
+        scope.insert(name.to_string(), Binding { typ: typ, annotated: annotated, declared: annotated });
+    }
+
+    /// Bind name without an annotation to a type inferred from an expression, declared if the
+    /// type of the expression comes from an annotation
+    fn bind_inferred(&mut self, name: &str, typ: Type, declared: bool) {
+        let scope = self.scopes.last_mut().unwrap();
+        scope.insert(name.to_string(), Binding { typ: typ, annotated: false, declared: declared });
+    }
+
+    /// Check whether the type of node comes from an annotation, rather than from literals alone
+    fn declared(&self, node: &Ast) -> bool {
+        match node {
+            Ast::Identifier { value, .. } => match self.lookup(value) {
+                Some(binding) => binding.declared,
+                None          => false,
+            },
+            Ast::FunctionLiteral { parameter_types, return_type, .. } =>
+                parameter_types.iter().chain(Some(return_type)).any(|annotation| Type::from_annotation(annotation).is_known()),
+            _ => node.children().iter().any(|child| self.declared(child)),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn lookup_mut(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+
+    /// Type named by annotation. An annotation with an unknown type name is reported and
+    /// taken as any, so that it is not reported again at every use.
+    fn annotation(&mut self, annotation: &Ast) -> Type {
+        if !self.check_type_names(annotation) {
+            return Type::Any;
+        }
+        Type::from_annotation(annotation)
+    }
+
+    fn check_type_names(&mut self, annotation: &Ast) -> bool {
+        let mut known = true;
+
+        if let Ast::TypeExpression { token, name, arguments } = annotation {
+            match name.as_ref() {
+                "any" | "int" | "string" | "bool" | "null" | "range" | "array" | "fn" => (),
+                _ if self.type_names.contains(name) => (),
+                _ => {
+                    self.error(token, format!("unknown type {}", name));
+                    known = false;
+                },
+            }
+
+            for argument in arguments {
+                known = self.check_type_names(argument) && known;
+            }
+        }
+
+        known
+    }
+
+    fn infer_block(&mut self, statements: &Vec<Box<Ast>>) -> Type {
+        self.scopes.push(HashMap::new());
+        let mut typ = Type::Any;
+        for statement in statements {
+            typ = self.infer(statement);
+        }
+        self.scopes.pop();
+
+        typ
+    }
+
+    /// Check node and return the type of its value
+    fn infer(&mut self, node: &Ast) -> Type {
+        match node {
+            Ast::Program { statements } => {
+                for statement in statements {
+                    self.infer(statement);
+                }
+                Type::Any
+            },
+            Ast::BlockStatement { statements, .. } => self.infer_block(statements),
+            Ast::ExpressionStatement { expression, .. } => self.infer(expression),
+            Ast::IntegerLiteral { .. } => Type::Int,
+            Ast::StringLiteral { .. } => Type::String,
+            Ast::Boolean { .. } => Type::Bool,
+            Ast::NullLiteral { .. } => Type::Null,
+            Ast::Identifier { value, .. } => match self.lookup(value) {
+                Some(binding) => binding.typ.clone(),
+                None          => Type::Any,
+            },
+            Ast::LetStatement { ident, annotation, value, .. } => {
+                let name = ident.to_string();
+                let declared = self.annotation(annotation);
+                let annotated = declared.is_known();
+
+                // a function can call itself by name
+                if let Ast::FunctionLiteral { ref parameters, ref parameter_types, ref return_type, .. } = **value {
+                    let signature = if is_variadic(parameters) { Type::Any } else { Type::Function(parameter_types.iter().map(|p| Type::from_annotation(p)).collect(),
+                                                   Box::new(Type::from_annotation(return_type))) };
+                    let declared = self.declared(value);
+                    self.bind_inferred(&name, signature, declared);
+                }
+
+                let typ = self.infer(value);
+                if !declared.is_consistent(&typ) {
+                    self.error(ident_token(ident), format!("cannot initialize {}: {} with {}",
+                                                           name, declared.to_string(), typ.to_string()));
+                }
+
+                if annotated {
+                    self.bind(&name, declared, true);
+                }
+                else if self.assigned.contains(&name) {
+                    self.bind(&name, Type::Any, false);
+                }
+                else {
+                    let declared = self.declared(value);
+                    self.bind_inferred(&name, typ, declared);
+                }
+                Type::Any
+            },
+            Ast::ReturnStatement { token, return_value } => {
+                let typ = self.infer(return_value);
+                self.check_return(token, &typ);
+                Type::Any
+            },
+            Ast::PrefixExpression { token, operator, right } => {
+                let typ = self.infer(right);
+                match (operator.as_ref(), &typ) {
+                    ("!", _)                        => Type::Bool,
+                    ("-", Type::Int) | ("-", Type::Any) => Type::Int,
+                    _ => {
+                        let declared = self.declared(right);
+                        self.report(token, format!("unknown operator: {}{}", operator, typ.to_string()), declared);
+                        Type::Any
+                    },
+                }
+            },
+            Ast::InfixExpression { token, left, operator, right } => {
+                let types = (self.infer(left), self.infer(right));
+                self.infer_infix(token, operator, types, (left, right))
+            },
+            Ast::IfExpression { condition, consequence, alternative, .. } => {
+                self.infer(condition);
+                let consequence = self.infer(consequence);
+                let alternative = match **alternative {
+                    Ast::Expression { .. } => Type::Null,
+                    _                      => self.infer(alternative),
+                };
+
+                if consequence == alternative { consequence } else { Type::Any }
+            },
+            Ast::FunctionLiteral { parameters, parameter_types, return_type, body, .. } => {
+                let mut types = Vec::new();
+                self.scopes.push(HashMap::new());
+                for (parameter, annotation) in parameters.iter().zip(parameter_types) {
+                    let mut typ = self.annotation(annotation);
+                    if is_spread(parameter) && !typ.is_known() {
+                        typ = Type::Array(Box::new(Type::Any));
+                    }
+                    self.bind(&parameter_name(parameter), typ.clone(), typ.is_known());
+                    types.push(typ);
+                }
+
+                // a generator function returns a generator, whatever its body returns
+                let generator = contains_yield(body);
+                let mut result = self.annotation(return_type);
+                if generator {
+                    result = Type::Any;
+                }
+
+                self.returns.push(result.clone());
+                let value = self.infer(body);
+                if let (false, Ast::BlockStatement { ref token, ref statements }) = (generator, &**body) {
+                    // the value of the last expression is returned implicitly
+                    if let Some(Ast::ExpressionStatement { .. }) = statements.last().map(|s| &**s) {
+                        self.check_return(token, &value);
+                    }
+                }
+                self.returns.pop();
+                self.scopes.pop();
+
+                // the number of arguments of a variadic function is not fixed
+                if is_variadic(parameters) {
+                    return Type::Any;
+                }
+                Type::Function(types, Box::new(result))
+            },
+            Ast::CallExpression { token, function: callee, arguments } => {
+                let function = self.infer(callee);
+                let spread = arguments.iter().any(|a| is_spread(a));
+                let arguments: Vec<Type> = arguments.iter().map(|a| self.infer(a)).collect();
+
+                match function {
+                    Type::Function(_, result) if spread => *result,
+                    Type::Function(parameters, result) => {
+                        let declared = self.declared(callee);
+                        if parameters.len() != arguments.len() {
+                            self.report(token, format!("wrong number of arguments. got={}, want={}",
+                                                       arguments.len(), parameters.len()), declared);
+                        }
+                        for (i, (parameter, argument)) in parameters.iter().zip(&arguments).enumerate() {
+                            if !parameter.is_consistent(argument) {
+                                self.report(token, format!("argument {} must be {}, got {}",
+                                                           i + 1, parameter.to_string(), argument.to_string()), declared);
+                            }
+                        }
+                        *result
+                    },
+                    Type::Any => Type::Any,
+                    _ => {
+                        let declared = self.declared(callee);
+                        self.report(token, format!("not a function: {}", function.to_string()), declared);
+                        Type::Any
+                    },
+                }
+            },
+            Ast::ArrayLiteral { elements, .. } => {
+                let elements: Vec<Type> = elements.iter().map(|e| match **e {
+                    Ast::SpreadExpression { ref value, .. } => element_type(self.infer(value)),
+                    _                                       => self.infer(e),
+                }).collect();
+                match elements.first() {
+                    Some(first) if elements.iter().all(|e| e == first) => Type::Array(Box::new(first.clone())),
+                    _                                                  => Type::Array(Box::new(Type::Any)),
+                }
+            },
+            Ast::IndexExpression { token, left: value, index: position } => {
+                let left = self.infer(value);
+                let index = self.infer(position);
+                if !index.is_consistent(&Type::Int) {
+                    let declared = self.declared(position);
+                    self.report(token, format!("index must be int, got {}", index.to_string()), declared);
+                }
+
+                match left {
+                    Type::Array(element) => *element,
+                    Type::String         => Type::String,
+                    Type::Range          => Type::Int,
+                    Type::Any            => Type::Any,
+                    // null, and the rest of the chain is skipped
+                    Type::Null if token.kind == TokenKind::QuestionLbracket => Type::Any,
+                    _ => {
+                        let declared = self.declared(value);
+                        self.report(token, format!("index operator not supported: {}", left.to_string()), declared);
+                        Type::Any
+                    },
+                }
+            },
+            Ast::SliceExpression { left, start, end, .. } => {
+                for bound in [start, end] {
+                    if let Ast::Expression { .. } = **bound {
+                        continue;
+                    }
+                    self.infer(bound);
+                }
+
+                match self.infer(left) {
+                    typ @ Type::Array(_) | typ @ Type::String => typ,
+                    _                                         => Type::Any,
+                }
+            },
+            Ast::AssignExpression { target, value, .. } => {
+                let typ = self.infer(value);
+                match **target {
+                    Ast::Identifier { ref token, ref value } => {
+                        let declared = match self.lookup_mut(value) {
+                            Some(binding) if binding.annotated => binding.typ.clone(),
+                            Some(binding) => {
+                                // an unannotated binding holds whatever is assigned to it
+                                if !binding.typ.is_consistent(&typ) {
+                                    binding.typ = Type::Any;
+                                }
+                                Type::Any
+                            },
+                            None => Type::Any,
+                        };
+                        if !declared.is_consistent(&typ) {
+                            self.error(token, format!("cannot assign {} to {}: {}",
+                                                      typ.to_string(), value, declared.to_string()));
+                        }
+                    },
+                    _ => { self.infer(target); },
+                }
+                typ
+            },
+            Ast::MemberExpression { object, .. } => {
+                self.infer(object);
+                Type::Any
+            },
+            Ast::ThrowStatement { value, .. } |
+            Ast::SpreadExpression { value, .. } => self.infer(value),
+            Ast::YieldStatement { value, .. } => {
+                self.infer(value);
+                Type::Any
+            },
+            Ast::ForExpression { variable, iterable, body, .. } => {
+                let element = element_type(self.infer(iterable));
+
+                self.scopes.push(HashMap::new());
+                let declared = self.declared(iterable);
+                self.bind_inferred(&variable.to_string(), element, declared);
+                self.infer(body);
+                self.scopes.pop();
+                Type::Null
+            },
+            Ast::RangeExpression { token, start, end, step } => {
+                for bound in [start, end, step] {
+                    if let Ast::Expression { .. } = **bound {
+                        continue;
+                    }
+                    let typ = self.infer(bound);
+                    if !typ.is_consistent(&Type::Int) {
+                        let declared = self.declared(bound);
+                        self.report(token, format!("range bound must be int, got {}", typ.to_string()), declared);
+                    }
+                }
+                Type::Range
+            },
+            Ast::ListComprehension { element, clauses, .. } => {
+                self.scopes.push(HashMap::new());
+                for clause in clauses {
+                    self.infer(clause);
+                }
+                let element = self.infer(element);
+                self.scopes.pop();
+                Type::Array(Box::new(element))
+            },
+            Ast::ComprehensionClause { token, variable, value } => {
+                let typ = self.infer(value);
+                if token.kind == TokenKind::For {
+                    let declared = self.declared(value);
+                    self.bind_inferred(&variable.to_string(), element_type(typ), declared);
+                }
+                Type::Null
+            },
+            Ast::TryExpression { block, parameter, catch, finally, .. } => {
+                self.infer(block);
+                if let Ast::BlockStatement { ref statements, .. } = **catch {
+                    self.scopes.push(HashMap::new());
+                    self.bind(&parameter.to_string(), Type::Any, false);
+                    self.infer_block(statements);
+                    self.scopes.pop();
+                }
+                self.infer(finally);
+                Type::Any
+            },
+            Ast::StructDeclaration { name, fields, .. } => {
+                let constructor = Type::Function(fields.iter().map(|_| Type::Any).collect(),
+                                                 Box::new(Type::Named(name.to_string())));
+                self.bind(&name.to_string(), constructor, false);
+                Type::Any
+            },
+            Ast::EnumDeclaration { name, .. } => {
+                self.bind(&name.to_string(), Type::Any, false);
+                Type::Any
+            },
+            Ast::ClassDeclaration { name, superclass, methods, .. } => {
+                self.infer(superclass);
+                self.bind(&name.to_string(), Type::Any, false);
+
+                self.scopes.push(HashMap::new());
+                self.bind("self", Type::Named(name.to_string()), false);
+                self.bind("super", Type::Any, false);
+                for method in methods {
+                    self.infer(method);
+                }
+                self.scopes.pop();
+                Type::Any
+            },
+            Ast::MethodDefinition { function, .. } => {
+                self.infer(function);
+                Type::Any
+            },
+            Ast::ImportStatement { alias, .. } => {
+                self.bind(&alias.to_string(), Type::Any, false);
+                Type::Any
+            },
+            Ast::ImportExpression { path, .. } => {
+                self.infer(path);
+                Type::Any
+            },
+            Ast::ExportStatement { statement, .. } => self.infer(statement),
+            Ast::InfixDeclaration { value, .. } => {
+                self.infer(value);
+                Type::Any
+            },
+            Ast::EnumVariant { .. } |
+            Ast::TypeExpression { .. } |
+            Ast::Expression { .. } => Type::Any,
+        }
+    }
+
+    /// Type of `left operator right`, where operands are the nodes the types were inferred from
+    fn infer_infix(&mut self, token: &Token, operator: &str, (left, right): (Type, Type), operands: (&Ast, &Ast)) -> Type {
+        // user-defined operators may take any operands
+        if token.kind == TokenKind::Operator {
+            return Type::Any;
+        }
+
+        if operator == "in" {
+            return Type::Bool;
+        }
+
+        if (operator == "==" || operator == "!=") && (left == Type::Null || right == Type::Null) {
+            return Type::Bool;
+        }
+
+        // `a ?? b` is b when a is null
+        if operator == "??" {
+            return match left {
+                Type::Null                    => right,
+                _ if left == right            => left,
+                _                             => Type::Any,
+            };
+        }
+
+        if !left.is_known() || !right.is_known() {
+            return match operator {
+                "<" | ">" | "==" | "!=" => Type::Bool,
+                "-" | "*" | "/"         => Type::Int,
+                _                       => Type::Any,
+            };
+        }
+
+        let declared = |checker: &Checker| checker.declared(operands.0) || checker.declared(operands.1);
+        if left != right {
+            let declared = declared(self);
+            self.report(token, format!("type mismatch: {} {} {}", left.to_string(), operator, right.to_string()), declared);
+            return Type::Any;
+        }
+
+        match (&left, operator) {
+            (Type::Int, "+") | (Type::Int, "-") | (Type::Int, "*") | (Type::Int, "/") => Type::Int,
+            (Type::Int, "<") | (Type::Int, ">") => Type::Bool,
+            (Type::String, "+")  => Type::String,
+            (_, "==") | (_, "!=") => Type::Bool,
+            _ => {
+                let declared = declared(self);
+                self.report(token, format!("unknown operator: {} {} {}", left.to_string(), operator, right.to_string()), declared);
+                Type::Any
+            },
+        }
+    }
+
+    fn check_return(&mut self, token: &Token, typ: &Type) {
+        let expected = match self.returns.last() {
+            Some(value) => value.clone(),
+            None        => return,
+        };
+
+        if !expected.is_consistent(typ) {
+            self.error(token, format!("cannot return {} from function returning {}",
+                                      typ.to_string(), expected.to_string()));
+        }
+    }
+}
+
+/// Type of the loop variable iterating over a value of type iterable
+fn element_type(iterable: Type) -> Type {
+    match iterable {
+        Type::Array(element) => *element,
+        Type::String         => Type::String,
+        Type::Range          => Type::Int,
+        _                    => Type::Any,
+    }
+}
+
+fn ident_token(ident: &Ast) -> &Token {
+    match ident {
+        Ast::Identifier { token, .. } => token,
+        _                             => panic!("let binds an identifier"),
+    }
+}
+
+#[test]
+fn test_check_types() {
+    use crate::lexier::{ Lexier };
+    use crate::parser::{ Parser };
+
+    let tests: Vec<(&str, Vec<&str>, Vec<&str>)> = vec![
+        ("let x = 1; let y = \"a\"; x + y", vec![], vec!["1:27: warning: type mismatch: int + string"]),
+        ("let x: int = 1; let y = \"a\"; x + y", vec!["1:32: type mismatch: int + string"], vec![]),
+        ("let x: int = 1; let y = x * 2; y + \"a\"", vec!["1:34: type mismatch: int + string"], vec![]),
+        ("let x: int = \"a\";", vec!["1:5: cannot initialize x: int with string"], vec![]),
+        ("let x: int = 1;\nx = true;", vec!["2:1: cannot assign bool to x: int"], vec![]),
+        ("let f = fn(a: int, b: string) -> bool { a == 1 }; f(1, 2); f(1)",
+         vec!["1:52: argument 2 must be string, got int", "1:61: wrong number of arguments. got=1, want=2"], vec![]),
+        ("let f = fn(a, b) { a }; f(1)", vec![], vec!["1:26: warning: wrong number of arguments. got=1, want=2"]),
+        ("fn(n: int) -> string { n }", vec!["1:22: cannot return int from function returning string"], vec![]),
+        ("fn(n: int) -> int { if (n > 0) { return \"a\"; } n }", vec!["1:34: cannot return string from function returning int"], vec![]),
+        ("let p: Point = 1; struct Q {} let q: Q = Q(); let r: [Q] = [q]", vec!["1:8: unknown type Point"], vec![]),
+        ("struct P { x } let p: P = P(1); let n: int = p;", vec!["1:37: cannot initialize n: int with P"], vec![]),
+        ("let xs = [1, 2]; xs[\"a\"]; -xs[0]; -\"a\"", vec![],
+         vec!["1:20: warning: index must be int, got string", "1:35: warning: unknown operator: -string"]),
+        ("let i: string = \"a\"; [1][i]; -i; i(1)",
+         vec!["1:25: index must be int, got string", "1:30: unknown operator: -string", "1:35: not a function: string"], vec![]),
+        ("true + false", vec![], vec!["1:6: warning: unknown operator: bool + bool"]),
+        ("let f: fn(int) -> int = fn(s: string) { s };", vec!["1:5: cannot initialize f: fn(int) -> int with fn(string) -> any"], vec![]),
+        ("let xs: [string] = [n * 2 for n in [1, 2] if n > 1];", vec!["1:5: cannot initialize xs: [string] with [int]"], vec![]),
+        ("let r: range = 0..\"a\"; let n: string = r[0]; for (i in 1..=3) { i + \"b\" }; 1 in r + 1",
+         vec!["1:28: cannot initialize n: string with int", "1:83: type mismatch: range + int"],
+         vec!["1:17: warning: range bound must be int, got string", "1:67: warning: type mismatch: int + string"]),
+        ("let n: int = null ?? 1; let s: string = 1 ?? 2; let b: bool = n == null; let m: null = null?[0]; null?[0][1];",
+         vec!["1:29: cannot initialize s: string with int"], vec![]),
+        ("let s: string = [...[1, 2]][0]; let f = fn(...r: [int]) { r }; f(1, \"a\"); let add = fn(a: int, b: int) { a + b }; add(...[1]);",
+         vec!["1:5: cannot initialize s: string with int"], vec![]),
+        // unannotated code is accepted
+        ("let x = 1; x = \"a\"; x + \"b\"", vec![], vec![]),
+        ("let x = 1; fn() { x = \"a\" }; x + 1", vec![], vec![]),
+        ("let f = fn(a, b) { a + b }; f(1, \"a\"); f(\"a\", \"b\")", vec![], vec![]),
+        ("let add = fn(a: int, b: int) -> int { a + b }; add(1, len(\"ab\"))", vec![], vec![]),
+        ("infix \"<>\" sum = fn(a, b) { a }; 1 <> \"a\"", vec![], vec![]),
+        ("let f = fn(n: int) -> int { if (n < 2) { 1 } else { n * f(n - 1) } }", vec![], vec![]),
+        ("let r = try { 1 + \"a\" } catch (e) { \"caught\" }; r", vec![], vec!["1:17: warning: type mismatch: int + string"]),
+    ];
+
+    for test in tests.iter() {
+        let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+        let program = parser.parse_program().unwrap();
+        parser.check_parser_errors();
+
+        let mut checker = Checker::new();
+        assert_eq!(checker.check(&program), test.1, "{}", test.0);
+        assert_eq!(checker.warnings(), &test.2[..], "{}", test.0);
+    }
+}
@@ -0,0 +1,264 @@
+//! State of a running program shared by all of its scopes, including those of imported modules.
+//!
+//! The context counts nested function calls so that deep recursion ends in a catchable
+//! `RecursionError` instead of overflowing the Rust stack. Every call takes stack space in
+//! the host, so the limit must fit the stack of the thread that evaluates: the CLI and the
+//! REPL evaluate on a thread with a stack of `STACK_SIZE` bytes, which holds
+//! `DEFAULT_MAX_CALL_DEPTH` calls even in a debug build. A thread gets 2 MB by default, too
+//! little for that many, so embedders parse and evaluate programs inside `with_stack`.
+//!
+//! For untrusted programs the context also enforces `Limits`: a number of evaluation steps,
+//! a total size of the strings and containers created, a wall-clock deadline and the files that
+//! `import` may read. Once a limit is exceeded every further step fails with the same error,
+//! so a program cannot catch it and carry on.
+
+use crate::object::{ Object, new_error_with_kind };
+use std::mem;
+use std::path::{ PathBuf };
+use std::thread;
+use std::time::{ Instant };
+
+/// Calls that may be nested before a call fails
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Stack size of the thread the CLI and the REPL evaluate on
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Run f on a new thread with a stack of `STACK_SIZE` bytes and return its result, or the
+/// panic of the thread. Environments and objects cannot leave the thread, so f creates them.
+pub fn with_stack<F, T>(f: F) -> thread::Result<T>
+    where F: FnOnce() -> T + Send + 'static, T: Send + 'static
+{
+    match thread::Builder::new().stack_size(STACK_SIZE).spawn(f) {
+        Ok(handle) => handle.join(),
+        Err(error) => Err(Box::new(error)),
+    }
+}
+
+/// Steps between two checks of the deadline
+const DEADLINE_INTERVAL: u64 = 64;
+
+/// Budgets of an evaluation, None is unlimited, and the files it may import
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub max_steps: Option<u64>,         // nodes evaluated, function calls and elements collected into arrays
+    pub max_allocation: Option<usize>,  // bytes of the strings, arrays and structs created in total, with their contents
+    pub deadline: Option<Instant>,
+    pub imports: Imports,
+}
+
+/// Files that `import` may read
+#[derive(Debug, Clone, Default)]
+pub enum Imports {
+    #[default]
+    Any,
+    Within(PathBuf),        // files inside the directory
+    Disabled,
+}
+
+#[derive(Debug)]
+pub struct Context {
+    depth: usize,           // function calls being evaluated
+    max_depth: usize,
+    limits: Limits,
+    steps: u64,
+    allocated: usize,
+    exhausted: Option<Object>,  // error of the limit that has been exceeded
+}
+
+impl Context {
+    pub fn new() -> Context {
+        Context { depth: 0, max_depth: DEFAULT_MAX_CALL_DEPTH, limits: Limits::default(),
+                  steps: 0, allocated: 0, exhausted: None }
+    }
+
+    pub fn set_max_call_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Start a call. Returns false, without starting it, if it would exceed the limit.
+    pub fn enter_call(&mut self) -> bool {
+        if self.depth >= self.max_depth {
+            return false;
+        }
+
+        self.depth += 1;
+        true
+    }
+
+    pub fn exit_call(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Replace the limits, and start counting steps and allocation from zero
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.steps = 0;
+        self.allocated = 0;
+        self.exhausted = None;
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    pub fn imports(&self) -> Imports {
+        self.limits.imports.clone()
+    }
+
+    /// Count an evaluation step. Returns the error to stop with if a limit is exceeded.
+    pub fn step(&mut self) -> Option<Object> {
+        self.take_steps(1)
+    }
+
+    /// Count the size of value if it is a string or a container, with the strings and containers
+    /// copied into it
+    pub fn allocate(&mut self, value: &Object) -> Option<Object> {
+        if let Some(ref error) = self.exhausted {
+            return Some(error.clone());
+        }
+
+        self.allocated = self.allocated.saturating_add(size(value));
+        match self.limits.max_allocation {
+            Some(max) if self.allocated > max =>
+                self.exhaust("MemoryLimitError", format!("allocation limit exceeded: {} bytes", max)),
+            _ => None,
+        }
+    }
+
+    /// Check that an array of elements built from a lazy range fits the limits before it is built,
+    /// counting a step for each element
+    pub fn reserve(&mut self, elements: i64) -> Option<Object> {
+        if let Some(error) = self.take_steps(elements.max(0) as u64) {
+            return Some(error);
+        }
+
+        self.check_array(elements.max(0) as usize)
+    }
+
+    /// Count a step for an element collected into an array, which then takes bytes with its
+    /// elements, and check that the array still fits the limits. The finished array is allocated as usual.
+    pub fn grow(&mut self, bytes: usize) -> Option<Object> {
+        if let Some(error) = self.take_steps(1) {
+            return Some(error);
+        }
+
+        self.check_size(bytes)
+    }
+
+    fn check_array(&mut self, elements: usize) -> Option<Object> {
+        self.check_size(elements.saturating_mul(mem::size_of::<Object>()))
+    }
+
+    /// Check that an object of bytes, still being built, fits the allocation limit
+    pub fn check_size(&mut self, bytes: usize) -> Option<Object> {
+        if let Some(ref error) = self.exhausted {
+            return Some(error.clone());
+        }
+
+        match self.limits.max_allocation {
+            Some(max) if self.allocated.saturating_add(bytes) > max =>
+                self.exhaust("MemoryLimitError", format!("allocation limit exceeded: {} bytes", max)),
+            _ => None,
+        }
+    }
+
+    fn take_steps(&mut self, steps: u64) -> Option<Object> {
+        if let Some(ref error) = self.exhausted {
+            return Some(error.clone());
+        }
+
+        let before = self.steps;
+        self.steps = self.steps.saturating_add(steps);
+        if let Some(max) = self.limits.max_steps {
+            if self.steps > max {
+                return self.exhaust("StepLimitError", format!("step limit exceeded: {} steps", max));
+            }
+        }
+
+        // reading the clock on every step would slow evaluation down
+        if let Some(deadline) = self.limits.deadline {
+            if before / DEADLINE_INTERVAL != self.steps / DEADLINE_INTERVAL && Instant::now() >= deadline {
+                return self.exhaust("TimeoutError", "deadline exceeded".to_string());
+            }
+        }
+
+        None
+    }
+
+    fn exhaust(&mut self, kind: &str, msg: String) -> Option<Object> {
+        let error = new_error_with_kind(kind, msg);
+        self.exhausted = Some(error.clone());
+        Some(error)
+    }
+}
+
+impl Default for Context {
+    fn default() -> Context {
+        Context::new()
+    }
+}
+
+/// Bytes counted for a string or a container, including the strings and containers in it, which
+/// are copies. The fields of an instance are shared between its copies, so they are not counted.
+pub fn size(value: &Object) -> usize {
+    let mut bytes: usize = 0;
+    let mut pending = vec![value];
+    while let Some(value) = pending.pop() {
+        match value {
+            Object::String { value }   => bytes = bytes.saturating_add(value.len()),
+            Object::Array { elements } => {
+                bytes = bytes.saturating_add(elements.len() * mem::size_of::<Object>());
+                pending.extend(elements.iter());
+            },
+            Object::Struct { fields, .. } | Object::Variant { fields, .. } => {
+                bytes = bytes.saturating_add(fields.len() * mem::size_of::<(String, Object)>());
+                pending.extend(fields.iter().map(|(_, field)| field));
+            },
+            _ => (),
+        }
+    }
+    bytes
+}
+
+#[test]
+fn test_limits() {
+    let mut context = Context::new();
+    assert!(context.step().is_none());
+    assert!(context.allocate(&Object::String { value: "a".repeat(1000) }).is_none());
+
+    context.set_limits(Limits { max_steps: Some(3), ..Limits::default() });
+    assert_eq!(context.allocated(), 0);
+    for _ in 0..3 {
+        assert!(context.step().is_none());
+    }
+    match context.step() {
+        Some(Object::Error { kind, msg, .. }) => assert_eq!((kind.as_ref(), msg.as_ref()), ("StepLimitError", "step limit exceeded: 3 steps")),
+        other => panic!("no error returned. got={:?}", other),
+    }
+    assert!(context.allocate(&Object::Null).is_some(), "an exceeded limit stays exceeded");
+
+    context.set_limits(Limits { max_allocation: Some(10), ..Limits::default() });
+    assert!(context.allocate(&Object::String { value: "abcde".to_string() }).is_none());
+    assert!(context.reserve(1).is_some());
+
+    context.set_limits(Limits { max_allocation: Some(mem::size_of::<Object>() * 2), ..Limits::default() });
+    assert!(context.grow(mem::size_of::<Object>() * 2).is_none());
+    assert!(context.grow(mem::size_of::<Object>() * 3).is_some());
+
+    let nested = Object::Array { elements: vec![Object::String { value: "abc".to_string() }] };
+    let array = Object::Array { elements: vec![nested.clone(), nested] };
+    assert_eq!(size(&array), mem::size_of::<Object>() * 4 + 6);
+
+    context.set_limits(Limits { deadline: Some(Instant::now()), ..Limits::default() });
+    let stopped = (0..DEADLINE_INTERVAL).any(|_| context.step().is_some());
+    assert!(stopped);
+}
@@ -1,42 +1,183 @@
+use crate::context::{ Context };
+use crate::module::{ Loader };
 use crate::object::{ Object };
-use std::collections::HashMap;
+use std::cell::{ RefCell };
+use std::collections::{ HashMap, HashSet };
+use std::fmt;
+use std::path::{ PathBuf };
+use std::rc::{ Rc };
 
-#[derive(Debug, Clone)]
+/// Handle to a scope. Cloning an Env shares the scope instead of copying it, so a closure
+/// sees the bindings made in its enclosing scopes after it was created, including its own name.
+#[derive(Clone)]
 pub struct Env {
+    scope: Rc<RefCell<Scope>>,
+}
+
+struct Scope {
     store: HashMap<String, Object>,
-    outer: Option<Box<Env>>,
+    constants: HashSet<String>,     // names in store bound by `const`
+    outer: Option<Env>,
+    loader: Rc<RefCell<Loader>>,    // shared by every scope of a program
+    context: Rc<RefCell<Context>>,  // shared by every scope of a program
+    file: Option<PathBuf>,          // source file the scope belongs to
 }
 
 impl Env {
-    pub fn new_enclosed_env(outer: Box<Env>) -> Env {
-        let mut env = Env::new();
-        env.outer = Some(outer);
+    pub fn new_enclosed_env(outer: &Env) -> Env {
+        let env = Env::new_import_env(outer, outer.file());
+        env.scope.borrow_mut().outer = Some(outer.clone());
 
         env
     }
-    
+
     pub fn new() -> Env {
-        Env { store: HashMap::new(), outer: None }
+        Env::new_module_env(Rc::new(RefCell::new(Loader::new())), None)
+    }
+
+    /// Create top-level scope for a source file sharing loader with the importing program
+    pub fn new_module_env(loader: Rc<RefCell<Loader>>, file: Option<PathBuf>) -> Env {
+        Env::new_scope(loader, Rc::new(RefCell::new(Context::new())), file)
+    }
+
+    /// Create top-level scope for a file imported from importer, sharing its loader and context
+    pub fn new_import_env(importer: &Env, file: Option<PathBuf>) -> Env {
+        Env::new_scope(importer.loader(), importer.context(), file)
+    }
+
+    fn new_scope(loader: Rc<RefCell<Loader>>, context: Rc<RefCell<Context>>, file: Option<PathBuf>) -> Env {
+        let scope = Scope { store: HashMap::new(), constants: HashSet::new(), outer: None,
+                            loader: loader, context: context, file: file };
+        Env { scope: Rc::new(RefCell::new(scope)) }
+    }
+
+    pub fn loader(&self) -> Rc<RefCell<Loader>> {
+        self.scope.borrow().loader.clone()
     }
 
+    pub fn context(&self) -> Rc<RefCell<Context>> {
+        self.scope.borrow().context.clone()
+    }
+
+    pub fn file(&self) -> Option<PathBuf> {
+        self.scope.borrow().file.clone()
+    }
+
+    /// Value bound to name, or null if it is unbound
     pub fn get(&self, name: String) -> Object {
-        match self.store.get(&name) {
-            Some(value) => (*value).clone(),
+        match self.lookup(&name) {
+            Some(value) => value,
+            None        => Object::Null,
+        }
+    }
+
+    /// Value bound to name in the nearest scope that has it, or None if it is unbound; unlike get,
+    /// tells a binding to null from no binding
+    pub fn lookup(&self, name: &str) -> Option<Object> {
+        let scope = self.scope.borrow();
+        match scope.store.get(name) {
+            Some(value) => Some(value.clone()),
             None        => {
-                match &self.outer {
-                    Some(outer) => match outer.store.get(&name) {
-                        Some(value) => (*value).clone(),
-                        None        => Object::Null,
-                    }
-                    None        => Object::Null,
+                match scope.outer {
+                    Some(ref outer) => outer.lookup(name),
+                    None            => None,
                 }
             },
-        }       
+        }
     }
-    
-    pub fn set(&mut self, name: String, val: Object) -> Object {
-        self.store.insert(name.clone(), val.clone());
+
+    pub fn set(&self, name: String, val: Object) -> Object {
+        self.scope.borrow_mut().store.insert(name, val.clone());
 
         val
     }
+
+    pub fn set_const(&self, name: String, val: Object) -> Object {
+        self.scope.borrow_mut().constants.insert(name.clone());
+        self.set(name, val)
+    }
+
+    /// Drop every binding of this scope. A function holds the scope it is defined in, so a scope
+    /// that binds a function is never freed otherwise; clear a top-level scope once it is done with.
+    pub fn clear(&self) {
+        let mut scope = self.scope.borrow_mut();
+        scope.store.clear();
+        scope.constants.clear();
+    }
+
+    /// Check whether name is bound by `const` in this scope
+    pub fn is_const(&self, name: &str) -> bool {
+        self.scope.borrow().constants.contains(name)
+    }
+
+    /// Update an existing binding in the nearest scope that has it
+    pub fn assign(&self, name: String, val: Object) -> Result<Object, String> {
+        let outer = {
+            let scope = self.scope.borrow();
+            if scope.store.contains_key(&name) {
+                if scope.constants.contains(&name) {
+                    return Err(format!("cannot assign to constant: {}", name));
+                }
+                None
+            }
+            else {
+                match scope.outer {
+                    Some(ref outer) => Some(outer.clone()),
+                    None            => return Err(format!("identifier not found: {}", name)),
+                }
+            }
+        };
+
+        match outer {
+            Some(outer) => outer.assign(name, val),
+            None        => Ok(self.set(name, val)),
+        }
+    }
+}
+
+impl Default for Env {
+    fn default() -> Env {
+        Env::new()
+    }
+}
+
+// scopes can hold closures that refer back to them, so only the names are shown
+impl fmt::Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scope = self.scope.borrow();
+        let mut names: Vec<&String> = scope.store.keys().collect();
+        names.sort();
+        f.debug_struct("Env").field("names", &names).field("outer", &scope.outer).finish()
+    }
+}
+
+#[test]
+fn test_clear() {
+    use crate::eval::{ eval };
+    use crate::lexier::{ Lexier };
+    use crate::parser::{ Parser };
+
+    let program = Parser::new(Lexier::new("let f = fn() { f }; let g = fn(n) { fn() { n } }; g(1);".to_string())).parse_program().unwrap();
+    let mut env = Env::new();
+    eval(&program, &mut env);
+
+    // f and g hold the scope they are bound in
+    let scope = Rc::downgrade(&env.scope);
+    assert!(Rc::strong_count(&env.scope) > 1);
+
+    env.clear();
+    assert_eq!(Rc::strong_count(&env.scope), 1);
+    drop(env);
+    assert!(scope.upgrade().is_none());
+
+    // the functions of an imported module hold its scope, which holds the loader
+    let program = Parser::new(Lexier::new("import \"tests/modules/lib/math.mk\";".to_string())).parse_program().unwrap();
+    let mut env = Env::new();
+    eval(&program, &mut env);
+
+    let loader = Rc::downgrade(&env.loader());
+    env.clear();
+    env.loader().borrow_mut().clear();
+    drop(env);
+    assert!(loader.upgrade().is_none());
 }
@@ -1,24 +1,63 @@
-use crate::ast::{ Ast };
+use crate::ast::{ Ast, is_variadic };
 use crate::builtins::{ builtins };
+use crate::context::{ size };
 use crate::env::*;
+use crate::generator::{ Generator, Iter, contains_yield };
 use crate::lexier::{ Lexier };
-use crate::object::{ Object, new_error };
+use crate::module::{ import };
+use crate::object::{ Object, CallSite, Class, Frame, BUILTIN_KINDS, find_method, new_error, new_error_with_kind };
+use std::cell::{ RefCell };
+use std::mem;
+use std::rc::{ Rc };
 use crate::parser::{ Parser };
-use crate::token::{ TokenKind };
+use crate::range::{ Range };
+use crate::token::{ Token, TokenKind };
 
-pub fn eval(node: Ast, env: &mut Env) -> Option<Object> {
+/// Evaluate node in env. Each node evaluated is a step counted against the limits of env's
+/// context, and the strings and arrays it creates count towards the allocation limit.
+pub fn eval(node: &Ast, env: &mut Env) -> Option<Object> {
+    let context = env.context();
+    if let Some(error) = context.borrow_mut().step() {
+        return Some(error);
+    }
+
+    let allocates = allocates(node);
+    let value = eval_node(node, env);
+    if let (true, Some(ref value)) = (allocates, &value) {
+        if let Some(error) = context.borrow_mut().allocate(value) {
+            return Some(error);
+        }
+    }
+
+    value
+}
+
+/// Check whether evaluating node creates a new string or array. Calls are counted in call,
+/// as only the results of builtins are new.
+fn allocates(node: &Ast) -> bool {
+    match node {
+        Ast::StringLiteral { .. } | Ast::ArrayLiteral { .. } |
+        Ast::ListComprehension { .. } | Ast::SliceExpression { .. } => true,
+        Ast::InfixExpression { token, .. } =>
+            token.kind != TokenKind::QuestionQuestion && token.kind != TokenKind::Operator,
+        _ => false,
+    }
+}
+
+fn eval_node(node: &Ast, env: &mut Env) -> Option<Object> {
     match node {
         Ast::Program { .. } => return eval_program(node, env),
         Ast::ExpressionStatement { expression, .. } => {
-            match eval(*expression, env) {
+            match eval(expression, env) {
                 Some(value) => return Some(value),
                 None        => return None,
             }
         },
-        Ast::IntegerLiteral { value, .. }  => return Some(Object::Integer{value: value}),
-        Ast::Boolean { value, .. }      => return Some(Object::Boolean{value: value}),
+        Ast::IntegerLiteral { value, .. }  => return Some(Object::Integer{value: *value}),
+        Ast::Boolean { value, .. }      => return Some(Object::Boolean{value: *value}),
+        Ast::NullLiteral { .. }         => return Some(Object::Null),
         Ast::PrefixExpression { operator, right, .. } => {
-            let right = match eval(*right, env){
+            let right = match eval(right, env){
                 Some(value) => value,
                 None        => return Some(new_error("prefix expression has no right hand side.".to_string())),
             };
@@ -32,8 +71,8 @@ pub fn eval(node: Ast, env: &mut Env) -> Option<Object> {
                 None        => return None,
             }
         },
-        Ast::InfixExpression { left, operator, right, .. } => {
-            let left = match eval(*left, env){
+        Ast::InfixExpression { token, left, operator, right } => {
+            let left = match eval(left, env){
                 Some(value) => value,
                 None        => return Some(new_error("infix expression has no left hand side.".to_string())),
             };
@@ -41,8 +80,16 @@ pub fn eval(node: Ast, env: &mut Env) -> Option<Object> {
             if is_error(&left) {
                 return Some(left);
             }
+
+            // `left ?? right` evaluates right only if left is null
+            if token.kind == TokenKind::QuestionQuestion {
+                return match left {
+                    Object::Null => eval(right, env),
+                    _            => Some(left),
+                };
+            }
             
-            let right = match eval(*right, env){
+            let right = match eval(right, env){
                 Some(value) => value,
                 None        => return Some(new_error("infix expression has no right hand side.".to_string())),
             };
@@ -51,12 +98,152 @@ pub fn eval(node: Ast, env: &mut Env) -> Option<Object> {
                 return Some(right);
             }
             
+            if token.kind == TokenKind::Operator {
+                let site = call_site(token, env);
+                return Some(eval_user_infix_expression(operator, left, right, &site, env));
+            }
+
             return Some(eval_infix_expression(operator, left, right));
         },
-        Ast::BlockStatement { .. } => return eval_block_statement(node, env),
-        Ast::IfExpression { .. } => return eval_if_expression(node, env),
+        Ast::InfixDeclaration { operator, value, .. } => {
+            let mut val = match eval(value, env){
+                Some(value) => value,
+                None        => Object::Null,
+            };
+            name_function(&mut val, operator);
+
+            if is_error(&val) {
+                return Some(val);
+            }
+
+            match val {
+                Object::Function { .. } | Object::Builtin { .. } => {
+                    env.set(operator.clone(), val);
+                    return Some(Object::Null);
+                },
+                _ => return Some(new_error_with_kind("TypeError", format!("operator {} must be bound to a function. got={}", operator, val.kind()))),
+            }
+        },
+        Ast::BlockStatement { .. } => return eval_block_statement(node, env, false),
+        Ast::IfExpression { .. } => return eval_if_expression(node, env, false),
+        Ast::TryExpression { .. } => return eval_try_expression(node, env),
+        Ast::ForExpression { .. } => return eval_for_expression(node, env),
+        Ast::ListComprehension { .. } => return eval_list_comprehension(node, env),
+        Ast::RangeExpression { .. } => return eval_range_expression(node, env),
+        Ast::YieldStatement { .. } => return Some(new_error_with_kind("RuntimeError", "yield outside of a generator".to_string())),
+        Ast::SpreadExpression { .. } => return Some(new_error_with_kind("RuntimeError", "spread is only allowed in array literals and call arguments".to_string())),
+        Ast::EnumDeclaration { name, variants, .. } => {
+            if let Ast::Identifier { ref value, .. } = **name {
+                if let Some(error) = check_type_name(value, env) {
+                    return Some(error);
+                }
+
+                let mut objects = Vec::new();
+                for variant in variants {
+                    if let Ast::EnumVariant { ref name, ref fields, .. } = **variant {
+                        let object = if fields.len() == 0 {
+                            Object::Variant { enum_name: value.clone(), name: name.clone(), fields: Vec::new() }
+                        }
+                        else {
+                            let fields = fields.iter().map(|f| f.to_string()).collect();
+                            Object::VariantType { enum_name: value.clone(), name: name.clone(), fields: fields }
+                        };
+                        objects.push((name.clone(), object));
+                    }
+                }
+                env.set(value.clone(), Object::EnumType { name: value.clone(), variants: objects });
+            }
+
+            return Some(Object::Null);
+        },
+        Ast::ImportStatement { path, alias, .. } => {
+            let name = alias.to_string();
+            if env.is_const(&name) {
+                return Some(new_error_with_kind("AssignmentError", format!("cannot redeclare constant: {}", name)));
+            }
+
+            let module = import(&path.to_string(), env);
+            if is_error(&module) {
+                return Some(module);
+            }
+            env.set(name, module);
+
+            return Some(Object::Null);
+        },
+        Ast::ImportExpression { path, .. } => {
+            let path = match eval(path, env) {
+                Some(value) => value,
+                None        => Object::Null,
+            };
+
+            match path {
+                Object::String { value } => return Some(import(&value, env)),
+                Object::Error { .. }     => return Some(path),
+                _ => return Some(new_error_with_kind("TypeError", format!("import path must be String. got={}", path.kind()))),
+            }
+        },
+        Ast::ExportStatement { statement, .. } => return eval(statement, env),
+        Ast::ClassDeclaration { name, superclass, methods, .. } => {
+            if let Ast::Identifier { ref value, .. } = **name {
+                if let Some(error) = check_type_name(value, env) {
+                    return Some(error);
+                }
+
+                let superclass = match **superclass {
+                    Ast::Identifier { value: ref parent, .. } => match env.get(parent.to_string()) {
+                        Object::Class { class } => Some(class),
+                        Object::Null            => return Some(new_error_with_kind("NameError", format!("identifier not found: {}", parent))),
+                        other                   => return Some(new_error_with_kind("TypeError", format!("superclass must be a Class. got={}", other.kind()))),
+                    },
+                    _ => None,
+                };
+
+                let mut functions = Vec::new();
+                for method in methods {
+                    if let Ast::MethodDefinition { ref name, ref function, .. } = **method {
+                        if let Ast::FunctionLiteral { ref parameters, ref body, .. } = **function {
+                            let method = Some(format!("{}.{}", value, name));
+                            functions.push((name.clone(), Object::Function { parameters: Rc::new(parameters.clone()), body: body.clone(),
+                                                                             generator: contains_yield(body), env: env.clone(), name: method }));
+                        }
+                    }
+                }
+
+                let class = Class { name: value.clone(), superclass: superclass, methods: functions };
+                env.set(value.clone(), Object::Class { class: Rc::new(class) });
+            }
+
+            return Some(Object::Null);
+        },
+        Ast::StructDeclaration { name, fields, .. } => {
+            if let Ast::Identifier { ref value, .. } = **name {
+                if let Some(error) = check_type_name(value, env) {
+                    return Some(error);
+                }
+                let fields = fields.iter().map(|f| f.to_string()).collect();
+                env.set(value.clone(), Object::StructType { name: value.clone(), fields: fields });
+            }
+
+            return Some(Object::Null);
+        },
+        Ast::ThrowStatement { value, .. } => {
+            let val = match eval(value, env){
+                Some(value) => value,
+                None        => Object::Null,
+            };
+
+            if is_error(&val) {
+                return Some(val);
+            }
+
+            match val {
+                Object::Exception { kind, msg } => return Some(new_error_with_kind(&kind, msg)),
+                Object::String { value }        => return Some(new_error_with_kind("Error", value)),
+                _                               => return Some(new_error_with_kind("Error", val.inspect())),
+            }
+        },
         Ast::ReturnStatement { return_value, .. } => {
-            let val = match eval(*return_value, env){
+            let val = match eval_tail(return_value, env){
                 Some(value) => Box::new(value),
                 None        => Box::new(Object::Null),
             };
@@ -67,87 +254,91 @@ pub fn eval(node: Ast, env: &mut Env) -> Option<Object> {
             
             return Some(Object::ReturnValue { value: val });
         },
-        Ast::LetStatement { ident, value, .. } => {
-            let val = match eval(*value, env){
+        Ast::LetStatement { token, ident, value, .. } => {
+            let mut val = match eval(value, env){
                 Some(value) => value,
                 None        => Object::Null,
             };
+            name_function(&mut val, &ident.to_string());
 
             if is_error(&val) {
                 return Some(val);
             }
-            if let Ast::Identifier { value, ..} = *ident {
-                return Some(env.set(value, val));
+            if let Ast::Identifier { ref value, ..} = **ident {
+                if env.is_const(value) {
+                    return Some(new_error_with_kind("AssignmentError", format!("cannot redeclare constant: {}", value)));
+                }
+                if token.kind == TokenKind::Const {
+                    return Some(env.set_const(value.clone(), val));
+                }
+                return Some(env.set(value.clone(), val));
             }
             
             return None;
 
         },
-        Ast::Identifier { value, .. } => return eval_identifier(value, env),
-        Ast::FunctionLiteral { parameters, body, .. } => {
-            return Some(Object::Function{
-                parameters: parameters,
-                body: body,
-                env: Box::new(env.clone())
-            });
-        },
-        Ast::CallExpression { function, arguments, ..} => {
-            let func = match eval(*function, env) {
+        Ast::AssignExpression { target, value, .. } => {
+            let val = match eval(value, env){
                 Some(value) => value,
                 None        => Object::Null,
             };
 
-            if is_error(&func) {
-                return Some(func);
+            if is_error(&val) {
+                return Some(val);
+            }
+            if let Ast::Identifier { ref value, ..} = **target {
+                match env.assign(value.clone(), val) {
+                    Ok(value) => return Some(value),
+                    Err(msg)  => return Some(new_error_with_kind("AssignmentError", msg)),
+                }
             }
+            if let Ast::MemberExpression { ref object, ref member, .. } = **target {
+                let obj = match eval(object, env) {
+                    Some(value) => value,
+                    None        => Object::Null,
+                };
 
-            let args = eval_expressions(arguments, env);
+                if is_error(&obj) {
+                    return Some(obj);
+                }
 
-            if args.len() == 1 && is_error(&args[0]) {
-                return Some(args[0].clone());
+                return Some(eval_member_assignment(obj, member.to_string(), val));
             }
 
-            Some(apply_function(func, args))
+            return None;
+        },
+        Ast::Identifier { value, .. } => return eval_identifier(value, env),
+        Ast::FunctionLiteral { parameters, body, .. } => {
+            return Some(Object::Function{
+                parameters: Rc::new(parameters.clone()),
+                body: body.clone(),
+                generator: contains_yield(body),
+                env: env.clone(),
+                name: None,
+            });
         },
-        Ast::StringLiteral { value, .. } => return Some(Object::String { value: value }),
+        Ast::CallExpression { .. } => return Some(eval_call_expression(node, env, false).unwrap_or(Object::Null)),
+        Ast::StringLiteral { value, .. } => return Some(Object::String { value: value.clone() }),
         Ast::ArrayLiteral { elements, token } => {
-            let elems = eval_expressions(elements, env);
+            let elems = eval_expressions(elements, env, true);
             if elems.len() == 1 && is_error(&elems[0]) {
                 return Some(elems[0].clone());
             }
 
             return Some(Object::Array { elements: elems})
         },
-        Ast::IndexExpression { left, index, .. } => {
-            let l = match eval(*left, env) {
-                Some(value) => value,
-                None => Object::Null,
-            };
-
-            if is_error(&l) {
-                return Some(l);
-            }
-
-            let i = match eval(*index, env) {
-                Some(value) => value,
-                None => Object::Null,
-            };
-
-            if is_error(&i) {
-                return Some(i);
-            }
-
-            return eval_index_expression(l, i)
+        Ast::IndexExpression { .. } | Ast::MemberExpression { .. } | Ast::SliceExpression { .. } => {
+            return Some(eval_chain(node, env).unwrap_or(Object::Null));
         },
         _ => return None,
     }
 }
 
-fn eval_statements(statements: Vec<Box<Ast>>, env: &mut Env) -> Option<Object> {
+fn eval_statements(statements: &[Box<Ast>], env: &mut Env) -> Option<Object> {
     let mut result = Object::Null;
     
     for statement in statements {
-        result = match eval(*statement, env) {
+        result = match eval(statement, env) {
             Some(value) => {
                 match value {
                     Object::ReturnValue { value: ret_value } => return Some(*ret_value),
@@ -166,8 +357,8 @@ fn eval_statements(statements: Vec<Box<Ast>>, env: &mut Env) -> Option<Object> {
     }
 }
 
-fn eval_prefix_expression(operator: String, right: Object) -> Option<Object> {
-    match operator.as_ref() {
+fn eval_prefix_expression(operator: &str, right: Object) -> Option<Object> {
+    match operator {
         "!" => return Some(eval_bang_operator_expression(right)),
         "-" => return Some(eval_minus_operator_expression(right)),
         _   => return None,
@@ -184,12 +375,29 @@ fn eval_bang_operator_expression(right: Object) -> Object {
 
 fn eval_minus_operator_expression(right: Object) -> Object {
     match right {
-        Object::Integer { value } => return Object::Integer{value: -value},
-        _                         => return new_error(format!("unknown operator: -{}", right.kind())),
+        Object::Integer { value } => match value.checked_neg() {
+            Some(value) => return Object::Integer{value: value},
+            None        => return new_error_with_kind("ArithmeticError", format!("integer overflow: -{}", value)),
+        },
+        _                         => return new_error_with_kind("OperatorError", format!("unknown operator: -{}", right.kind())),
     }
 }
 
-fn eval_infix_expression(operator: String, left: Object, right: Object) -> Object {
+fn eval_infix_expression(operator: &str, left: Object, right: Object) -> Object {
+    if operator == "in" {
+        return eval_membership(left, right);
+    }
+
+    // any value can be compared with null
+    if operator == "==" || operator == "!=" {
+        match (&left, &right) {
+            (Object::Null, _) | (_, Object::Null) => {
+                let equal = left.kind() == right.kind();
+                return Object::Boolean { value: if operator == "==" { equal } else { !equal } };
+            },
+            _ => (),
+        }
+    }
     if left.kind() == "Integer".to_string() && right.kind() == "Integer".to_string() {
         return eval_integer_infix_expression(operator, left, right);
     }
@@ -197,7 +405,16 @@ fn eval_infix_expression(operator: String, left: Object, right: Object) -> Objec
         return eval_string_infix_expression(operator, left, right);
     }
     else if left.kind() != right.kind() {
-        return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+        return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+    }
+    match (&left, operator) {
+        (Object::Struct { .. }, "==") | (Object::Variant { .. }, "==") => return Object::Boolean { value: objects_equal(&left, &right) },
+        (Object::Struct { .. }, "!=") | (Object::Variant { .. }, "!=") => return Object::Boolean { value: !objects_equal(&left, &right) },
+        (Object::Range { .. }, "==") => return Object::Boolean { value: objects_equal(&left, &right) },
+        (Object::Range { .. }, "!=") => return Object::Boolean { value: !objects_equal(&left, &right) },
+        (Object::Instance { .. }, "==") => return Object::Boolean { value: objects_equal(&left, &right) },
+        (Object::Instance { .. }, "!=") => return Object::Boolean { value: !objects_equal(&left, &right) },
+        _ => (),
     }
     if operator == "==".to_string() {
         match left {
@@ -206,10 +423,10 @@ fn eval_infix_expression(operator: String, left: Object, right: Object) -> Objec
                     Object::Boolean { value: rvalue } => {
                         return Object::Boolean { value: lvalue==rvalue};
                     },
-                    _ => return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
+                    _ => return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
                 }
             },
-            _ => return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
+            _ => return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
         }
     }
     else if operator == "!=".to_string() {
@@ -219,48 +436,96 @@ fn eval_infix_expression(operator: String, left: Object, right: Object) -> Objec
                     Object::Boolean { value: rvalue } => {
                         return Object::Boolean { value: lvalue!=rvalue};
                     },
-                    _ => return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
+                    _ => return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
                 }
             },
-            _ => return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
+            _ => return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind())),
         }
     }
-    new_error(format!("unknown operator: {} {} {}", left.kind(), operator, right.kind()))
+    new_error_with_kind("OperatorError", format!("unknown operator: {} {} {}", left.kind(), operator, right.kind()))
+}
+
+/// Structural equality used by struct and enum comparison; instances compare by identity
+fn objects_equal(left: &Object, right: &Object) -> bool {
+    match (left, right) {
+        (Object::Null, Object::Null) => true,
+        (Object::Integer { value: l }, Object::Integer { value: r }) => l == r,
+        (Object::Boolean { value: l }, Object::Boolean { value: r }) => l == r,
+        (Object::String { value: l }, Object::String { value: r })   => l == r,
+        (Object::Array { elements: l }, Object::Array { elements: r }) =>
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| objects_equal(l, r)),
+        (Object::Struct { name: ln, fields: l }, Object::Struct { name: rn, fields: r }) =>
+            ln == rn && l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.0 == r.0 && objects_equal(&l.1, &r.1)),
+        (Object::Range { range: l }, Object::Range { range: r }) => l.same_elements(r),
+        (Object::Instance { fields: l, .. }, Object::Instance { fields: r, .. }) => Rc::ptr_eq(l, r),
+        (Object::Variant { enum_name: le, name: ln, fields: l }, Object::Variant { enum_name: re, name: rn, fields: r }) =>
+            le == re && ln == rn && l.len() == r.len() && l.iter().zip(r).all(|(l, r)| objects_equal(&l.1, &r.1)),
+        _ => false,
+    }
+}
+
+/// `value in container`: element of an array, substring of a string or member of a range
+fn eval_membership(value: Object, container: Object) -> Object {
+    match (&value, &container) {
+        (_, Object::Array { elements }) => Object::Boolean { value: elements.iter().any(|e| objects_equal(e, &value)) },
+        (Object::String { value: part }, Object::String { value: string }) => Object::Boolean { value: string.contains(part.as_str()) },
+        (Object::Integer { value }, Object::Range { range }) => Object::Boolean { value: range.contains(*value) },
+        (_, Object::Range { .. }) => Object::Boolean { value: false },
+        (_, Object::String { .. }) => new_error_with_kind("TypeError", format!("type mismatch: {} in String", value.kind())),
+        _ => new_error_with_kind("OperatorError", format!("unknown operator: {} in {}", value.kind(), container.kind())),
+    }
+}
+
+/// Apply the function bound to a user-defined operator symbol
+fn eval_user_infix_expression(operator: &str, left: Object, right: Object, site: &CallSite, env: &mut Env) -> Object {
+    match env.get(operator.to_string()) {
+        Object::Null => new_error_with_kind("OperatorError", format!("unknown operator: {} {} {}", left.kind(), operator, right.kind())),
+        func         => call(func, vec![left, right], site.clone(), false, env),
+    }
 }
 
-fn eval_integer_infix_expression(operator: String, left: Object, right: Object) -> Object {
-    match operator.as_ref() {
+/// Result of integer arithmetic, or an error when it divides by zero or overflows
+fn checked_integer(result: Option<i64>, operator: &str, left: i64, right: i64) -> Object {
+    match result {
+        Some(value)                           => Object::Integer { value: value },
+        None if operator == "/" && right == 0 => new_error_with_kind("ArithmeticError", format!("division by zero: {} / {}", left, right)),
+        None                                  => new_error_with_kind("ArithmeticError", format!("integer overflow: {} {} {}", left, operator, right)),
+    }
+}
+
+fn eval_integer_infix_expression(operator: &str, left: Object, right: Object) -> Object {
+    match operator {
         "+" => {
             if let Object::Integer { value: lvalue } = left {
                 if let Object::Integer { value: rvalue } = right {
-                    return Object::Integer { value: lvalue + rvalue};
+                    return checked_integer(lvalue.checked_add(rvalue), operator, lvalue, rvalue);
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "-" => {
             if let Object::Integer { value: lvalue } = left {
                 if let Object::Integer { value: rvalue } = right {
-                    return Object::Integer { value: lvalue - rvalue};
+                    return checked_integer(lvalue.checked_sub(rvalue), operator, lvalue, rvalue);
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "*" => {
             if let Object::Integer { value: lvalue } = left {
                 if let Object::Integer { value: rvalue } = right {
-                    return Object::Integer { value: lvalue * rvalue};
+                    return checked_integer(lvalue.checked_mul(rvalue), operator, lvalue, rvalue);
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "/" => {
             if let Object::Integer { value: lvalue } = left {
                 if let Object::Integer { value: rvalue } = right {
-                    return Object::Integer { value: lvalue / rvalue};
+                    return checked_integer(lvalue.checked_div(rvalue), operator, lvalue, rvalue);
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "<" => {
             if let Object::Integer { value: lvalue } = left {
@@ -268,7 +533,7 @@ fn eval_integer_infix_expression(operator: String, left: Object, right: Object)
                     return Object::Boolean { value: lvalue < rvalue};
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         ">" => {
             if let Object::Integer { value: lvalue } = left {
@@ -276,7 +541,7 @@ fn eval_integer_infix_expression(operator: String, left: Object, right: Object)
                     return Object::Boolean { value: lvalue > rvalue};
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "==" => {
             if let Object::Integer { value: lvalue } = left {
@@ -284,7 +549,7 @@ fn eval_integer_infix_expression(operator: String, left: Object, right: Object)
                     return Object::Boolean { value: lvalue == rvalue};
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "!=" => {
             if let Object::Integer { value: lvalue } = left {
@@ -292,21 +557,21 @@ fn eval_integer_infix_expression(operator: String, left: Object, right: Object)
                     return Object::Boolean { value: lvalue != rvalue};
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
-        _  => return new_error(format!("unknown operator: {} {} {}", left.kind(), operator, right.kind())),
+        _  => return new_error_with_kind("OperatorError", format!("unknown operator: {} {} {}", left.kind(), operator, right.kind())),
     }
 }
 
-fn eval_string_infix_expression(operator: String, left: Object, right: Object) -> Object{
-    match operator.as_ref() {
+fn eval_string_infix_expression(operator: &str, left: Object, right: Object) -> Object{
+    match operator {
         "+" => {
             if let Object::String { value: lvalue } = left.clone() {
                 if let Object::String { value: rvalue } = right.clone() {
                     return Object::String { value: lvalue + &rvalue };
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "==" => {
             if let Object::String { value: lvalue } = left.clone() {
@@ -314,7 +579,7 @@ fn eval_string_infix_expression(operator: String, left: Object, right: Object) -
                     return Object::Boolean { value: lvalue == rvalue };
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
         },
         "!=" => {
             if let Object::String { value: lvalue } = left.clone() {
@@ -322,16 +587,151 @@ fn eval_string_infix_expression(operator: String, left: Object, right: Object) -
                     return Object::Boolean { value: lvalue != rvalue };
                 };
             };
-            return new_error(format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+            return new_error_with_kind("TypeError", format!("type mismatch: {} {} {}", left.kind(), operator, right.kind()));
+        },
+        _ => new_error_with_kind("OperatorError", format!("unknown operator: {} {} {}", left.kind(), operator, right.kind())),
+    }
+}
+
+/// Value of a member, index, slice or call chain, or None when a `?.` or `?[` in it met null:
+/// the null of an optional step skips the rest of the chain, so `a?.b.c` is null when a is
+fn eval_chain(node: &Ast, env: &mut Env) -> Option<Object> {
+    match node {
+        Ast::IndexExpression { token, left, index } => {
+            let l = eval_chain(left, env)?;
+
+            if is_error(&l) {
+                return Some(l);
+            }
+
+            if let Object::Null = l {
+                if token.kind == TokenKind::QuestionLbracket {
+                    return None;
+                }
+            }
+
+            let i = match eval(index, env) {
+                Some(value) => value,
+                None => Object::Null,
+            };
+
+            if is_error(&i) {
+                return Some(i);
+            }
+
+            return Some(eval_index_expression(l, i).unwrap_or(Object::Null))
+        },
+        Ast::MemberExpression { token, object, member } => {
+            let obj = eval_chain(object, env)?;
+
+            if is_error(&obj) {
+                return Some(obj);
+            }
+
+            if let Object::Null = obj {
+                if token.kind == TokenKind::QuestionDot {
+                    return None;
+                }
+            }
+
+            if let Ast::Identifier { ref value, .. } = **member {
+                return Some(eval_member_expression(obj, value));
+            }
+
+            return Some(Object::Null);
+        },
+        Ast::SliceExpression { token, left, start, end } => {
+            let l = eval_chain(left, env)?;
+
+            if is_error(&l) {
+                return Some(l);
+            }
+
+            if let Object::Null = l {
+                if token.kind == TokenKind::QuestionLbracket {
+                    return None;
+                }
+            }
+
+            let s = eval(start, env);
+            if let Some(ref value) = s {
+                if is_error(value) {
+                    return s;
+                }
+            }
+
+            let e = eval(end, env);
+            if let Some(ref value) = e {
+                if is_error(value) {
+                    return e;
+                }
+            }
+
+            return Some(eval_slice_expression(l, s, e))
+        },
+        Ast::CallExpression { .. } => eval_call_expression(node, env, false),
+        _ => Some(eval(node, env).unwrap_or(Object::Null)),
+    }
+}
+
+/// Evaluate node in the tail position of a function body or `return`: a call there is returned
+/// unevaluated as Object::TailCall, for apply_function to run once the calling function has returned
+fn eval_tail(node: &Ast, env: &mut Env) -> Option<Object> {
+    match node {
+        Ast::ExpressionStatement { expression, .. } => eval_tail(expression, env),
+        Ast::BlockStatement { .. } => eval_block_statement(node, env, true),
+        Ast::IfExpression { .. } => eval_if_expression(node, env, true),
+        Ast::CallExpression { .. } => Some(eval_call_expression(node, env, true).unwrap_or(Object::Null)),
+        _ => eval(node, env),
+    }
+}
+
+/// Run the call deferred by a `return` in result, for a caller that needs its value before returning
+pub fn finish_tail_call(result: Object) -> Object {
+    match result {
+        Object::ReturnValue { value } => {
+            let value = finish_tail_call(*value);
+            if is_error(&value) {
+                return value;
+            }
+            Object::ReturnValue { value: Box::new(value) }
+        },
+        Object::TailCall { function, arguments, site } => apply_function(*function, arguments, &site),
+        _ => result,
+    }
+}
+
+/// Call in a chain: None when a `?.` or `?[` in it met null
+fn eval_call_expression(node: &Ast, env: &mut Env, tail: bool) -> Option<Object> {
+    match node {
+        Ast::CallExpression { token, function, arguments } => {
+            let site = call_site(token, env);
+            if let Ast::MemberExpression { ref token, ref object, ref member } = **function {
+                return eval_method_call(object, member, arguments, token.kind == TokenKind::QuestionDot, site, tail, env);
+            }
+
+            let func = eval_chain(function, env)?;
+
+            if is_error(&func) {
+                return Some(func);
+            }
+
+            let args = eval_expressions(arguments, env, false);
+
+            if args.len() == 1 && is_error(&args[0]) {
+                return Some(args[0].clone());
+            }
+
+            Some(call(func, args, site, tail, env))
         },
-        _ => new_error(format!("unknown operator: {} {} {}", left.kind(), operator, right.kind())),
+        _ => return Some(Object::Null),
     }
 }
 
-fn eval_if_expression(node: Ast, env: &mut Env) -> Option<Object> {
+fn eval_if_expression(node: &Ast, env: &mut Env, tail: bool) -> Option<Object> {
     match node {
         Ast::IfExpression { condition, consequence, alternative, .. } => {
-            let condition = match eval(*condition, env) {
+            let condition = match eval(condition, env) {
                 Some(value) => value,
                 None        => return Some(Object::Null),
             };
@@ -340,28 +740,219 @@ fn eval_if_expression(node: Ast, env: &mut Env) -> Option<Object> {
                 return Some(condition);
             }
             
+            let eval_branch = if tail { eval_tail } else { eval };
+
             if is_truthy(condition) {
-                return eval(*consequence, env);
+                return eval_branch(consequence, env);
             }
             
             else {
-                match *alternative {
+                match **alternative {
                     Ast::Expression { ref token, .. } => {
                         if token.get_kind_literal() == "Illegal".to_string()  {
                             return Some(Object::Null);
                         }
                         else {
-                            return eval(*alternative, env);
+                            return eval_branch(alternative, env);
                         }
                     },
-                    _ => return eval(*alternative, env),
+                    _ => return eval_branch(alternative, env),
+                }
+            }
+        },
+        _ => return Some(Object::Null),
+    }
+}
+
+fn eval_try_expression(node: &Ast, env: &mut Env) -> Option<Object> {
+    match node {
+        Ast::TryExpression { block, parameter, catch, finally, .. } => {
+            // a call returned from the try or catch block must run before catch and finally
+            let mut result = match eval(block, env) {
+                Some(value) => finish_tail_call(value),
+                None        => Object::Null,
+            };
+
+            // the parameter is bound only in the catch block
+            if let Object::Error { kind, msg, .. } = result.clone() {
+                if let Ast::Identifier { ref value, .. } = **parameter {
+                    let mut scope = Env::new_enclosed_env(env);
+                    scope.set(value.clone(), Object::Exception { kind: kind, msg: msg });
+                    result = match eval(catch, &mut scope) {
+                        Some(value) => finish_tail_call(value),
+                        None        => Object::Null,
+                    };
+                }
+            }
+
+            // an error or return from the finally block replaces the pending result
+            if let Ast::BlockStatement { .. } = **finally {
+                let cleanup = match eval(finally, env) {
+                    Some(value) => value,
+                    None        => Object::Null,
+                };
+                match cleanup {
+                    Object::Error { .. } |
+                    Object::ReturnValue { .. } => return Some(cleanup),
+                    _ => (),
                 }
             }
+
+            Some(result)
         },
         _ => return Some(Object::Null),
     }
 }
 
+fn eval_for_expression(node: &Ast, env: &mut Env) -> Option<Object> {
+    match node {
+        Ast::ForExpression { variable, iterable, body, .. } => {
+            let iterable = match eval(iterable, env) {
+                Some(value) => value,
+                None        => Object::Null,
+            };
+
+            let iter = match Iter::new(iterable) {
+                Ok(value)  => value,
+                Err(error) => return Some(error),
+            };
+
+            let name = variable.to_string();
+            if let Some(error) = loop_variable_error(&name, env) {
+                return Some(error);
+            }
+
+            for element in iter {
+                if is_error(&element) {
+                    return Some(element);
+                }
+                env.set(name.clone(), element);
+
+                let result = match eval(body, env) {
+                    Some(value) => value,
+                    None        => Object::Null,
+                };
+                match result {
+                    Object::Error { .. } |
+                    Object::ReturnValue { .. } => return Some(result),
+                    _ => (),
+                }
+            }
+
+            Some(Object::Null)
+        },
+        _ => return Some(Object::Null),
+    }
+}
+
+/// Error if the variable of a `for` loop would rebind a constant
+pub fn loop_variable_error(name: &str, env: &Env) -> Option<Object> {
+    if env.is_const(name) {
+        return Some(new_error_with_kind("AssignmentError", format!("cannot redeclare constant: {}", name)));
+    }
+
+    None
+}
+
+/// Evaluate `start..end step n` to a lazy Object::Range
+fn eval_range_expression(node: &Ast, env: &mut Env) -> Option<Object> {
+    match node {
+        Ast::RangeExpression { token, start, end, step } => {
+            let mut bounds = Vec::new();
+            for bound in [start, end, step] {
+                let value = match eval(bound, env) {
+                    Some(value) => value,
+                    None        => Object::Integer { value: 1 },      // omitted step
+                };
+                match value {
+                    Object::Integer { value } => bounds.push(value),
+                    Object::Error { .. }      => return Some(value),
+                    _ => return Some(new_error_with_kind("TypeError", format!("range bound must be Integer. got={}", value.kind()))),
+                }
+            }
+
+            if bounds[2] == 0 {
+                return Some(new_error_with_kind("ValueError", "range step must not be zero".to_string()));
+            }
+
+            let inclusive = token.kind == TokenKind::DotDotEq;
+            Some(Object::Range { range: Range::new(bounds[0], bounds[1], bounds[2], inclusive) })
+        },
+        _ => return Some(Object::Null),
+    }
+}
+
+/// Evaluate `[element for x in xs if cond ..]`; the loop variables live in a child scope of env
+fn eval_list_comprehension(node: &Ast, env: &mut Env) -> Option<Object> {
+    match node {
+        Ast::ListComprehension { element, clauses, .. } => {
+            let mut scope = Env::new_enclosed_env(env);
+            let mut elements = Vec::new();
+
+            match eval_comprehension_clauses(element, clauses, &mut scope, &mut elements) {
+                Some(error) => Some(error),
+                None        => Some(Object::Array { elements: elements }),
+            }
+        },
+        _ => return Some(Object::Null),
+    }
+}
+
+/// Run the first of clauses and the rest for each of its bindings, collecting element once all
+/// clauses have passed. Returns the error or return value that stops the comprehension.
+fn eval_comprehension_clauses(element: &Ast, clauses: &[Box<Ast>], env: &mut Env, elements: &mut Vec<Object>) -> Option<Object> {
+    let (clause, rest) = match clauses.split_first() {
+        Some(value) => value,
+        None        => {
+            let value = match eval(element, env) {
+                Some(value) => value,
+                None        => Object::Null,
+            };
+            match value {
+                Object::Error { .. } |
+                Object::ReturnValue { .. } => return Some(value),
+                _ => elements.push(value),
+            }
+            return None;
+        },
+    };
+
+    if let Ast::ComprehensionClause { ref token, ref variable, ref value } = **clause {
+        let value = match eval(value, env) {
+            Some(value) => value,
+            None        => Object::Null,
+        };
+        if is_error(&value) {
+            return Some(value);
+        }
+
+        match token.kind {
+            TokenKind::For => {
+                let iter = match Iter::new(value) {
+                    Ok(value)  => value,
+                    Err(error) => return Some(error),
+                };
+
+                for item in iter {
+                    if is_error(&item) {
+                        return Some(item);
+                    }
+                    env.set(variable.to_string(), item);
+
+                    if let Some(stop) = eval_comprehension_clauses(element, rest, env, elements) {
+                        return Some(stop);
+                    }
+                }
+            },
+            _ => if is_truthy(value) {
+                return eval_comprehension_clauses(element, rest, env, elements);
+            },
+        }
+    }
+
+    None
+}
+
 fn is_truthy(obj: Object) -> bool {
     match obj {
         Object::Null => return false,
@@ -370,17 +961,17 @@ fn is_truthy(obj: Object) -> bool {
     }
 }
 
-fn eval_program(program: Ast, env: &mut Env) -> Option<Object> {
+fn eval_program(program: &Ast, env: &mut Env) -> Option<Object> {
     let mut result = Object::Null;
     
     if let Ast::Program { statements, .. } = program {
         for statement in statements {
-            result = match eval(*statement, env) {
+            result = match eval(statement, env) {
                 Some(value) => value,
                 None        => Object::Null,
             };
             match  result {
-                Object::ReturnValue { value } => return Some(*value),
+                Object::ReturnValue { value } => return Some(finish_tail_call(*value)),
                 Object::Error { .. }          => return Some(result),
                 _ => (),
             };
@@ -390,12 +981,14 @@ fn eval_program(program: Ast, env: &mut Env) -> Option<Object> {
     Some(result)
 }
 
-fn eval_block_statement(block: Ast, env: &mut Env) -> Option<Object> {
+fn eval_block_statement(block: &Ast, env: &mut Env, tail: bool) -> Option<Object> {
     let mut result = Object::Null;
 
     if let Ast::BlockStatement { statements, .. } = block {
-        for statement in statements {
-            result = match eval(*statement, env){
+        let last = statements.len();
+        for (i, statement) in statements.iter().enumerate() {
+            let evaluated = if tail && i + 1 == last { eval_tail(statement, env) } else { eval(statement, env) };
+            result = match evaluated {
                 Some(value) => value,
                 None        => Object::Null,
             };
@@ -408,26 +1001,32 @@ fn eval_block_statement(block: Ast, env: &mut Env) -> Option<Object> {
     Some(result)
 }
 
-fn eval_identifier(value: String, env: &mut Env) -> Option<Object> {
-    let val = env.get(value.clone());
-    
-    match val {
-        Object::Null => (),
-        _ => return Some(val),
+fn eval_identifier(value: &str, env: &mut Env) -> Option<Object> {
+    if let Some(val) = env.lookup(value) {
+        return Some(val);
     }
 
-    if let Object::Builtin { function } = builtins(value.clone()) {
-        return Some(builtins(value.clone()));
+    if let Object::Builtin { function } = builtins(value.to_string()) {
+        return Some(builtins(value.to_string()));
     }
     
-    Some(new_error(format!("identifier not found: {}", value)))
+    Some(new_error_with_kind("NameError", format!("identifier not found: {}", value)))
 }
 
-fn eval_expressions(exps: Vec<Box<Ast>>, env: &mut Env) -> Vec<Object>{
+/// Values of exps, with the elements of spread values inserted. For an array, which holds copies
+/// of the values, the size built so far is checked against the allocation limit after each one.
+fn eval_expressions(exps: &[Box<Ast>], env: &mut Env, array: bool) -> Vec<Object>{
     let mut result = Vec::new();
+    let mut bytes: usize = 0;
 
     for exp in exps {
-        let evaluated = match eval(*exp, env){
+        // `...value` inserts the elements of value
+        let (spread, exp) = match **exp {
+            Ast::SpreadExpression { ref value, .. } => (true, &**value),
+            ref other                               => (false, other),
+        };
+
+        let evaluated = match eval(exp, env){
             Some(value) => value,
             None        => Object::Null,
         };
@@ -436,70 +1035,437 @@ fn eval_expressions(exps: Vec<Box<Ast>>, env: &mut Env) -> Vec<Object>{
             return vec![evaluated];
         }
 
-        result.push(evaluated);
+        if !spread {
+            if array {
+                if let Some(error) = grow_array(&mut bytes, &evaluated, env) {
+                    return vec![error];
+                }
+            }
+            result.push(evaluated);
+            continue;
+        }
+
+        if let Object::Range { ref range } = evaluated {
+            if let Some(error) = env.context().borrow_mut().reserve(range.len()) {
+                return vec![error];
+            }
+        }
+
+        let iter = match Iter::new(evaluated) {
+            Ok(value)  => value,
+            Err(error) => return vec![error],
+        };
+        for element in iter {
+            if is_error(&element) {
+                return vec![element];
+            }
+            if array {
+                if let Some(error) = grow_array(&mut bytes, &element, env) {
+                    return vec![error];
+                }
+            }
+            result.push(element);
+        }
     }
 
     result
 }
 
+/// Add the size of element to the bytes of an array being built, and check that it fits
+fn grow_array(bytes: &mut usize, element: &Object, env: &Env) -> Option<Object> {
+    *bytes = bytes.saturating_add(mem::size_of::<Object>() + size(element));
+    env.context().borrow_mut().check_size(*bytes)
+}
+
 fn eval_index_expression(left: Object, index: Object) -> Option<Object> {
     if left.kind() == "Array".to_string() && index.kind() == "Integer" {
         return Some(eval_array_index_expression(left, index));
     }
-    Some(new_error(format!("index operator not supported: {}", left.kind())))
+    if left.kind() == "String".to_string() && index.kind() == "Integer" {
+        return Some(eval_string_index_expression(left, index));
+    }
+    if let Object::Range { range } = left {
+        if index.kind() == "Integer" {
+            return match normalize_index(index, range.len() as usize) {
+                Some(idx) => Some(Object::Integer { value: range.get(idx as i64) }),
+                None      => Some(Object::Null),
+            };
+        }
+        return Some(new_error_with_kind("IndexError", "index operator not supported: Range".to_string()));
+    }
+    Some(new_error_with_kind("IndexError", format!("index operator not supported: {}", left.kind())))
 }
 
 fn eval_array_index_expression(array: Object, index: Object) -> Object  {
-    let idx = match index {
-        Object::Integer { value } => value as usize,
+    let elements = match array {
+        Object::Array { elements } => elements,
         _ => return Object::Null,
     };
 
-    let max = match array {
-        Object::Array { ref elements } => (&elements.len() - 1),
+    match normalize_index(index, elements.len()) {
+        Some(idx) => elements[idx].clone(),
+        None      => Object::Null,
+    }
+}
+
+fn eval_string_index_expression(string: Object, index: Object) -> Object  {
+    let chars: Vec<char> = match string {
+        Object::String { value } => value.chars().collect(),
         _ => return Object::Null,
     };
 
-    if idx < 0 || idx > max {
-        return Object::Null;
+    match normalize_index(index, chars.len()) {
+        Some(idx) => Object::String { value: chars[idx].to_string() },
+        None      => Object::Null,
+    }
+}
+
+/// Resolve a possibly negative index against length. Return None if out of range.
+fn normalize_index(index: Object, len: usize) -> Option<usize> {
+    let idx = match index {
+        Object::Integer { value } => value,
+        _ => return None,
+    };
+
+    let idx = if idx < 0 { idx + len as i64 } else { idx };
+
+    if idx < 0 || idx >= len as i64 {
+        return None;
     }
 
-    match array {
-        Object::Array { ref elements } => elements[idx].clone(),
+    Some(idx as usize)
+}
+
+fn eval_slice_expression(left: Object, start: Option<Object>, end: Option<Object>) -> Object {
+    let len = match left {
+        Object::Array { ref elements } => elements.len(),
+        Object::String { ref value }   => value.chars().count(),
+        _ => return new_error_with_kind("IndexError", format!("slice operator not supported: {}", left.kind())),
+    };
+
+    let start = match start {
+        Some(Object::Integer { value }) => clamp_slice_bound(value, len),
+        Some(obj) => return new_error_with_kind("IndexError", format!("slice index must be Integer. got={}", obj.kind())),
+        None      => 0,
+    };
+
+    let end = match end {
+        Some(Object::Integer { value }) => clamp_slice_bound(value, len),
+        Some(obj) => return new_error_with_kind("IndexError", format!("slice index must be Integer. got={}", obj.kind())),
+        None      => len,
+    };
+
+    let end = if end < start { start } else { end };
+
+    match left {
+        Object::Array { elements } => Object::Array { elements: elements[start..end].to_vec() },
+        Object::String { value }   => Object::String { value: value.chars().skip(start).take(end - start).collect() },
         _ => Object::Null,
     }
 }
 
-fn apply_function(func: Object, args: Vec<Object>) -> Object {
+/// Resolve a possibly negative slice bound and clamp it into 0..=len.
+fn clamp_slice_bound(bound: i64, len: usize) -> usize {
+    let bound = if bound < 0 { bound + len as i64 } else { bound };
+
+    if bound < 0 {
+        return 0;
+    }
+    if bound > len as i64 {
+        return len;
+    }
+
+    bound as usize
+}
+
+fn eval_member_expression(obj: Object, name: &str) -> Object {
+    match &obj {
+        Object::Struct { fields, .. } |
+        Object::Variant { fields, .. } |
+        Object::EnumType { variants: fields, .. } => {
+            if let Some((_, value)) = fields.iter().find(|(k, _)| *k == name) {
+                return value.clone();
+            }
+        },
+        Object::Instance { class, fields } => {
+            if let Some((_, value)) = fields.borrow().iter().find(|(k, _)| *k == name) {
+                return value.clone();
+            }
+            if let Some((owner, method)) = find_method(class, name) {
+                return bind_method(&obj, &owner, method);
+            }
+        },
+        Object::Super { instance, class } => {
+            if let Some((owner, method)) = find_method(class, name) {
+                return bind_method(instance, &owner, method);
+            }
+        },
+        Object::Module { path, exports } => {
+            return match exports.iter().find(|(k, _)| *k == name) {
+                Some((_, value)) => value.clone(),
+                None             => new_error_with_kind("NameError", format!("{} is not exported by {}", name, path)),
+            };
+        },
+        _ => (),
+    }
+
+    if let Object::Exception { kind, msg } = &obj {
+        match name {
+            "kind"    => return Object::String { value: kind.to_string() },
+            "message" => return Object::String { value: msg.to_string() },
+            _         => (),
+        }
+    }
+
+    new_error_with_kind("NameError", format!("unknown member: {}.{}", obj.kind(), name))
+}
+
+/// Set a field of a class instance; other values are immutable
+fn eval_member_assignment(obj: Object, name: String, val: Object) -> Object {
+    match obj {
+        Object::Instance { fields, .. } => {
+            let mut fields = fields.borrow_mut();
+            match fields.iter_mut().find(|(k, _)| *k == name) {
+                Some(field) => field.1 = val.clone(),
+                None        => fields.push((name, val.clone())),
+            }
+
+            val
+        },
+        _ => new_error_with_kind("AssignmentError", format!("cannot assign to member {} of {}", name, obj.kind())),
+    }
+}
+
+/// Bind `self`, and `super` if the defining class has a superclass, in a scope enclosed by the method's environment
+fn bind_method(instance: &Object, owner: &Rc<Class>, method: Object) -> Object {
+    match method {
+        Object::Function { parameters, body, generator, env, name } => {
+            let env = Env::new_enclosed_env(&env);
+            env.set("self".to_string(), instance.clone());
+            if let Some(ref superclass) = owner.superclass {
+                env.set("super".to_string(), Object::Super { instance: Box::new(instance.clone()), class: superclass.clone() });
+            }
+
+            Object::Function { parameters: parameters, body: body, generator: generator, env: env, name: name }
+        },
+        _ => method,
+    }
+}
+
+/// Create an instance of class and run its `init` method, if any, with args
+fn instantiate(class: Rc<Class>, args: Vec<Object>, site: &CallSite) -> Object {
+    let instance = Object::Instance { class: class.clone(), fields: Rc::new(RefCell::new(Vec::new())) };
+
+    match find_method(&class, "init") {
+        Some((owner, init)) => {
+            let result = apply_function(bind_method(&instance, &owner, init), args, site);
+            if is_error(&result) {
+                return result;
+            }
+        },
+        None => {
+            if args.len() != 0 {
+                return new_error_with_kind("ArgumentError", format!("wrong number of arguments to {}. got={}, want=0", class.name, args.len()));
+            }
+        },
+    }
+
+    instance
+}
+
+/// Reject declaring a type under a constant or built-in type name
+fn check_type_name(name: &str, env: &Env) -> Option<Object> {
+    if env.is_const(name) {
+        return Some(new_error_with_kind("AssignmentError", format!("cannot redeclare constant: {}", name)));
+    }
+    if BUILTIN_KINDS.contains(&name) {
+        return Some(new_error_with_kind("TypeError", format!("cannot redefine built-in type: {}", name)));
+    }
+
+    None
+}
+
+/// Evaluate `obj.name(args)` as `name(obj, args)`
+/// Call `object.member(arguments)`. An optional call `object?.member(..)` is None, skipping the rest of the chain, if object is null.
+fn eval_method_call(object: &Ast, member: &Ast, arguments: &[Box<Ast>], optional: bool, site: CallSite, tail: bool, env: &mut Env) -> Option<Object> {
+    let obj = eval_chain(object, env)?;
+
+    if is_error(&obj) {
+        return Some(obj);
+    }
+
+    if let Object::Null = obj {
+        if optional {
+            return None;
+        }
+    }
+
+    let name = match member {
+        Ast::Identifier { ref value, .. } => value,
+        _                             => return Some(Object::Null),
+    };
+
+    let args = eval_expressions(arguments, env, false);
+
+    if args.len() == 1 && is_error(&args[0]) {
+        return Some(args[0].clone());
+    }
+
+    // a field holding a function, a method, an enum variant constructor or a module member is called without the receiver
+    match &obj {
+        Object::Struct { .. } | Object::Variant { .. } | Object::EnumType { .. } |
+        Object::Instance { .. } | Object::Super { .. } | Object::Module { .. } => {
+            let value = eval_member_expression(obj.clone(), name);
+            if !is_error(&value) {
+                return Some(call(value, args, site, tail, env));
+            }
+            if let Object::Module { .. } = obj {
+                return Some(value);
+            }
+        },
+        _ => (),
+    }
+
+    let func = match eval_identifier(name, env) {
+        Some(value) => value,
+        None        => Object::Null,
+    };
+
+    if is_error(&func) {
+        return Some(new_error_with_kind("NameError", format!("undefined method: {} for {}", name, obj.kind())));
+    }
+
+    let mut receiver_args = vec![obj];
+    receiver_args.extend(args);
+
+    Some(call(func, receiver_args, site, tail, env))
+}
+
+/// Apply func to args, or defer the call to the caller's apply_function if it is a function call in
+/// tail position. Other callables, such as a class running `init`, still nest and count as calls.
+/// Builtins are only called here, with the context of env. The new strings and containers they
+/// return, and constructed structs and variants, count towards the allocation limit.
+fn call(func: Object, args: Vec<Object>, site: CallSite, tail: bool, env: &Env) -> Object {
+    if let (true, Object::Function { .. }) = (tail, &func) {
+        return Object::TailCall { function: Box::new(func), arguments: args, site: site };
+    }
+
+    // the strings and arrays a builtin returns are new
+    if let Object::Builtin { function } = func {
+        let context = env.context();
+        let result = function(args, &context);
+        if let Some(error) = context.borrow_mut().allocate(&result) {
+            return error;
+        }
+        return result;
+    }
+
+    // a struct or variant holds copies of the arguments it is constructed from
+    if let Object::StructType { .. } | Object::VariantType { .. } = func {
+        let result = apply_function(func, args, &site);
+        if let Some(error) = env.context().borrow_mut().allocate(&result) {
+            return error;
+        }
+        return result;
+    }
+
+    apply_function(func, args, &site)
+}
+
+/// Name an anonymous function after the first binding it gets, for tracebacks
+fn name_function(value: &mut Object, binding: &str) {
+    if let Object::Function { name: ref mut name @ None, .. } = value {
+        *name = Some(binding.to_string());
+    }
+}
+
+fn call_site(token: &Token, env: &Env) -> CallSite {
+    CallSite { file: env.file(), line: token.line, column: token.column }
+}
+
+/// Apply func to args. Calls in tail position come back as Object::TailCall and run in this loop,
+/// so tail recursion takes constant stack.
+fn apply_function(func: Object, args: Vec<Object>, site: &CallSite) -> Object {
+    let mut result = apply_function_once(func, args, site);
+
+    loop {
+        match result {
+            Object::TailCall { function, arguments, site } => result = apply_function_once(*function, arguments, &site),
+            _                                        => return result,
+        }
+    }
+}
+
+/// Apply func to args once, adding the call to the trace of an error that leaves it
+fn apply_function_once(func: Object, args: Vec<Object>, site: &CallSite) -> Object {
     match func {
-        Object::Function { .. } => {
-            let mut extend_env = extend_function_env(func.clone(), args);
-            let evaluated = match func {
-                Object::Function { body, ..} => match eval(*body, &mut extend_env) {
-                    Some(value) => value,
-                    None        => return Object::Null,
-                },
-                _                            => return Object::Null,
+        Object::Function { ref parameters, .. } if is_variadic(parameters) && args.len() < parameters.len() - 1 =>
+            return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want={} or more", args.len(), parameters.len() - 1)),
+        Object::Function { ref parameters, .. } if !is_variadic(parameters) && parameters.len() != args.len() =>
+            return new_error_with_kind("ArgumentError", format!("wrong number of arguments. got={}, want={}", args.len(), parameters.len())),
+        Object::Function { ref body, generator: true, .. } => {
+            let extend_env = extend_function_env(&func, args);
+            return Object::Generator { generator: Rc::new(RefCell::new(Generator::new(body, extend_env))) };
+        },
+        Object::Function { ref body, ref name, .. } => {
+            let mut extend_env = extend_function_env(&func, args);
+            let context = extend_env.context();
+            if let Some(error) = context.borrow_mut().step() {
+                return error;
+            }
+            if !context.borrow_mut().enter_call() {
+                return new_error_with_kind("RecursionError", "maximum recursion depth exceeded".to_string());
+            }
+
+            let evaluated = eval_tail(body, &mut extend_env);
+            context.borrow_mut().exit_call();
+
+            let mut result = match evaluated {
+                Some(value) => unwrap_return_value(value),
+                None        => Object::Null,
             };
-            
-            return unwrap_return_value(evaluated);            
+            if let Object::Error { ref mut trace, .. } = result {
+                let function = match name {
+                    Some(value) => value.clone(),
+                    None        => "fn".to_string(),
+                };
+                trace.push(Frame { function: function, location: site.to_string() });
+            }
+            return result;
         },
-        Object::Builtin { function } => return function(args),
-        _                       => return new_error(format!("not a function: {}", func.kind())),
+        Object::StructType { name, fields } => {
+            if args.len() != fields.len() {
+                return new_error_with_kind("ArgumentError", format!("wrong number of arguments to {}. got={}, want={}", name, args.len(), fields.len()));
+            }
+            return Object::Struct { name: name, fields: fields.into_iter().zip(args).collect() };
+        },
+        Object::Class { class } => return instantiate(class, args, site),
+        Object::VariantType { enum_name, name, fields } => {
+            if args.len() != fields.len() {
+                return new_error_with_kind("ArgumentError", format!("wrong number of arguments to {}.{}. got={}, want={}", enum_name, name, args.len(), fields.len()));
+            }
+            return Object::Variant { enum_name: enum_name, name: name, fields: fields.into_iter().zip(args).collect() };
+        },
+        _                       => return new_error_with_kind("TypeError", format!("not a function: {}", func.kind())),
     }
 
 }
 
-fn extend_function_env(func: Object, args: Vec<Object>) -> Env {
-    let mut env = match func.clone() {
+fn extend_function_env(func: &Object, args: Vec<Object>) -> Env {
+    let env = match func {
         Object::Function { env, .. } => Env::new_enclosed_env(env),
         _                            => return Env::new(),
     };
 
     if let Object::Function { parameters, ..} = func {
         for (i, parameter) in parameters.iter().enumerate() {
-            if let Ast::Identifier { value, .. } = (**parameter).clone() {
-                env.set(value, args[i].clone());
+            match **parameter {
+                Ast::Identifier { ref value, .. } => { env.set(value.clone(), args[i].clone()); },
+                // a variadic parameter collects the remaining arguments
+                Ast::SpreadExpression { ref value, .. } => {
+                    env.set(value.to_string(), Object::Array { elements: args[i..].to_vec() });
+                },
+                _ => (),
             }
         }
     }
@@ -0,0 +1,329 @@
+use crate::ast::{ Ast, parameter_name };
+use crate::token::{ Token, TokenKind };
+use std::collections::HashMap;
+use std::rc::{ Rc };
+
+/// Constant folding pass.
+/// Evaluates operators whose operands are literals, and replaces references to
+/// `const` bindings with their value when it folds to a literal.
+pub fn fold_constants(program: Ast) -> Ast {
+    let mut constants = HashMap::new();
+
+    fold(program, &mut constants)
+}
+
+fn fold(node: Ast, constants: &mut HashMap<String, Ast>) -> Ast {
+    match node {
+        Ast::Program { statements } => Ast::Program { statements: fold_all(statements, constants) },
+        Ast::BlockStatement { token, statements } => {
+            // bindings declared in a conditional block are not visible to folding outside of it
+            let mut inner = constants.clone();
+            Ast::BlockStatement { token: token, statements: Rc::new(fold_all(Rc::unwrap_or_clone(statements), &mut inner)) }
+        },
+        Ast::Identifier { token, value } => match constants.get(&value) {
+            Some(literal) => literal.clone(),
+            None          => Ast::Identifier { token: token, value: value },
+        },
+        Ast::LetStatement { token, ident, annotation, value } => {
+            let value = fold(*value, constants);
+
+            if let Ast::Identifier { value: ref name, .. } = *ident {
+                if token.kind == TokenKind::Const && is_literal(&value) {
+                    constants.insert(name.to_string(), value.clone());
+                }
+                else {
+                    constants.remove(name);
+                }
+            }
+
+            Ast::LetStatement { token: token, ident: ident, annotation: annotation, value: Box::new(value) }
+        },
+        Ast::FunctionLiteral { token, parameters, parameter_types, return_type, body } => {
+            let mut inner = constants.clone();
+            for parameter in &parameters {
+                inner.remove(&parameter_name(parameter));
+            }
+            for name in declared_names(&body) {
+                inner.remove(&name);
+            }
+
+            Ast::FunctionLiteral { token: token, parameters: parameters, parameter_types: parameter_types,
+                                  return_type: return_type, body: Rc::new(fold(Rc::unwrap_or_clone(body), &mut inner)) }
+        },
+        Ast::PrefixExpression { token, operator, right } => {
+            let right = fold(*right, constants);
+
+            match fold_prefix(&operator, &right) {
+                Some(value) => value,
+                None        => Ast::PrefixExpression { token: token, operator: operator, right: Box::new(right) },
+            }
+        },
+        Ast::InfixExpression { token, left, operator, right } => {
+            let left = fold(*left, constants);
+            let right = fold(*right, constants);
+
+            if token.kind == TokenKind::Operator {
+                return Ast::InfixExpression { token: token, left: Box::new(left), operator: operator, right: Box::new(right) };
+            }
+
+            match fold_infix(&operator, &left, &right) {
+                Some(value) => value,
+                None        => Ast::InfixExpression { token: token, left: Box::new(left), operator: operator, right: Box::new(right) },
+            }
+        },
+        Ast::ReturnStatement { token, return_value } =>
+            Ast::ReturnStatement { token: token, return_value: Box::new(fold(*return_value, constants)) },
+        Ast::InfixDeclaration { token, operator, precedence, associativity, value } =>
+            Ast::InfixDeclaration { token: token, operator: operator, precedence: precedence,
+                                    associativity: associativity, value: Box::new(fold(*value, constants)) },
+        Ast::ImportStatement { ref alias, .. } => {
+            constants.remove(&alias.to_string());
+            node
+        },
+        Ast::ImportExpression { token, path } =>
+            Ast::ImportExpression { token: token, path: Box::new(fold(*path, constants)) },
+        Ast::ExportStatement { token, statement } =>
+            Ast::ExportStatement { token: token, statement: Box::new(fold(*statement, constants)) },
+        Ast::ThrowStatement { token, value } =>
+            Ast::ThrowStatement { token: token, value: Box::new(fold(*value, constants)) },
+        Ast::YieldStatement { token, value } =>
+            Ast::YieldStatement { token: token, value: Box::new(fold(*value, constants)) },
+        Ast::SpreadExpression { token, value } =>
+            Ast::SpreadExpression { token: token, value: Box::new(fold(*value, constants)) },
+        Ast::ForExpression { token, variable, iterable, body } => {
+            // the loop variable shadows constants inside the body
+            let mut inner = constants.clone();
+            if let Ast::Identifier { ref value, .. } = *variable {
+                inner.remove(value);
+            }
+
+            Ast::ForExpression {
+                token: token,
+                variable: variable,
+                iterable: Box::new(fold(*iterable, constants)),
+                body: Box::new(fold(*body, &mut inner)),
+            }
+        },
+        Ast::RangeExpression { token, start, end, step } => Ast::RangeExpression {
+            token: token,
+            start: Box::new(fold(*start, constants)),
+            end: Box::new(fold(*end, constants)),
+            step: Box::new(fold(*step, constants)),
+        },
+        Ast::ListComprehension { token, element, clauses } => {
+            // each `for` variable shadows constants in the clauses after it and in the element
+            let mut inner = constants.clone();
+            let clauses = clauses.into_iter().map(|clause| {
+                let folded = fold(*clause, &mut inner);
+                if let Ast::ComprehensionClause { ref variable, .. } = folded {
+                    if let Ast::Identifier { ref value, .. } = **variable {
+                        inner.remove(value);
+                    }
+                }
+                Box::new(folded)
+            }).collect();
+
+            Ast::ListComprehension {
+                token: token,
+                element: Box::new(fold(*element, &mut inner)),
+                clauses: clauses,
+            }
+        },
+        Ast::ComprehensionClause { token, variable, value } =>
+            Ast::ComprehensionClause { token: token, variable: variable, value: Box::new(fold(*value, constants)) },
+        Ast::TryExpression { token, block, parameter, catch, finally } => {
+            // the catch parameter shadows constants inside the catch block
+            let mut inner = constants.clone();
+            if let Ast::Identifier { ref value, .. } = *parameter {
+                inner.remove(value);
+            }
+
+            Ast::TryExpression {
+                token: token,
+                block: Box::new(fold(*block, constants)),
+                parameter: parameter,
+                catch: Box::new(fold(*catch, &mut inner)),
+                finally: Box::new(fold(*finally, constants)),
+            }
+        },
+        Ast::StructDeclaration { .. } |
+        Ast::EnumDeclaration { .. } |
+        Ast::ClassDeclaration { .. } => {
+            for name in declared_names(&node) {
+                constants.remove(&name);
+            }
+            node
+        },
+        Ast::ExpressionStatement { token, expression } =>
+            Ast::ExpressionStatement { token: token, expression: Box::new(fold(*expression, constants)) },
+        Ast::IfExpression { token, condition, consequence, alternative } =>
+            Ast::IfExpression {
+                token: token,
+                condition: Box::new(fold(*condition, constants)),
+                consequence: Box::new(fold(*consequence, constants)),
+                alternative: Box::new(fold(*alternative, constants)),
+            },
+        Ast::CallExpression { token, function, arguments } =>
+            Ast::CallExpression {
+                token: token,
+                function: Box::new(fold(*function, constants)),
+                arguments: fold_all(arguments, constants),
+            },
+        Ast::ArrayLiteral { token, elements } => Ast::ArrayLiteral { token: token, elements: fold_all(elements, constants) },
+        Ast::IndexExpression { token, left, index } =>
+            Ast::IndexExpression { token: token, left: Box::new(fold(*left, constants)), index: Box::new(fold(*index, constants)) },
+        Ast::SliceExpression { token, left, start, end } =>
+            Ast::SliceExpression {
+                token: token,
+                left: Box::new(fold(*left, constants)),
+                start: Box::new(fold(*start, constants)),
+                end: Box::new(fold(*end, constants)),
+            },
+        Ast::MemberExpression { token, object, member } =>
+            Ast::MemberExpression { token: token, object: Box::new(fold(*object, constants)), member: member },
+        Ast::AssignExpression { token, target, value } =>
+            Ast::AssignExpression { token: token, target: target, value: Box::new(fold(*value, constants)) },
+        Ast::EnumVariant { .. } |
+        Ast::TypeExpression { .. } |
+        Ast::MethodDefinition { .. } |
+        Ast::Expression { .. } |
+        Ast::IntegerLiteral { .. } |
+        Ast::Boolean { .. } |
+        Ast::NullLiteral { .. } |
+        Ast::StringLiteral { .. } => node,
+    }
+}
+
+fn fold_all(nodes: Vec<Box<Ast>>, constants: &mut HashMap<String, Ast>) -> Vec<Box<Ast>> {
+    nodes.into_iter().map(|node| Box::new(fold(*node, constants))).collect()
+}
+
+/// Names bound by let/const/struct/enum/class/import anywhere in a function body, excluding nested functions
+fn declared_names(node: &Ast) -> Vec<String> {
+    let mut names = Vec::new();
+
+    match node {
+        Ast::FunctionLiteral { .. } => return names,
+        Ast::LetStatement { ident, .. } |
+        Ast::StructDeclaration { name: ident, .. } |
+        Ast::EnumDeclaration { name: ident, .. } |
+        Ast::ClassDeclaration { name: ident, .. } => {
+            if let Ast::Identifier { ref value, .. } = **ident {
+                names.push(value.to_string());
+            }
+        },
+        Ast::EnumVariant { name, .. } => names.push(name.to_string()),
+        Ast::ImportStatement { alias, .. } => names.push(alias.to_string()),
+        Ast::ForExpression { variable, .. } => names.push(variable.to_string()),
+        _ => (),
+    }
+
+    for child in node.children() {
+        names.extend(declared_names(child));
+    }
+
+    names
+}
+
+fn is_literal(node: &Ast) -> bool {
+    match node {
+        Ast::IntegerLiteral { .. } |
+        Ast::Boolean { .. } |
+        Ast::NullLiteral { .. } |
+        Ast::StringLiteral { .. } => true,
+        _                         => false,
+    }
+}
+
+fn fold_prefix(operator: &str, right: &Ast) -> Option<Ast> {
+    match (operator, right) {
+        ("-", Ast::IntegerLiteral { value, .. }) => value.checked_neg().map(integer),
+        ("!", Ast::Boolean { value, .. })        => Some(boolean(!value)),
+        ("!", Ast::NullLiteral { .. })           => Some(boolean(true)),
+        _                                        => None,
+    }
+}
+
+fn fold_infix(operator: &str, left: &Ast, right: &Ast) -> Option<Ast> {
+    if operator == "??" {
+        return match left {
+            Ast::NullLiteral { .. }   => Some(right.clone()),
+            _ if is_literal(left)     => Some(left.clone()),
+            _                         => None,
+        };
+    }
+
+    match (left, right) {
+        (Ast::IntegerLiteral { value: l, .. }, Ast::IntegerLiteral { value: r, .. }) => match operator {
+            "+"  => l.checked_add(*r).map(integer),
+            "-"  => l.checked_sub(*r).map(integer),
+            "*"  => l.checked_mul(*r).map(integer),
+            "/"  => l.checked_div(*r).map(integer),
+            "<"  => Some(boolean(l < r)),
+            ">"  => Some(boolean(l > r)),
+            "==" => Some(boolean(l == r)),
+            "!=" => Some(boolean(l != r)),
+            _    => None,
+        },
+        (Ast::StringLiteral { value: l, .. }, Ast::StringLiteral { value: r, .. }) => match operator {
+            "+"  => Some(string(format!("{}{}", l, r))),
+            "==" => Some(boolean(l == r)),
+            "!=" => Some(boolean(l != r)),
+            _    => None,
+        },
+        (Ast::Boolean { value: l, .. }, Ast::Boolean { value: r, .. }) => match operator {
+            "==" => Some(boolean(l == r)),
+            "!=" => Some(boolean(l != r)),
+            _    => None,
+        },
+        _ => None,
+    }
+}
+
+fn integer(value: i64) -> Ast {
+    Ast::IntegerLiteral { token: Token::new(TokenKind::Integer, value.to_string()), value: value }
+}
+
+fn boolean(value: bool) -> Ast {
+    let kind = if value { TokenKind::True } else { TokenKind::False };
+    Ast::Boolean { token: Token::new(kind, value.to_string()), value: value }
+}
+
+fn string(value: String) -> Ast {
+    Ast::StringLiteral { token: Token::new(TokenKind::String, value.clone()), value: value }
+}
+
+#[test]
+fn test_fold_constants() {
+    use crate::lexier::{ Lexier };
+    use crate::parser::{ Parser };
+
+    let tests = [
+        ("1 + 2 * 3", "7"),
+        ("-(2 - 5)", "3"),
+        ("!(1 < 2)", "false"),
+        ("\"a\" + \"b\" == \"ab\"", "true"),
+        ("x + 1 * 2", "(x + 2)"),
+        ("1 / 0", "(1 / 0)"),
+        ("const a = 2 * 3; let b = a + 1;", "const a = 6;let b = 7;"),
+        ("const a = 1; fn(a) { a + 1 }", "const a = 1;fn(a) {(a + 1)}"),
+        ("const a = 1; fn(x) { let a = x; a + 1 }", "const a = 1;fn(x) {let a = x;(a + 1)}"),
+        ("const a = 1; fn(x) { x + a }", "const a = 1;fn(x) {(x + 1)}"),
+        ("const a = [1]; a[0]", "const a = [1];(a[0])"),
+        ("let a = 1; a + 1", "let a = 1;(a + 1)"),
+        ("if (c) { const a = 1; a } a", "if(c) { const a = 1;1 }a"),
+        ("const a = 1; x.a", "const a = 1;(x.a)"),
+        ("const a = 1; struct a { x } a", "const a = 1;struct a { x }a"),
+        ("const a = 1; enum E { a(x) } a", "const a = 1;enum E { a(x) }a"),
+        ("const n = null; n ?? 1 + 2", "const n = null;3"),
+        ("2 ?? x; x ?? 2", "2(x ?? 2)"),
+    ];
+
+    for test in tests.iter() {
+        let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+        let program = parser.parse_program().unwrap();
+        parser.check_parser_errors();
+
+        assert_eq!(fold_constants(program).to_string(), test.1.to_string());
+    }
+}
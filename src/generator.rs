@@ -0,0 +1,261 @@
+//! Generators and iteration.
+//!
+//! A function whose body contains a `yield` statement is a generator function: calling
+//! it returns an `Object::Generator` without running the body. Each `next` runs the body
+//! until the following `yield` and returns the yielded value.
+//!
+//! `eval` is recursive and cannot stop in the middle of a call, so a generator keeps its
+//! own stack of the blocks and loops it is executing and hands single statements to
+//! `eval`. A `yield` can therefore appear as a statement of the function body, of an
+//! `if` or `else` block or of a `for` loop body, at any depth, but not inside an
+//! expression or a `try` block. The parser rejects any other `yield` with `misplaced_yield`.
+
+use crate::ast::{ Ast };
+use crate::env::{ Env };
+use crate::eval::{ eval, finish_tail_call, loop_variable_error };
+use crate::object::{ Object, new_error_with_kind };
+use crate::range::{ Range };
+use std::cell::{ RefCell };
+use std::rc::{ Rc };
+
+#[derive(Debug)]
+pub struct Generator {
+    env: Env,
+    stack: Vec<Frame>,      // innermost last; empty when the generator is finished
+}
+
+// the statements of blocks are shared with the function body instead of copied
+#[derive(Debug)]
+enum Frame {
+    Block {
+        statements: Rc<Vec<Box<Ast>>>,
+        index: usize,                   // next statement to run
+    },
+    Loop {
+        variable: String,
+        iter: Iter,
+        body: Rc<Vec<Box<Ast>>>,        // statements run by each iteration
+    },
+}
+
+impl Generator {
+    /// Generator running body in env, the environment of the call
+    pub fn new(body: &Ast, env: Env) -> Generator {
+        Generator { env: env, stack: vec![Frame::Block { statements: statements(body), index: 0 }] }
+    }
+
+    /// Run until the next `yield`. Returns None when the body has finished, and
+    /// stops the generator on an error. Resuming counts as a call towards the call depth limit.
+    pub fn resume(&mut self) -> Option<Object> {
+        let context = self.env.context();
+        if let Some(error) = context.borrow_mut().step() {
+            return self.fail(error);
+        }
+        if !context.borrow_mut().enter_call() {
+            return self.fail(new_error_with_kind("RecursionError", "maximum recursion depth exceeded".to_string()));
+        }
+
+        let value = self.run();
+        context.borrow_mut().exit_call();
+        value
+    }
+
+    fn run(&mut self) -> Option<Object> {
+        loop {
+            let (statements, index) = match self.stack.last_mut() {
+                None => return None,
+                Some(Frame::Block { statements, index }) => {
+                    if *index == statements.len() {
+                        self.stack.pop();
+                        continue;
+                    }
+                    *index += 1;
+                    (statements.clone(), *index - 1)
+                },
+                Some(Frame::Loop { variable, iter, body }) => {
+                    match iter.next() {
+                        Some(error @ Object::Error { .. }) => return self.fail(error),
+                        Some(value) => {
+                            self.env.set(variable.to_string(), value);
+                            let body = body.clone();
+                            self.stack.push(Frame::Block { statements: body, index: 0 });
+                        },
+                        None => { self.stack.pop(); },
+                    }
+                    continue;
+                },
+            };
+
+            match self.step(&statements[index]) {
+                Some(value) => return Some(value),
+                None        => (),
+            }
+        }
+    }
+
+    /// Run one statement. Returns the value to hand out if the statement yields or fails.
+    fn step(&mut self, statement: &Ast) -> Option<Object> {
+        if !contains_yield(statement) {
+            return match eval(statement, &mut self.env) {
+                Some(error @ Object::Error { .. }) => self.fail(error),
+                Some(Object::ReturnValue { value }) => match finish_tail_call(*value) {
+                    error @ Object::Error { .. } => self.fail(error),
+                    _ => {
+                        self.stack.clear();
+                        None
+                    },
+                },
+                _ => None,
+            };
+        }
+
+        let expression = match statement {
+            Ast::ExpressionStatement { expression, .. } => &**expression,
+            other                                       => other,
+        };
+
+        match expression {
+            Ast::YieldStatement { value, .. } => {
+                let value = match eval(value, &mut self.env) {
+                    Some(value) => value,
+                    None        => Object::Null,
+                };
+                if let Object::Error { .. } = value {
+                    return self.fail(value);
+                }
+                Some(value)
+            },
+            Ast::IfExpression { condition, consequence, alternative, .. } => {
+                let condition = match eval(condition, &mut self.env) {
+                    Some(value) => value,
+                    None        => Object::Null,
+                };
+                if let Object::Error { .. } = condition {
+                    return self.fail(condition);
+                }
+
+                let block = match condition {
+                    Object::Null | Object::Boolean { value: false } => alternative,
+                    _                                               => consequence,
+                };
+                self.stack.push(Frame::Block { statements: statements(block), index: 0 });
+                None
+            },
+            Ast::ForExpression { variable, iterable, body, .. } => {
+                let iterable = match eval(iterable, &mut self.env) {
+                    Some(value) => value,
+                    None        => Object::Null,
+                };
+                let iter = match Iter::new(iterable) {
+                    Ok(value)  => value,
+                    Err(error) => return self.fail(error),
+                };
+                let name = variable.to_string();
+                if let Some(error) = loop_variable_error(&name, &self.env) {
+                    return self.fail(error);
+                }
+
+                self.stack.push(Frame::Loop { variable: name, iter: iter, body: statements(body) });
+                None
+            },
+            Ast::BlockStatement { .. } => {
+                self.stack.push(Frame::Block { statements: statements(expression), index: 0 });
+                None
+            },
+            _ => self.fail(new_error_with_kind("RuntimeError", "yield must be a statement of the generator body, an if block or a for loop".to_string())),
+        }
+    }
+
+    fn fail(&mut self, error: Object) -> Option<Object> {
+        self.stack.clear();
+        Some(error)
+    }
+}
+
+/// Statements of a block; none if block is not an Ast::BlockStatement
+fn statements(block: &Ast) -> Rc<Vec<Box<Ast>>> {
+    match block {
+        Ast::BlockStatement { statements, .. } => statements.clone(),
+        _                                      => Rc::new(Vec::new()),
+    }
+}
+
+/// Check whether node yields, not counting the bodies of nested functions
+pub fn contains_yield(node: &Ast) -> bool {
+    match node {
+        Ast::YieldStatement { .. }  => true,
+        Ast::FunctionLiteral { .. } => false,
+        _                           => node.children().iter().any(|child| contains_yield(child)),
+    }
+}
+
+/// Check whether the function body block has a `yield` that a generator cannot stop at, one that
+/// is not a statement of the body or of an `if`, `else` or `for` block in it
+pub fn misplaced_yield(block: &Ast) -> bool {
+    statements(block).iter().any(|statement| {
+        let expression = match **statement {
+            Ast::ExpressionStatement { ref expression, .. } => &**expression,
+            ref other                                       => other,
+        };
+
+        match expression {
+            Ast::YieldStatement { value, .. } => contains_yield(value),
+            Ast::IfExpression { condition, consequence, alternative, .. } =>
+                contains_yield(condition) || misplaced_yield(consequence) || misplaced_yield(alternative),
+            Ast::ForExpression { iterable, body, .. } => contains_yield(iterable) || misplaced_yield(body),
+            Ast::BlockStatement { .. } => misplaced_yield(expression),
+            other                      => contains_yield(other),
+        }
+    })
+}
+
+/// Iteration over the elements of an array, the characters of a string, the integers of a range
+/// or the values of a generator
+#[derive(Debug)]
+pub enum Iter {
+    Elements(Vec<Object>, usize),
+    Range(Range, i64),
+    Generator(Rc<RefCell<Generator>>),
+}
+
+impl Iter {
+    pub fn new(iterable: Object) -> Result<Iter, Object> {
+        match iterable {
+            Object::Array { elements } => Ok(Iter::Elements(elements, 0)),
+            Object::String { value }   => {
+                let chars = value.chars().map(|c| Object::String { value: c.to_string() }).collect();
+                Ok(Iter::Elements(chars, 0))
+            },
+            Object::Range { range }         => Ok(Iter::Range(range, 0)),
+            Object::Generator { generator } => Ok(Iter::Generator(generator)),
+            Object::Error { .. } => Err(iterable),
+            _ => Err(new_error_with_kind("TypeError", format!("{} is not iterable", iterable.kind()))),
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = Object;
+
+    /// Next element, or None at the end. An error ends the iteration after it is returned.
+    fn next(&mut self) -> Option<Object> {
+        match self {
+            Iter::Elements(elements, index) => {
+                let element = elements.get(*index).cloned();
+                *index += 1;
+                element
+            },
+            Iter::Range(range, index) => {
+                if *index >= range.len() {
+                    return None;
+                }
+                *index += 1;
+                Some(Object::Integer { value: range.get(*index - 1) })
+            },
+            Iter::Generator(generator) => match generator.try_borrow_mut() {
+                Ok(mut generator) => generator.resume(),
+                Err(_)            => Some(new_error_with_kind("RuntimeError", "generator is already running".to_string())),
+            },
+        }
+    }
+}
@@ -0,0 +1,822 @@
+//! Hindley-Milner type inference, used by `simia check --infer`.
+//!
+//! Every expression gets a type, with type variables for what is not known yet, and
+//! the constraints between them are solved by unification. A `let` binding is
+//! generalized over the variables that do not occur in the enclosing scopes, so a
+//! helper like `let id = fn(x) { x };` can be used at several types.
+//!
+//! Annotations are taken as constraints, with `any` standing for a fresh variable.
+//! Values the inference knows nothing about (builtins, imported modules, members of
+//! structs and instances) get a fresh variable at every use.
+
+use crate::ast::{ Ast, is_spread, is_variadic, parameter_name };
+use crate::generator::{ contains_yield };
+use crate::token::{ Token, TokenKind };
+use crate::types::{ Type, variadic_parameters };
+use std::collections::{ HashMap };
+
+/// Result of inference: the signatures of the top-level bindings and the type errors
+pub struct Inference {
+    pub signatures: Vec<(String, String)>,
+    pub errors: Vec<String>,    // "line:column: message"
+}
+
+/// Infer the types of program
+pub fn infer_types(program: &Ast) -> Inference {
+    let mut infer = Infer {
+        substitution: Vec::new(),
+        scopes: vec![HashMap::new()],
+        returns: Vec::new(),
+        overloaded: Vec::new(),
+        signatures: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let len = Type::Function(vec![infer.fresh()], Box::new(Type::Int));
+    let len = infer.generalize(&len);
+    infer.scopes[0].insert("len".to_string(), len);
+
+    if let Ast::Program { statements } = program {
+        for statement in statements {
+            infer.infer(statement);
+            infer.signature(statement);
+        }
+    }
+    infer.check_overloaded();
+
+    Inference { signatures: infer.signatures, errors: infer.errors }
+}
+
+/// Type with quantified variables, bound by let
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    typ: Type,
+    overloaded: Vec<(Token, String, usize)>,   // quantified variables that must be int or string
+}
+
+impl Scheme {
+    fn monomorphic(typ: Type) -> Scheme {
+        Scheme { vars: Vec::new(), typ: typ, overloaded: Vec::new() }
+    }
+}
+
+struct Infer {
+    substitution: Vec<Option<Type>>,        // binding of each type variable
+    scopes: Vec<HashMap<String, Scheme>>,
+    returns: Vec<Type>,                     // return types of the enclosing functions
+    overloaded: Vec<(Token, String, Type)>, // operands of `+`, which must be int or string
+    signatures: Vec<(String, String)>,
+    errors: Vec<String>,
+}
+
+impl Infer {
+    fn fresh(&mut self) -> Type {
+        self.substitution.push(None);
+        Type::Var(self.substitution.len() - 1)
+    }
+
+    fn error(&mut self, token: &Token, msg: String) {
+        self.errors.push(format!("{}: {}", token.location(), msg));
+    }
+
+    fn bind(&mut self, name: &str, scheme: Scheme) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), scheme);
+    }
+
+    /// Type with the solved variables replaced by their binding
+    fn resolve(&self, typ: &Type) -> Type {
+        match typ {
+            Type::Var(id) => match self.substitution[*id] {
+                Some(ref bound) => self.resolve(bound),
+                None            => typ.clone(),
+            },
+            Type::Array(element) => Type::Array(Box::new(self.resolve(element))),
+            Type::Function(parameters, result) =>
+                Type::Function(parameters.iter().map(|p| self.resolve(p)).collect(), Box::new(self.resolve(result))),
+            Type::Variadic(parameters, result) =>
+                Type::Variadic(parameters.iter().map(|p| self.resolve(p)).collect(), Box::new(self.resolve(result))),
+            _ => typ.clone(),
+        }
+    }
+
+    fn occurs(&self, id: usize, typ: &Type) -> bool {
+        match self.resolve(typ) {
+            Type::Var(other) => id == other,
+            Type::Array(element) => self.occurs(id, &element),
+            Type::Function(parameters, result) | Type::Variadic(parameters, result) =>
+                parameters.iter().any(|p| self.occurs(id, p)) || self.occurs(id, &result),
+            _ => false,
+        }
+    }
+
+    fn unify(&mut self, left: &Type, right: &Type) -> bool {
+        let left = self.resolve(left);
+        let right = self.resolve(right);
+
+        match (&left, &right) {
+            (Type::Var(l), Type::Var(r)) if l == r => true,
+            (Type::Var(id), other) | (other, Type::Var(id)) => {
+                if self.occurs(*id, other) {
+                    return false;
+                }
+                self.substitution[*id] = Some(other.clone());
+                true
+            },
+            (Type::Array(l), Type::Array(r)) => self.unify(l, r),
+            (Type::Variadic(vp, vr), Type::Function(fp, fr)) | (Type::Function(fp, fr), Type::Variadic(vp, vr)) => {
+                let element = self.fresh();
+                if !self.unify_arguments(vp, &element, fp) {
+                    return false;
+                }
+                self.unify(vr, fr)
+            },
+            (Type::Function(lp, lr), Type::Function(rp, rr)) | (Type::Variadic(lp, lr), Type::Variadic(rp, rr)) => {
+                if lp.len() != rp.len() {
+                    return false;
+                }
+                for (l, r) in lp.iter().zip(rp) {
+                    if !self.unify(l, r) {
+                        return false;
+                    }
+                }
+                self.unify(lr, rr)
+            },
+            _ => left == right,
+        }
+    }
+
+    /// Unify the parameters of a variadic function with the arguments of a call, the arguments
+    /// past the fixed parameters with element, the element type of the rest parameter
+    fn unify_arguments(&mut self, parameters: &[Type], element: &Type, arguments: &[Type]) -> bool {
+        let (rest, fixed) = match parameters.split_last() {
+            Some(split) => split,
+            None        => return false,
+        };
+        if arguments.len() < fixed.len() || !self.unify(rest, &Type::Array(Box::new(element.clone()))) {
+            return false;
+        }
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let parameter = if i < fixed.len() { &fixed[i] } else { element };
+            if !self.unify(parameter, argument) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Unify actual with expected, reporting "msg: expected X, got Y" on failure
+    fn expect(&mut self, token: &Token, expected: &Type, actual: &Type, msg: &str) -> bool {
+        if self.unify(expected, actual) {
+            return true;
+        }
+
+        let mut names = HashMap::new();
+        let expected = self.show(expected, &mut names);
+        let actual = self.show(actual, &mut names);
+        self.error(token, format!("{}: expected {}, got {}", msg, expected, actual));
+        false
+    }
+
+    fn free_vars(&self, typ: &Type, vars: &mut Vec<usize>) {
+        match self.resolve(typ) {
+            Type::Var(id) => if !vars.contains(&id) { vars.push(id) },
+            Type::Array(element) => self.free_vars(&element, vars),
+            Type::Function(parameters, result) | Type::Variadic(parameters, result) => {
+                for parameter in parameters.iter() {
+                    self.free_vars(parameter, vars);
+                }
+                self.free_vars(&result, vars);
+            },
+            _ => (),
+        }
+    }
+
+    fn generalize(&self, typ: &Type) -> Scheme {
+        let mut bound = Vec::new();
+        for scope in self.scopes.iter() {
+            for scheme in scope.values() {
+                let mut vars = Vec::new();
+                self.free_vars(&scheme.typ, &mut vars);
+                bound.extend(vars.into_iter().filter(|id| !scheme.vars.contains(id)));
+            }
+        }
+
+        let mut vars = Vec::new();
+        self.free_vars(typ, &mut vars);
+        vars.retain(|id| !bound.contains(id));
+
+        // the operands of `+` keep their constraint in every instance
+        let mut overloaded = Vec::new();
+        for (token, operator, operand) in self.overloaded.iter() {
+            if let Type::Var(id) = self.resolve(operand) {
+                if vars.contains(&id) {
+                    overloaded.push((token.clone(), operator.to_string(), id));
+                }
+            }
+        }
+
+        Scheme { vars: vars, typ: self.resolve(typ), overloaded: overloaded }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let mut mapping = HashMap::new();
+        for id in scheme.vars.iter() {
+            let var = self.fresh();
+            mapping.insert(*id, var);
+        }
+
+        for (token, operator, id) in scheme.overloaded.iter() {
+            self.overloaded.push((token.clone(), operator.to_string(), mapping[id].clone()));
+        }
+
+        replace_vars(&scheme.typ, &mapping)
+    }
+
+    fn lookup(&mut self, name: &str) -> Type {
+        let scheme = match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(value) => value.clone(),
+            None        => return self.fresh(),
+        };
+
+        self.instantiate(&scheme)
+    }
+
+    /// Type named by annotation, with a fresh variable for every omitted or `any` type
+    fn annotation(&mut self, annotation: &Ast) -> Type {
+        let typ = Type::from_annotation(annotation);
+        self.replace_any(&typ)
+    }
+
+    fn replace_any(&mut self, typ: &Type) -> Type {
+        match typ {
+            Type::Any => self.fresh(),
+            Type::Array(element) => Type::Array(Box::new(self.replace_any(element))),
+            Type::Function(parameters, result) => {
+                let parameters = parameters.iter().map(|p| self.replace_any(p)).collect();
+                Type::Function(parameters, Box::new(self.replace_any(result)))
+            },
+            Type::Variadic(parameters, result) => {
+                let parameters = parameters.iter().map(|p| self.replace_any(p)).collect();
+                Type::Variadic(parameters, Box::new(self.replace_any(result)))
+            },
+            _ => typ.clone(),
+        }
+    }
+
+    /// Type written with the variables named a, b, c, .. in order of appearance
+    fn show(&self, typ: &Type, names: &mut HashMap<usize, String>) -> String {
+        match self.resolve(typ) {
+            Type::Var(id) => {
+                let next = names.len();
+                names.entry(id).or_insert_with(|| variable_name(next)).to_string()
+            },
+            Type::Array(element) => format!("[{}]", self.show(&element, names)),
+            Type::Function(parameters, result) => {
+                let parameters: Vec<String> = parameters.iter().map(|p| self.show(p, names)).collect();
+                format!("fn({}) -> {}", parameters.join(", "), self.show(&result, names))
+            },
+            Type::Variadic(parameters, result) => {
+                let parameters: Vec<String> = parameters.iter().map(|p| self.show(p, names)).collect();
+                format!("fn({}) -> {}", variadic_parameters(parameters), self.show(&result, names))
+            },
+            other => other.to_string(),
+        }
+    }
+
+    /// Record the signature of a top-level let binding
+    fn signature(&mut self, statement: &Ast) {
+        let ident = match statement {
+            Ast::LetStatement { ident, .. } => ident,
+            Ast::ExportStatement { statement, .. } => return self.signature(statement),
+            _ => return,
+        };
+
+        let name = ident.to_string();
+        let typ = match self.scopes[0].get(&name) {
+            Some(scheme) => scheme.typ.clone(),
+            None         => return,
+        };
+        let typ = self.show(&typ, &mut HashMap::new());
+        self.signatures.push((name, typ));
+    }
+
+    /// Report `+` applied to operands that turned out not to be int or string
+    fn check_overloaded(&mut self) {
+        for (token, operator, typ) in self.overloaded.clone() {
+            match self.resolve(&typ) {
+                Type::Int | Type::String | Type::Var(_) => (),
+                other => self.error(&token, format!("unknown operator: {} {} {}", other.to_string(), operator, other.to_string())),
+            }
+        }
+    }
+
+    fn infer_block(&mut self, statements: &Vec<Box<Ast>>) -> Type {
+        self.scopes.push(HashMap::new());
+        let mut typ = self.fresh();
+        for statement in statements {
+            typ = match **statement {
+                Ast::ExpressionStatement { .. } => self.infer(statement),
+                _ => {
+                    self.infer(statement);
+                    self.fresh()
+                },
+            };
+        }
+        self.scopes.pop();
+
+        typ
+    }
+
+    /// Infer the type of the value of node
+    fn infer(&mut self, node: &Ast) -> Type {
+        match node {
+            Ast::Program { statements } => self.infer_block(statements),
+            Ast::BlockStatement { statements, .. } => self.infer_block(statements),
+            Ast::ExpressionStatement { expression, .. } => self.infer(expression),
+            Ast::IntegerLiteral { .. } => Type::Int,
+            Ast::StringLiteral { .. } => Type::String,
+            Ast::Boolean { .. } => Type::Bool,
+            Ast::NullLiteral { .. } => Type::Null,
+            Ast::Identifier { value, .. } => self.lookup(value),
+            Ast::LetStatement { ident, annotation, value, .. } => {
+                let name = ident.to_string();
+                let declared = self.annotation(annotation);
+
+                // a function can call itself by name
+                self.scopes.push(HashMap::new());
+                if let Ast::FunctionLiteral { .. } = **value {
+                    self.bind(&name, Scheme::monomorphic(declared.clone()));
+                }
+                let typ = self.infer(value);
+                self.scopes.pop();
+
+                self.expect(ident_token(ident), &declared, &typ, &format!("cannot initialize {}", name));
+                let scheme = self.generalize(&declared);
+                self.bind(&name, scheme);
+                declared
+            },
+            Ast::ReturnStatement { token, return_value } => {
+                let typ = self.infer(return_value);
+                if let Some(expected) = self.returns.last().cloned() {
+                    self.expect(token, &expected, &typ, "wrong return type");
+                }
+                self.fresh()
+            },
+            Ast::PrefixExpression { token, operator, right } => {
+                let right = self.infer(right);
+                match operator.as_ref() {
+                    "-" => {
+                        if !self.unify(&Type::Int, &right) {
+                            let right = self.show(&right, &mut HashMap::new());
+                            self.error(token, format!("unknown operator: -{}", right));
+                        }
+                        Type::Int
+                    },
+                    _   => Type::Bool,
+                }
+            },
+            Ast::InfixExpression { token, left, operator, right } => {
+                let left = self.infer(left);
+                let right = self.infer(right);
+                self.infer_infix(token, operator, left, right)
+            },
+            Ast::IfExpression { condition, consequence, alternative, .. } => {
+                self.infer(condition);
+                let consequence = self.infer(consequence);
+                if let Ast::Expression { .. } = **alternative {
+                    return self.fresh();
+                }
+
+                let alternative = self.infer(alternative);
+                if !self.unify(&consequence, &alternative) {
+                    return self.fresh();
+                }
+                consequence
+            },
+            Ast::FunctionLiteral { parameters, parameter_types, return_type, body, .. } => {
+                let mut types = Vec::new();
+                self.scopes.push(HashMap::new());
+                for (parameter, annotation) in parameters.iter().zip(parameter_types) {
+                    let typ = self.annotation(annotation);
+                    if let Ast::SpreadExpression { ref token, .. } = **parameter {
+                        let element = self.fresh();
+                        self.expect(token, &Type::Array(Box::new(element)), &typ, "variadic parameter");
+                    }
+                    self.bind(&parameter_name(parameter), Scheme::monomorphic(typ.clone()));
+                    types.push(typ);
+                }
+
+                // a generator function returns a generator, whatever its body returns
+                let generator = contains_yield(body);
+                let mut result = self.annotation(return_type);
+                if generator {
+                    result = self.fresh();
+                }
+
+                self.returns.push(result.clone());
+                let value = self.infer(body);
+                if let (false, Ast::BlockStatement { ref token, ref statements }) = (generator, &**body) {
+                    // the value of the last expression is returned implicitly
+                    if let Some(Ast::ExpressionStatement { .. }) = statements.last().map(|s| &**s) {
+                        self.expect(token, &result, &value, "wrong return type");
+                    }
+                }
+                self.returns.pop();
+                self.scopes.pop();
+
+                if is_variadic(parameters) {
+                    return Type::Variadic(types, Box::new(result));
+                }
+                Type::Function(types, Box::new(result))
+            },
+            Ast::CallExpression { token, function, arguments } => {
+                let function = self.infer(function);
+                let spread = arguments.iter().position(|a| is_spread(a));
+                let arguments: Vec<Type> = arguments.iter().map(|a| match **a {
+                    Ast::SpreadExpression { ref value, .. } => {
+                        let iterable = self.infer(value);
+                        self.element_type(&iterable)
+                    },
+                    _ => self.infer(a),
+                }).collect();
+                if let Some(first) = spread {
+                    return self.infer_spread_call(token, &function, &arguments, first);
+                }
+
+                match self.resolve(&function) {
+                    Type::Function(parameters, result) => {
+                        if parameters.len() != arguments.len() {
+                            self.error(token, format!("wrong number of arguments. got={}, want={}",
+                                                      arguments.len(), parameters.len()));
+                            return self.fresh();
+                        }
+                        for (i, (parameter, argument)) in parameters.iter().zip(&arguments).enumerate() {
+                            self.expect(token, parameter, argument, &format!("argument {}", i + 1));
+                        }
+                        *result
+                    },
+                    Type::Variadic(parameters, result) => {
+                        let fixed = parameters.len() - 1;
+                        if arguments.len() < fixed {
+                            self.error(token, format!("wrong number of arguments. got={}, want={} or more", arguments.len(), fixed));
+                            return self.fresh();
+                        }
+                        let element = self.fresh();
+                        self.unify(&parameters[fixed], &Type::Array(Box::new(element.clone())));
+                        for (i, argument) in arguments.iter().enumerate() {
+                            let parameter = if i < fixed { &parameters[i] } else { &element };
+                            self.expect(token, parameter, argument, &format!("argument {}", i + 1));
+                        }
+                        *result
+                    },
+                    Type::Var(_) => {
+                        let result = self.fresh();
+                        // fails only for a function applied to itself, which needs an infinite type
+                        self.expect(token, &function, &Type::Function(arguments, Box::new(result.clone())), "cannot call");
+                        result
+                    },
+                    other => {
+                        self.error(token, format!("not a function: {}", other.to_string()));
+                        self.fresh()
+                    },
+                }
+            },
+            Ast::ArrayLiteral { token, elements } => {
+                let element = self.fresh();
+                for e in elements {
+                    let typ = match **e {
+                        Ast::SpreadExpression { ref value, .. } => {
+                            let iterable = self.infer(value);
+                            self.element_type(&iterable)
+                        },
+                        _ => self.infer(e),
+                    };
+                    self.expect(token, &element, &typ, "array elements must have the same type");
+                }
+                Type::Array(Box::new(element))
+            },
+            Ast::IndexExpression { token, left, index } => {
+                let left = self.infer(left);
+                let index = self.infer(index);
+                self.expect(token, &Type::Int, &index, "index");
+
+                match self.resolve(&left) {
+                    Type::String => return Type::String,
+                    Type::Range  => return Type::Int,
+                    Type::Null if token.kind == TokenKind::QuestionLbracket => return self.fresh(),
+                    _            => (),
+                }
+                let element = self.fresh();
+                self.expect(token, &Type::Array(Box::new(element.clone())), &left, "index operator");
+                element
+            },
+            Ast::SliceExpression { token, left, start, end } => {
+                for bound in [start, end] {
+                    if let Ast::Expression { .. } = **bound {
+                        continue;
+                    }
+                    let typ = self.infer(bound);
+                    self.expect(token, &Type::Int, &typ, "slice bound");
+                }
+
+                let left = self.infer(left);
+                if self.resolve(&left) == Type::String {
+                    return Type::String;
+                }
+                let element = self.fresh();
+                self.expect(token, &Type::Array(Box::new(element)), &left, "slice operator");
+                left
+            },
+            Ast::AssignExpression { target, value, .. } => {
+                let typ = self.infer(value);
+                match **target {
+                    Ast::Identifier { ref token, ref value } => {
+                        let declared = self.lookup(value);
+                        self.expect(token, &declared, &typ, &format!("cannot assign to {}", value));
+                    },
+                    _ => { self.infer(target); },
+                }
+                typ
+            },
+            Ast::MemberExpression { object, .. } => {
+                self.infer(object);
+                self.fresh()
+            },
+            Ast::SpreadExpression { value, .. } => self.infer(value),
+            Ast::ThrowStatement { value, .. } |
+            Ast::YieldStatement { value, .. } => {
+                self.infer(value);
+                self.fresh()
+            },
+            Ast::ForExpression { variable, iterable, body, .. } => {
+                let iterable = self.infer(iterable);
+                let element = self.element_type(&iterable);
+
+                self.scopes.push(HashMap::new());
+                self.bind(&variable.to_string(), Scheme::monomorphic(element));
+                self.infer(body);
+                self.scopes.pop();
+                Type::Null
+            },
+            Ast::RangeExpression { token, start, end, step } => {
+                for bound in [start, end, step] {
+                    if let Ast::Expression { .. } = **bound {
+                        continue;
+                    }
+                    let typ = self.infer(bound);
+                    self.expect(token, &Type::Int, &typ, "range bound");
+                }
+                Type::Range
+            },
+            Ast::ListComprehension { element, clauses, .. } => {
+                self.scopes.push(HashMap::new());
+                for clause in clauses {
+                    self.infer(clause);
+                }
+                let element = self.infer(element);
+                self.scopes.pop();
+                Type::Array(Box::new(element))
+            },
+            Ast::ComprehensionClause { token, variable, value } => {
+                let value = self.infer(value);
+                if token.kind == TokenKind::For {
+                    let element = self.element_type(&value);
+                    self.bind(&variable.to_string(), Scheme::monomorphic(element));
+                }
+                Type::Null
+            },
+            Ast::TryExpression { block, parameter, catch, finally, .. } => {
+                self.infer(block);
+                if let Ast::BlockStatement { ref statements, .. } = **catch {
+                    self.scopes.push(HashMap::new());
+                    let typ = self.fresh();
+                    self.bind(&parameter.to_string(), Scheme::monomorphic(typ));
+                    self.infer_block(statements);
+                    self.scopes.pop();
+                }
+                if let Ast::BlockStatement { .. } = **finally {
+                    self.infer(finally);
+                }
+                self.fresh()
+            },
+            Ast::StructDeclaration { name, fields, .. } => {
+                let parameters = fields.iter().map(|_| self.fresh()).collect();
+                let constructor = Type::Function(parameters, Box::new(Type::Named(name.to_string())));
+                let scheme = self.generalize(&constructor);
+                self.bind(&name.to_string(), scheme);
+                self.fresh()
+            },
+            Ast::EnumDeclaration { name, .. } |
+            Ast::ClassDeclaration { name, .. } => {
+                if let Ast::ClassDeclaration { ref superclass, ref methods, .. } = *node {
+                    if let Ast::Identifier { .. } = **superclass {
+                        self.infer(superclass);
+                    }
+
+                    self.scopes.push(HashMap::new());
+                    self.bind("self", Scheme::monomorphic(Type::Named(name.to_string())));
+                    for method in methods {
+                        self.infer(method);
+                    }
+                    self.scopes.pop();
+                }
+
+                // constructors and variants are not typed, every use is unrelated to the others
+                let typ = self.fresh();
+                let scheme = self.generalize(&typ);
+                self.bind(&name.to_string(), scheme);
+                self.fresh()
+            },
+            Ast::MethodDefinition { function, .. } => self.infer(function),
+            Ast::ImportStatement { alias, .. } => {
+                let typ = self.fresh();
+                let scheme = self.generalize(&typ);
+                self.bind(&alias.to_string(), scheme);
+                self.fresh()
+            },
+            Ast::ImportExpression { path, .. } => {
+                self.infer(path);
+                self.fresh()
+            },
+            Ast::ExportStatement { statement, .. } => self.infer(statement),
+            Ast::InfixDeclaration { value, .. } => {
+                self.infer(value);
+                self.fresh()
+            },
+            Ast::EnumVariant { .. } |
+            Ast::TypeExpression { .. } |
+            Ast::Expression { .. } => self.fresh(),
+        }
+    }
+
+    /// Type of the loop variable iterating over a value of type iterable
+    /// Type of a call with spread arguments: an argument from the first spread on may fill any
+    /// parameter from that position on, so it must fit all of them
+    fn infer_spread_call(&mut self, token: &Token, function: &Type, arguments: &[Type], first: usize) -> Type {
+        let (parameters, result) = match self.resolve(function) {
+            Type::Function(parameters, result) => (parameters, result),
+            Type::Variadic(mut parameters, result) => {
+                // the rest parameter stands for any number of its elements
+                let element = self.fresh();
+                if let Some(rest) = parameters.pop() {
+                    self.unify(&rest, &Type::Array(Box::new(element.clone())));
+                }
+                parameters.push(element);
+                (parameters, result)
+            },
+            _ => return self.fresh(),
+        };
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let reachable = if i < first { parameters.iter().skip(i).take(1) } else { parameters.iter().skip(first).take(parameters.len()) };
+            for parameter in reachable {
+                if !self.expect(token, parameter, argument, &format!("argument {}", i + 1)) {
+                    break;
+                }
+            }
+        }
+        *result
+    }
+
+    fn element_type(&mut self, iterable: &Type) -> Type {
+        match self.resolve(iterable) {
+            Type::Array(element) => *element,
+            Type::String         => Type::String,
+            Type::Range          => Type::Int,
+            _                    => self.fresh(),
+        }
+    }
+
+    fn infer_infix(&mut self, token: &Token, operator: &str, left: Type, right: Type) -> Type {
+        // user-defined operators may take any operands
+        if token.kind == TokenKind::Operator {
+            return self.fresh();
+        }
+
+        if operator == "in" {
+            return Type::Bool;
+        }
+
+        // any value can be compared with null
+        if (operator == "==" || operator == "!=") && (self.resolve(&left) == Type::Null || self.resolve(&right) == Type::Null) {
+            return Type::Bool;
+        }
+
+        // `a ?? b` is b when a is null, otherwise both sides have the same type
+        if operator == "??" && self.resolve(&left) == Type::Null {
+            return right;
+        }
+
+        if !self.unify(&left, &right) {
+            let mut names = HashMap::new();
+            let left = self.show(&left, &mut names);
+            let right = self.show(&right, &mut names);
+            self.error(token, format!("type mismatch: {} {} {}", left, operator, right));
+            return self.fresh();
+        }
+
+        match operator {
+            "+" => {
+                self.overloaded.push((token.clone(), operator.to_string(), left.clone()));
+                left
+            },
+            "-" | "*" | "/" | "<" | ">" => {
+                if !self.unify(&left, &Type::Int) {
+                    let left = self.show(&left, &mut HashMap::new());
+                    self.error(token, format!("unknown operator: {} {} {}", left, operator, left));
+                }
+                if operator == "<" || operator == ">" { Type::Bool } else { Type::Int }
+            },
+            "??" => left,
+            _ => Type::Bool,
+        }
+    }
+}
+
+fn replace_vars(typ: &Type, mapping: &HashMap<usize, Type>) -> Type {
+    match typ {
+        Type::Var(id) => match mapping.get(id) {
+            Some(var) => var.clone(),
+            None      => typ.clone(),
+        },
+        Type::Array(element) => Type::Array(Box::new(replace_vars(element, mapping))),
+        Type::Function(parameters, result) =>
+            Type::Function(parameters.iter().map(|p| replace_vars(p, mapping)).collect(),
+                           Box::new(replace_vars(result, mapping))),
+        Type::Variadic(parameters, result) =>
+            Type::Variadic(parameters.iter().map(|p| replace_vars(p, mapping)).collect(),
+                           Box::new(replace_vars(result, mapping))),
+        _ => typ.clone(),
+    }
+}
+
+/// a, b, .., z, a1, b1, ..
+fn variable_name(n: usize) -> String {
+    let letter = (b'a' + (n % 26) as u8) as char;
+    match n / 26 {
+        0     => letter.to_string(),
+        round => format!("{}{}", letter, round),
+    }
+}
+
+fn ident_token(ident: &Ast) -> &Token {
+    match ident {
+        Ast::Identifier { token, .. } => token,
+        _                             => panic!("let binds an identifier"),
+    }
+}
+
+#[test]
+fn test_infer_types() {
+    use crate::lexier::{ Lexier };
+    use crate::parser::{ Parser };
+
+    let tests: Vec<(&str, Vec<&str>, Vec<&str>)> = vec![
+        ("let x = 1; let s = \"a\" + \"b\"; let xs = [true];", vec!["x: int", "s: string", "xs: [bool]"], vec![]),
+        ("let id = fn(x) { x }; let a = id(1); let b = id(\"s\");", vec!["id: fn(a) -> a", "a: int", "b: string"], vec![]),
+        ("let compose = fn(f, g) { fn(x) { f(g(x)) } };",
+         vec!["compose: fn(fn(a) -> b, fn(c) -> a) -> fn(c) -> b"], vec![]),
+        ("let head = fn(xs) { xs[0] }; let n = head([1]) + len(\"ab\");", vec!["head: fn([a]) -> a", "n: int"], vec![]),
+        ("let fact = fn(n) { if (n < 2) { return 1; } n * fact(n - 1) };", vec!["fact: fn(int) -> int"], vec![]),
+        ("let f = fn(a, b: string) -> any { b }; export let g = fn(x) { f(x, x) };",
+         vec!["f: fn(a, string) -> string", "g: fn(string) -> string"], vec![]),
+        ("struct P { x } let p = P(1); let q = P(\"a\");", vec!["p: P", "q: P"], vec![]),
+        ("let inc = fn(n) { n + 1 }; inc(\"a\")", vec!["inc: fn(int) -> int"], vec!["1:31: argument 1: expected int, got string"]),
+        ("let x: string = 1 - 2;", vec!["x: string"], vec!["1:5: cannot initialize x: expected string, got int"]),
+        ("let x = 1; x = \"a\"", vec!["x: int"], vec!["1:12: cannot assign to x: expected int, got string"]),
+        ("let f = fn(g) { g(g) };", vec!["f: fn(a) -> b"], vec!["1:18: cannot call: expected a, got fn(a) -> b"]),
+        ("[1, \"a\"]; true + false; -\"a\"; 1(2)",
+         vec![], vec!["1:1: array elements must have the same type: expected int, got string",
+                      "1:25: unknown operator: -string", "1:32: not a function: int", "1:16: unknown operator: bool + bool"]),
+        ("let pairs = fn(n) { [[x, y] for x in [1, n] for y in [n] if x != y] }; let cs = [c + \"!\" for c in \"ab\"];",
+         vec!["pairs: fn(int) -> [[int]]", "cs: [string]"], vec![]),
+        ("let evens = fn(n) { [i for i in 0..n step 2 if i in 1..=n] }; let r = 0..\"a\";",
+         vec!["evens: fn(int) -> [int]", "r: range"], vec!["1:72: range bound: expected int, got string"]),
+        ("let d = fn(x) { x ?? 0 }; let e = null ?? \"a\"; let t = 1 != null; 1 ?? \"a\"",
+         vec!["d: fn(int) -> int", "e: string", "t: bool"], vec!["1:69: type mismatch: int ?? string"]),
+        ("let add = fn(a, b) { a + b }; let s = add(true, false); let n = add(1, 2); let t = add(\"a\", \"b\");",
+         vec!["add: fn(a, a) -> a", "s: bool", "n: int", "t: string"], vec!["1:24: unknown operator: bool + bool"]),
+        ("let g = fn(x, ...r) { x }; let y = g + 1; let z = g(1, 2, 3); let s = g(\"a\", ...[1]); g(); let h = fn(...r) { r[0] + 1 }; h(1, \"a\");",
+         vec!["g: fn(a, ...[b]) -> a", "y: a", "z: int", "s: string", "h: fn(...[int]) -> int"],
+         vec!["1:38: type mismatch: fn(a, ...[b]) -> a + int", "1:88: wrong number of arguments. got=0, want=1 or more",
+              "1:124: argument 2: expected int, got string"]),
+        ("let apply = fn(f) { f(1, 2) }; let k = apply(fn(...r) { r }); apply(fn(x, y, ...r) { x });",
+         vec!["apply: fn(fn(int, int) -> a) -> a", "k: [int]"], vec![]),
+        ("let f = fn(a, b) { a }; f(1)", vec!["f: fn(a, b) -> a"], vec!["1:26: wrong number of arguments. got=1, want=2"]),
+        ("let xs = [0, ...1..3]; let add = fn(a, b) { a + b }; let n = add(...xs); let f = fn(x, ...r) { [x, ...r] }; [...xs, \"a\"]",
+         vec!["xs: [int]", "add: fn(a, a) -> a", "n: int", "f: fn(a, ...[a]) -> [a]"], vec!["1:109: array elements must have the same type: expected int, got string"]),
+    ];
+
+    for test in tests.iter() {
+        let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+        let program = parser.parse_program().unwrap();
+        parser.check_parser_errors();
+
+        let inference = infer_types(&program);
+        let signatures: Vec<String> = inference.signatures.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+        assert_eq!(signatures, test.1, "{}", test.0);
+        assert_eq!(inference.errors, test.2, "{}", test.0);
+    }
+}
@@ -0,0 +1,324 @@
+/// Minimal JSON value used by AST serialization.
+/// Numbers are restricted to integers because the AST has no other numeric type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),   // keeps key order
+}
+
+impl Json {
+    /// Look up a key of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _                     => None,
+        }
+    }
+
+    /// Serialize into compact JSON text
+    pub fn to_string(&self) -> String {
+        match self {
+            Json::Null           => "null".to_string(),
+            Json::Boolean(value) => value.to_string(),
+            Json::Integer(value) => value.to_string(),
+            Json::String(value)  => quote(value),
+            Json::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                format!("[{}]", elements.join(","))
+            },
+            Json::Object(members) => {
+                let members: Vec<String> = members.iter()
+                    .map(|(k, v)| format!("{}:{}", quote(k), v.to_string()))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            },
+        }
+    }
+
+    /// Serialize into indented JSON text
+    pub fn to_pretty_string(&self) -> String {
+        let mut string = String::new();
+        self.write_pretty(&mut string, 0);
+
+        string
+    }
+
+    fn write_pretty(&self, string: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth + 1);
+        match self {
+            Json::Array(elements) if elements.len() != 0 => {
+                string.push_str("[\n");
+                for (i, element) in elements.iter().enumerate() {
+                    if i != 0 {
+                        string.push_str(",\n");
+                    }
+                    string.push_str(&indent);
+                    element.write_pretty(string, depth + 1);
+                }
+                string.push_str(&format!("\n{}]", "  ".repeat(depth)));
+            },
+            Json::Object(members) if members.len() != 0 => {
+                string.push_str("{\n");
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i != 0 {
+                        string.push_str(",\n");
+                    }
+                    string.push_str(&format!("{}{}: ", indent, quote(key)));
+                    value.write_pretty(string, depth + 1);
+                }
+                string.push_str(&format!("\n{}}}", "  ".repeat(depth)));
+            },
+            _ => string.push_str(&self.to_string()),
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut string = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"'  => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            c if (c as u32) < 0x20 => string.push_str(&format!("\\u{:04x}", c as u32)),
+            c    => string.push(c),
+        }
+    }
+    string.push('"');
+
+    string
+}
+
+/// Parse JSON text
+pub fn parse(input: &str) -> Result<Json, String> {
+    let mut reader = Reader { input: input.chars().collect(), position: 0 };
+    let value = reader.read_value()?;
+
+    reader.skip();
+    if reader.position < reader.input.len() {
+        return Err(format!("unexpected character '{}' at {}", reader.input[reader.position], reader.position));
+    }
+
+    Ok(value)
+}
+
+struct Reader {
+    input: Vec<char>,
+    position: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn skip(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip();
+        match self.peek() {
+            Some(ch) if ch == expected => {
+                self.position += 1;
+                Ok(())
+            },
+            Some(ch) => Err(format!("expected '{}', got '{}' at {}", expected, ch, self.position)),
+            None     => Err(format!("expected '{}', got end of input", expected)),
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Json, String> {
+        self.skip();
+        match self.peek() {
+            Some('n') => self.read_keyword("null", Json::Null),
+            Some('t') => self.read_keyword("true", Json::Boolean(true)),
+            Some('f') => self.read_keyword("false", Json::Boolean(false)),
+            Some('"') => Ok(Json::String(self.read_string()?)),
+            Some('[') => self.read_array(),
+            Some('{') => self.read_object(),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.read_integer(),
+            Some(ch)  => Err(format!("unexpected character '{}' at {}", ch, self.position)),
+            None      => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn read_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            if self.peek() != Some(expected) {
+                return Err(format!("invalid literal at {}", self.position));
+            }
+            self.position += 1;
+        }
+
+        Ok(value)
+    }
+
+    fn read_integer(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        if self.peek() == Some('-') {
+            self.position += 1;
+        }
+        while let Some(ch) = self.peek() {
+            if !ch.is_ascii_digit() {
+                break;
+            }
+            self.position += 1;
+        }
+
+        if let Some(ch) = self.peek() {
+            if ch == '.' || ch == 'e' || ch == 'E' {
+                return Err(format!("only integer numbers are supported at {}", start));
+            }
+        }
+
+        let literal: String = self.input[start..self.position].iter().collect();
+        match literal.parse::<i64>() {
+            Ok(value) => Ok(Json::Integer(value)),
+            Err(_)    => Err(format!("invalid integer {} at {}", literal, start)),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            let ch = match self.peek() {
+                Some(ch) => ch,
+                None     => return Err("unterminated string".to_string()),
+            };
+            self.position += 1;
+
+            match ch {
+                '"'  => return Ok(string),
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some(ch) => ch,
+                        None     => return Err("unterminated string".to_string()),
+                    };
+                    self.position += 1;
+                    match escaped {
+                        '"'  => string.push('"'),
+                        '\\' => string.push('\\'),
+                        '/'  => string.push('/'),
+                        'b'  => string.push('\u{8}'),
+                        'f'  => string.push('\u{c}'),
+                        'n'  => string.push('\n'),
+                        'r'  => string.push('\r'),
+                        't'  => string.push('\t'),
+                        'u'  => string.push(self.read_unicode_escape()?),
+                        c    => return Err(format!("invalid escape \\{} at {}", c, self.position)),
+                    }
+                },
+                c    => string.push(c),
+            }
+        }
+    }
+
+    fn read_hex4(&mut self) -> Result<u32, String> {
+        if self.position + 4 > self.input.len() {
+            return Err("truncated unicode escape".to_string());
+        }
+        let hex: String = self.input[self.position..self.position + 4].iter().collect();
+        self.position += 4;
+
+        u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid unicode escape \\u{}", hex))
+    }
+
+    fn read_unicode_escape(&mut self) -> Result<char, String> {
+        let mut code = self.read_hex4()?;
+
+        // surrogate pair
+        if code >= 0xd800 && code < 0xdc00 {
+            if self.peek() != Some('\\') || self.input.get(self.position + 1) != Some(&'u') {
+                return Err("unpaired surrogate in unicode escape".to_string());
+            }
+            self.position += 2;
+            let low = self.read_hex4()?;
+            code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+        }
+
+        std::char::from_u32(code).ok_or(format!("invalid unicode escape {:x}", code))
+    }
+
+    fn read_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+
+        self.skip();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(elements));
+        }
+
+        loop {
+            elements.push(self.read_value()?);
+            self.skip();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(elements));
+                },
+                _ => return Err(format!("expected ',' or ']' at {}", self.position)),
+            }
+        }
+    }
+
+    fn read_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+
+        self.skip();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            self.skip();
+            let key = self.read_string()?;
+            self.expect(':')?;
+            let value = self.read_value()?;
+            members.push((key, value));
+
+            self.skip();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                },
+                _ => return Err(format!("expected ',' or '}}' at {}", self.position)),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_json_round_trip() {
+    let input = r#"{"a": [1, -2, true, false, null], "b": {"c": "x\"y\\z\né😀"}, "d": []}"#;
+    let json = parse(input).unwrap();
+
+    assert_eq!(json.get("a"), Some(&Json::Array(vec![Json::Integer(1), Json::Integer(-2), Json::Boolean(true),
+                                                    Json::Boolean(false), Json::Null])));
+    assert_eq!(json.get("b").unwrap().get("c"), Some(&Json::String("x\"y\\z\né😀".to_string())));
+    assert_eq!(parse(&json.to_string()).unwrap(), json);
+    assert_eq!(parse(&json.to_pretty_string()).unwrap(), json);
+
+    assert!(parse("[1, 2").is_err());
+    assert!(parse("1.5").is_err());
+    assert!(parse("{} x").is_err());
+}
@@ -3,19 +3,25 @@ use crate::token::{ TokenKind, Token };
 /// Lexical Analyzer
 #[derive(Debug, Clone)]
 pub struct Lexier {
-    input: String, 
+    input: Vec<char>,
     position: usize,
     read_position: usize,
     ch: char,
+    line: usize,                // position of ch
+    column: usize,
+    operators: Vec<String>,
 }
 
 impl Lexier {
     pub fn new(input: String) -> Lexier {
         let mut lexier: Lexier;
-        lexier = Lexier { input: input,
+        lexier = Lexier { input: input.chars().collect(),
                           position: 0,
                           read_position: 0,
                           ch: ' ',
+                          line: 1,
+                          column: 0,
+                          operators: Vec::new(),
         };
 
         lexier.read_char();
@@ -25,50 +31,116 @@ impl Lexier {
 
     /// Tokenize input string 
     pub fn next_token(&mut self) -> Token {
-        let token: Token;
         self.skip();
+
+        let (line, column) = (self.line, self.column);
+        let mut token = self.read_token();
+        token.line = line;
+        token.column = column;
+
+        token
+    }
+
+    fn read_token(&mut self) -> Token {
+        let token: Token;
+
+        if let Some(operator) = self.read_operator() {
+            return Token::new(TokenKind::Operator, operator);
+        }
+
+        if let Some(token) = self.read_compound_assign() {
+            return token;
+        }
         
         match self.ch {
             '=' => {
                 if self.peek_char() == '=' {
-                    token = Token {kind:TokenKind::Eq, literal: "==".to_string()};
+                    token = Token::new(TokenKind::Eq, "==".to_string());
+                    self.read_char();
+                }
+                else if self.peek_char() == '>' {
+                    token = Token::new(TokenKind::Arrow, "=>".to_string());
+                    self.read_char();
+                }
+                else {
+                    token = Token::new(TokenKind::Assign, self.ch.to_string());
+                }
+            },
+            '+' => token = Token::new(TokenKind::Plus, self.ch.to_string()),
+            '-' => {
+                if self.peek_char() == '>' {
+                    token = Token::new(TokenKind::ThinArrow, "->".to_string());
                     self.read_char();
                 }
                 else {
-                    token = Token {kind: TokenKind::Assign, literal: self.ch.to_string()};
+                    token = Token::new(TokenKind::Minus, self.ch.to_string());
                 }
             },
-            '+' => token = Token {kind: TokenKind::Plus, literal: self.ch.to_string()},
-            '-' => token = Token {kind: TokenKind::Minus, literal: self.ch.to_string()},
             '!' => {
                 if self.peek_char() == '=' {
-                    token = Token {kind: TokenKind::NotEq, literal: "!=".to_string()};
+                    token = Token::new(TokenKind::NotEq, "!=".to_string());
+                    self.read_char();
+                }
+                else {
+                    token = Token::new(TokenKind::Bang, self.ch.to_string());
+                }
+            },
+            '*' => token = Token::new(TokenKind::Asterisk, self.ch.to_string()),
+            '/' => token = Token::new(TokenKind::Slash, self.ch.to_string()),
+            '<' => token = Token::new(TokenKind::Lt, self.ch.to_string()),
+            '>' => token = Token::new(TokenKind::Gt, self.ch.to_string()),
+            '|' => token = Token::new(TokenKind::Pipe, self.ch.to_string()),
+            '(' => token = Token::new(TokenKind::Lparen, self.ch.to_string()),
+            ')' => token = Token::new(TokenKind::Rparen, self.ch.to_string()),
+            '{' => token = Token::new(TokenKind::Lbrace, self.ch.to_string()),
+            '}' => token = Token::new(TokenKind::Rbrace, self.ch.to_string()),
+            ',' => token = Token::new(TokenKind::Comma, self.ch.to_string()),
+            ';' => token = Token::new(TokenKind::Semicolon, self.ch.to_string()),
+            ':' => token = Token::new(TokenKind::Colon, self.ch.to_string()),
+            '.' => {
+                if self.peek_char() == '.' {
                     self.read_char();
+                    if self.peek_char() == '=' {
+                        token = Token::new(TokenKind::DotDotEq, "..=".to_string());
+                        self.read_char();
+                    }
+                    else if self.peek_char() == '.' {
+                        token = Token::new(TokenKind::Ellipsis, "...".to_string());
+                        self.read_char();
+                    }
+                    else {
+                        token = Token::new(TokenKind::DotDot, "..".to_string());
+                    }
                 }
                 else {
-                    token = Token {kind: TokenKind::Bang, literal: self.ch.to_string()};
+                    token = Token::new(TokenKind::Dot, self.ch.to_string());
                 }
             },
-            '*' => token = Token {kind: TokenKind::Asterisk, literal: self.ch.to_string()},
-            '/' => token = Token {kind: TokenKind::Slash, literal: self.ch.to_string()},
-            '<' => token = Token {kind: TokenKind::Lt, literal: self.ch.to_string()},
-            '>' => token = Token {kind: TokenKind::Gt,  literal: self.ch.to_string()},
-            '(' => token = Token {kind: TokenKind::Lparen, literal: self.ch.to_string()},
-            ')' => token = Token {kind: TokenKind::Rparen, literal: self.ch.to_string()},
-            '{' => token = Token {kind: TokenKind::Lbrace, literal: self.ch.to_string()},
-            '}' => token = Token {kind: TokenKind::Rbrace, literal: self.ch.to_string()},
-            ',' => token = Token {kind: TokenKind::Comma, literal: self.ch.to_string()},
-            ';' => token = Token {kind: TokenKind::Semicolon, literal: self.ch.to_string()},
-            '\0' => token = Token {kind: TokenKind::Eof, literal: "".to_string()},
+            '?' => {
+                let kind = match self.peek_char() {
+                    '.' => TokenKind::QuestionDot,
+                    '[' => TokenKind::QuestionLbracket,
+                    '?' => TokenKind::QuestionQuestion,
+                    _   => TokenKind::Illegal,
+                };
+                if kind == TokenKind::Illegal {
+                    token = Token::new(kind, self.ch.to_string());
+                }
+                else {
+                    self.read_char();
+                    token = Token::new(kind, format!("?{}", self.ch));
+                }
+            },
+            '\0' => token = Token::new(TokenKind::Eof, "".to_string()),
             'a'...'z' | 'A' ... 'Z' | '_' => {
                 let ident = self.read_identifier();
                 return self.lookup_ident(&ident)
             },
-            '0' ... '9' => return Token {kind: TokenKind::Integer, literal: self.read_integer()},
-            '"' => token = Token {kind: TokenKind::String, literal: self.read_string()},
-            '[' => token = Token {kind: TokenKind::Lbracket, literal: self.ch.to_string()},
-            ']' => token = Token {kind: TokenKind::Rbracket, literal: self.ch.to_string()},
-            _  => token = Token {kind: TokenKind::Illegal, literal: self.ch.to_string()},
+            '0' ... '9' => return Token::new(TokenKind::Integer, self.read_integer()),
+            '"' => token = Token::new(TokenKind::String, self.read_string()),
+            '[' => token = Token::new(TokenKind::Lbracket, self.ch.to_string()),
+            ']' => token = Token::new(TokenKind::Rbracket, self.ch.to_string()),
+            _  => token = Token::new(TokenKind::Illegal, self.ch.to_string()),
         }
 
         self.read_char();
@@ -78,25 +150,101 @@ impl Lexier {
     /// Check whether ident is keywords, and return the suitable token. 
     fn lookup_ident(&mut self, ident: &str) -> Token {
         match ident {
-            "fn" => Token {kind: TokenKind::Function, literal: ident.to_string()},
-            "let" => Token {kind: TokenKind::Let, literal: ident.to_string()},
-            "true" => Token {kind: TokenKind::True, literal: ident.to_string()},
-            "false" => Token {kind: TokenKind::False, literal: ident.to_string()},
-            "if" => Token {kind: TokenKind::If, literal: ident.to_string()},
-            "else" => Token {kind: TokenKind::Else, literal: ident.to_string()},
-            "return" => Token {kind: TokenKind::Return, literal: ident.to_string()},
-            _ => Token {kind: TokenKind::Identifier, literal: ident.to_string()}
+            "fn" => Token::new(TokenKind::Function, ident.to_string()),
+            "let" => Token::new(TokenKind::Let, ident.to_string()),
+            "const" => Token::new(TokenKind::Const, ident.to_string()),
+            "true" => Token::new(TokenKind::True, ident.to_string()),
+            "false" => Token::new(TokenKind::False, ident.to_string()),
+            "if" => Token::new(TokenKind::If, ident.to_string()),
+            "else" => Token::new(TokenKind::Else, ident.to_string()),
+            "return" => Token::new(TokenKind::Return, ident.to_string()),
+            "infix" => Token::new(TokenKind::Infix, ident.to_string()),
+            "throw" => Token::new(TokenKind::Throw, ident.to_string()),
+            "try" => Token::new(TokenKind::Try, ident.to_string()),
+            "catch" => Token::new(TokenKind::Catch, ident.to_string()),
+            "finally" => Token::new(TokenKind::Finally, ident.to_string()),
+            "struct" => Token::new(TokenKind::Struct, ident.to_string()),
+            "enum" => Token::new(TokenKind::Enum, ident.to_string()),
+            "class" => Token::new(TokenKind::Class, ident.to_string()),
+            "extends" => Token::new(TokenKind::Extends, ident.to_string()),
+            "import" => Token::new(TokenKind::Import, ident.to_string()),
+            "export" => Token::new(TokenKind::Export, ident.to_string()),
+            "as" => Token::new(TokenKind::As, ident.to_string()),
+            "yield" => Token::new(TokenKind::Yield, ident.to_string()),
+            "for" => Token::new(TokenKind::For, ident.to_string()),
+            "in" => Token::new(TokenKind::In, ident.to_string()),
+            "null" => Token::new(TokenKind::Null, ident.to_string()),
+            _ => Token::new(TokenKind::Identifier, ident.to_string())
+        }
+    }
+
+    /// Register user-defined operator symbol.
+    pub fn add_operator(&mut self, operator: String) {
+        if !self.operators.contains(&operator) {
+            self.operators.push(operator);
+            self.operators.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()));
+        }
+    }
+
+    /// Read the longest user-defined operator at current position
+    fn read_operator(&mut self) -> Option<String> {
+        let mut found = None;
+
+        for operator in &self.operators {
+            let len = operator.chars().count();
+            if self.position + len > self.input.len() {
+                continue;
+            }
+
+            if self.input[self.position..self.position + len].iter().cloned().eq(operator.chars()) {
+                found = Some(operator.clone());
+                break;
+            }
+        }
+
+        if let Some(ref operator) = found {
+            for _ in 0..operator.chars().count() {
+                self.read_char();
+            }
+        }
+
+        found
+    }
+
+    /// Read `+=`, `-=`, `*=` or `/=` at current position
+    fn read_compound_assign(&mut self) -> Option<Token> {
+        let kind = match self.ch {
+            '+' => TokenKind::PlusAssign,
+            '-' => TokenKind::MinusAssign,
+            '*' => TokenKind::AsteriskAssign,
+            '/' => TokenKind::SlashAssign,
+            _   => return None,
+        };
+
+        if self.peek_char() != '=' {
+            return None;
         }
+
+        let token = Token::new(kind, format!("{}=", self.ch));
+        self.read_char();
+        self.read_char();
+
+        Some(token)
     }
 
     /// Increment current position 
     fn read_char(&mut self) {
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 0;
+        }
+        self.column += 1;
+
         if self.read_position >= self.input.len() {
             self.ch = '\0';
         }
         else {
-            self.ch = self.input.chars()
-                .skip(self.read_position).next().unwrap()
+            self.ch = self.input[self.read_position];
         }
         self.position = self.read_position;
         self.read_position += 1;
@@ -147,12 +295,12 @@ impl Lexier {
         if self.read_position >= self.input.len() {
             return '\0'
         }
-        self.input.chars().nth(self.read_position).unwrap()
+        self.input[self.read_position]
     }
     
     /// Skip meaningless character (e.x. whitespace)
     fn skip(&mut self) {
-        if self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
+        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
             self.read_char();
         }
     }
@@ -185,114 +333,141 @@ return false;\
 \"foo bar\"\
 \"\"\
 [1, 2];\
+|x| x => x;\
+a[1:2];\
+a.push(1);\
 ".to_string();
 
-    let tests = [ Token { kind: TokenKind::Identifier, literal: "foo".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
+    let tests = [ Token::new(TokenKind::Identifier, "foo".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
                   
-                  Token { kind: TokenKind::Identifier, literal: "bar".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
+                  Token::new(TokenKind::Identifier, "bar".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
                   
-                  Token { kind: TokenKind::Let, literal: "let".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "five".to_string() },
-                  Token { kind: TokenKind::Assign, literal: "=".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "5".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
+                  Token::new(TokenKind::Let, "let".to_string()),
+                  Token::new(TokenKind::Identifier, "five".to_string()),
+                  Token::new(TokenKind::Assign, "=".to_string()),
+                  Token::new(TokenKind::Integer, "5".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
                   
-                  Token { kind: TokenKind::Let, literal: "let".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "ten".to_string() },
-                  Token { kind: TokenKind::Assign, literal: "=".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "10".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::Let, literal: "let".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "add".to_string() },
-                  Token { kind: TokenKind::Assign, literal: "=".to_string() },
-                  Token { kind: TokenKind::Function, literal: "fn".to_string() },
-                  Token { kind: TokenKind::Lparen, literal: "(".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "x".to_string() },
-                  Token { kind: TokenKind::Comma, literal: ",".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "y".to_string() },
-                  Token { kind: TokenKind::Rparen, literal: ")".to_string() },
-                  Token { kind: TokenKind::Lbrace, literal: "{".to_string() },
-
-                  Token { kind: TokenKind::Identifier, literal: "x".to_string() },
-                  Token { kind: TokenKind::Plus, literal: "+".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "y".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::Rbrace, literal: "}".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
+                  Token::new(TokenKind::Let, "let".to_string()),
+                  Token::new(TokenKind::Identifier, "ten".to_string()),
+                  Token::new(TokenKind::Assign, "=".to_string()),
+                  Token::new(TokenKind::Integer, "10".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Let, "let".to_string()),
+                  Token::new(TokenKind::Identifier, "add".to_string()),
+                  Token::new(TokenKind::Assign, "=".to_string()),
+                  Token::new(TokenKind::Function, "fn".to_string()),
+                  Token::new(TokenKind::Lparen, "(".to_string()),
+                  Token::new(TokenKind::Identifier, "x".to_string()),
+                  Token::new(TokenKind::Comma, ",".to_string()),
+                  Token::new(TokenKind::Identifier, "y".to_string()),
+                  Token::new(TokenKind::Rparen, ")".to_string()),
+                  Token::new(TokenKind::Lbrace, "{".to_string()),
+
+                  Token::new(TokenKind::Identifier, "x".to_string()),
+                  Token::new(TokenKind::Plus, "+".to_string()),
+                  Token::new(TokenKind::Identifier, "y".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Rbrace, "}".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
                   
-                  Token { kind: TokenKind::Let, literal: "let".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "result".to_string() },
-                  Token { kind: TokenKind::Assign, literal: "=".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "add".to_string() },
-                  Token { kind: TokenKind::Lparen, literal: "(".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "five".to_string() },
-                  Token { kind: TokenKind::Comma, literal: ",".to_string() },
-                  Token { kind: TokenKind::Identifier, literal: "ten".to_string() },
-                  Token { kind: TokenKind::Rparen, literal: ")".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
+                  Token::new(TokenKind::Let, "let".to_string()),
+                  Token::new(TokenKind::Identifier, "result".to_string()),
+                  Token::new(TokenKind::Assign, "=".to_string()),
+                  Token::new(TokenKind::Identifier, "add".to_string()),
+                  Token::new(TokenKind::Lparen, "(".to_string()),
+                  Token::new(TokenKind::Identifier, "five".to_string()),
+                  Token::new(TokenKind::Comma, ",".to_string()),
+                  Token::new(TokenKind::Identifier, "ten".to_string()),
+                  Token::new(TokenKind::Rparen, ")".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
                   
-                  Token { kind: TokenKind::Bang, literal: "!".to_string() },
-                  Token { kind: TokenKind::Minus, literal: "-".to_string() },
-                  Token { kind: TokenKind::Slash, literal: "/".to_string() },
-                  Token { kind: TokenKind::Asterisk, literal: "*".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "5".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::Integer, literal: "5".to_string() },
-                  Token { kind: TokenKind::Lt, literal: "<".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "10".to_string() },
-                  Token { kind: TokenKind::Gt, literal: ">".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "5".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::If, literal: "if".to_string() },
-                  Token { kind: TokenKind::Lparen, literal: "(".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "5".to_string() },
-                  Token { kind: TokenKind::Lt, literal: "<".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "10".to_string() },
-                  Token { kind: TokenKind::Rparen, literal: ")".to_string() },
-                  Token { kind: TokenKind::Lbrace, literal: "{".to_string() },
-
-                  Token { kind: TokenKind::Return, literal: "return".to_string() },
-                  Token { kind: TokenKind::True, literal: "true".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::Rbrace, literal: "}".to_string() },
-                  Token { kind: TokenKind::Else, literal: "else".to_string() },
-                  Token { kind: TokenKind::Lbrace, literal: "{".to_string() },
+                  Token::new(TokenKind::Bang, "!".to_string()),
+                  Token::new(TokenKind::Minus, "-".to_string()),
+                  Token::new(TokenKind::Slash, "/".to_string()),
+                  Token::new(TokenKind::Asterisk, "*".to_string()),
+                  Token::new(TokenKind::Integer, "5".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Integer, "5".to_string()),
+                  Token::new(TokenKind::Lt, "<".to_string()),
+                  Token::new(TokenKind::Integer, "10".to_string()),
+                  Token::new(TokenKind::Gt, ">".to_string()),
+                  Token::new(TokenKind::Integer, "5".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::If, "if".to_string()),
+                  Token::new(TokenKind::Lparen, "(".to_string()),
+                  Token::new(TokenKind::Integer, "5".to_string()),
+                  Token::new(TokenKind::Lt, "<".to_string()),
+                  Token::new(TokenKind::Integer, "10".to_string()),
+                  Token::new(TokenKind::Rparen, ")".to_string()),
+                  Token::new(TokenKind::Lbrace, "{".to_string()),
+
+                  Token::new(TokenKind::Return, "return".to_string()),
+                  Token::new(TokenKind::True, "true".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Rbrace, "}".to_string()),
+                  Token::new(TokenKind::Else, "else".to_string()),
+                  Token::new(TokenKind::Lbrace, "{".to_string()),
                   
-                  Token { kind: TokenKind::Return, literal: "return".to_string() },
-                  Token { kind: TokenKind::False, literal: "false".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
+                  Token::new(TokenKind::Return, "return".to_string()),
+                  Token::new(TokenKind::False, "false".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
                   
-                  Token { kind: TokenKind::Rbrace, literal: "}".to_string() },
-
-                  Token { kind: TokenKind::Integer, literal: "10".to_string() },
-                  Token { kind: TokenKind::Eq, literal: "==".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "10".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::Integer, literal: "10".to_string() },
-                  Token { kind: TokenKind::NotEq, literal: "!=".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "9".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::String, literal: "foobar".to_string() },
-                  Token { kind: TokenKind::String, literal: "foo bar".to_string() },
-                  Token { kind: TokenKind::String, literal: "".to_string() },
-
-                  Token { kind: TokenKind::Lbracket, literal: "[".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "1".to_string() },
-                  Token { kind: TokenKind::Comma, literal: ",".to_string() },
-                  Token { kind: TokenKind::Integer, literal: "2".to_string() },
-                  Token { kind: TokenKind::Rbracket, literal: "]".to_string() },
-                  Token { kind: TokenKind::Semicolon, literal: ";".to_string() },
-
-                  Token { kind: TokenKind::Eof, literal: "".to_string() }
+                  Token::new(TokenKind::Rbrace, "}".to_string()),
+
+                  Token::new(TokenKind::Integer, "10".to_string()),
+                  Token::new(TokenKind::Eq, "==".to_string()),
+                  Token::new(TokenKind::Integer, "10".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Integer, "10".to_string()),
+                  Token::new(TokenKind::NotEq, "!=".to_string()),
+                  Token::new(TokenKind::Integer, "9".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::String, "foobar".to_string()),
+                  Token::new(TokenKind::String, "foo bar".to_string()),
+                  Token::new(TokenKind::String, "".to_string()),
+
+                  Token::new(TokenKind::Lbracket, "[".to_string()),
+                  Token::new(TokenKind::Integer, "1".to_string()),
+                  Token::new(TokenKind::Comma, ",".to_string()),
+                  Token::new(TokenKind::Integer, "2".to_string()),
+                  Token::new(TokenKind::Rbracket, "]".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Pipe, "|".to_string()),
+                  Token::new(TokenKind::Identifier, "x".to_string()),
+                  Token::new(TokenKind::Pipe, "|".to_string()),
+                  Token::new(TokenKind::Identifier, "x".to_string()),
+                  Token::new(TokenKind::Arrow, "=>".to_string()),
+                  Token::new(TokenKind::Identifier, "x".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Identifier, "a".to_string()),
+                  Token::new(TokenKind::Lbracket, "[".to_string()),
+                  Token::new(TokenKind::Integer, "1".to_string()),
+                  Token::new(TokenKind::Colon, ":".to_string()),
+                  Token::new(TokenKind::Integer, "2".to_string()),
+                  Token::new(TokenKind::Rbracket, "]".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Identifier, "a".to_string()),
+                  Token::new(TokenKind::Dot, ".".to_string()),
+                  Token::new(TokenKind::Identifier, "push".to_string()),
+                  Token::new(TokenKind::Lparen, "(".to_string()),
+                  Token::new(TokenKind::Integer, "1".to_string()),
+                  Token::new(TokenKind::Rparen, ")".to_string()),
+                  Token::new(TokenKind::Semicolon, ";".to_string()),
+
+                  Token::new(TokenKind::Eof, "".to_string())
                   
     ];
     
@@ -313,3 +488,107 @@ return false;\
     }
     
 }
+
+#[test]
+fn test_user_defined_operators() {
+    let input = "a <> b < c <=> d".to_string();
+
+    let tests = [ Token::new(TokenKind::Identifier, "a".to_string()),
+                  Token::new(TokenKind::Operator, "<>".to_string()),
+                  Token::new(TokenKind::Identifier, "b".to_string()),
+                  Token::new(TokenKind::Lt, "<".to_string()),
+                  Token::new(TokenKind::Identifier, "c".to_string()),
+                  Token::new(TokenKind::Operator, "<=>".to_string()),
+                  Token::new(TokenKind::Identifier, "d".to_string()),
+                  Token::new(TokenKind::Eof, "".to_string())
+    ];
+
+    let mut lexier = Lexier::new(input);
+    lexier.add_operator("<>".to_string());
+    lexier.add_operator("<=>".to_string());
+
+    for test in tests.iter() {
+        let token = lexier.next_token();
+        assert_eq!(token.get_kind_literal(), test.get_kind_literal());
+        assert_eq!(token.literal, test.literal);
+    }
+}
+
+#[test]
+fn test_compound_assignment() {
+    let input = "x += 1; y -= 2; z *= 3; w /= 4".to_string();
+
+    let tests = [ (TokenKind::Identifier, "x"), (TokenKind::PlusAssign, "+="), (TokenKind::Integer, "1"), (TokenKind::Semicolon, ";"),
+                  (TokenKind::Identifier, "y"), (TokenKind::MinusAssign, "-="), (TokenKind::Integer, "2"), (TokenKind::Semicolon, ";"),
+                  (TokenKind::Identifier, "z"), (TokenKind::AsteriskAssign, "*="), (TokenKind::Integer, "3"), (TokenKind::Semicolon, ";"),
+                  (TokenKind::Identifier, "w"), (TokenKind::SlashAssign, "/="), (TokenKind::Integer, "4"),
+                  (TokenKind::Eof, ""),
+    ];
+
+    let mut lexier = Lexier::new(input);
+
+    for test in tests.iter() {
+        let token = lexier.next_token();
+        assert_eq!(token.kind, test.0);
+        assert_eq!(token.literal, test.1.to_string());
+    }
+}
+
+#[test]
+fn test_token_location() {
+    let input = "let x = 5;\n  x + \"é\"\n\tfoo".to_string();
+
+    let tests = [ ("let", 1, 1), ("x", 1, 5), ("=", 1, 7), ("5", 1, 9), (";", 1, 10),
+                  ("x", 2, 3), ("+", 2, 5), ("é", 2, 7),
+                  ("foo", 3, 2),
+    ];
+
+    let mut lexier = Lexier::new(input);
+
+    for test in tests.iter() {
+        let token = lexier.next_token();
+        assert_eq!(token.literal, test.0.to_string());
+        assert_eq!((token.line, token.column), (test.1, test.2), "token {}", test.0);
+    }
+}
+
+#[test]
+fn test_range_tokens() {
+    let input = "0..n 1..=10 step 2 x in r a.b ...xs".to_string();
+
+    let tests = [ (TokenKind::Integer, "0"), (TokenKind::DotDot, ".."), (TokenKind::Identifier, "n"),
+                  (TokenKind::Integer, "1"), (TokenKind::DotDotEq, "..="), (TokenKind::Integer, "10"),
+                  (TokenKind::Identifier, "step"), (TokenKind::Integer, "2"),
+                  (TokenKind::Identifier, "x"), (TokenKind::In, "in"), (TokenKind::Identifier, "r"),
+                  (TokenKind::Identifier, "a"), (TokenKind::Dot, "."), (TokenKind::Identifier, "b"),
+                  (TokenKind::Ellipsis, "..."), (TokenKind::Identifier, "xs"),
+                  (TokenKind::Eof, ""),
+    ];
+
+    let mut lexier = Lexier::new(input);
+
+    for test in tests.iter() {
+        let token = lexier.next_token();
+        assert_eq!(token.kind, test.0);
+        assert_eq!(token.literal, test.1.to_string());
+    }
+}
+
+#[test]
+fn test_optional_tokens() {
+    let input = "a?.b c?[0] null ?? d ?".to_string();
+
+    let tests = [ (TokenKind::Identifier, "a"), (TokenKind::QuestionDot, "?."), (TokenKind::Identifier, "b"),
+                  (TokenKind::Identifier, "c"), (TokenKind::QuestionLbracket, "?["), (TokenKind::Integer, "0"), (TokenKind::Rbracket, "]"),
+                  (TokenKind::Null, "null"), (TokenKind::QuestionQuestion, "??"), (TokenKind::Identifier, "d"),
+                  (TokenKind::Illegal, "?"), (TokenKind::Eof, ""),
+    ];
+
+    let mut lexier = Lexier::new(input);
+
+    for test in tests.iter() {
+        let token = lexier.next_token();
+        assert_eq!(token.kind, test.0);
+        assert_eq!(token.literal, test.1.to_string());
+    }
+}
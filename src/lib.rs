@@ -6,4 +6,15 @@ pub mod parser;
 pub mod object;
 pub mod eval;
 pub mod env;
+pub mod context;
 pub mod builtins;
+pub mod json;
+pub mod sexp;
+pub mod serialize;
+pub mod fold;
+pub mod module;
+pub mod generator;
+pub mod range;
+pub mod types;
+pub mod checker;
+pub mod infer;
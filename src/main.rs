@@ -1,6 +1,178 @@
+use simia::ast::Ast;
+use simia::checker;
+use simia::context;
+use simia::env::Env;
+use simia::infer;
+use simia::lexier::Lexier;
+use simia::module;
+use simia::object::{ Object, format_trace };
+use simia::parser::Parser;
 use simia::repl;
+use simia::serialize;
+use std::env;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process;
 
+const USAGE: &str = "\
+usage: simia                              start REPL
+       simia run [-I DIR].. FILE            evaluate FILE, searching DIR and $SIMIA_PATH for imports
+       simia check [--infer] FILE           report type errors and warnings in FILE, or infer its
+                                            types and print the signatures of its top-level bindings
+       simia parse [--emit=json|sexp] FILE  print the syntax tree of FILE";
 
 fn main() {
-    repl::start()
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // nested calls need more stack than the main thread has
+    let finished = context::with_stack(move || {
+        match args.first().map(|arg| arg.as_ref()) {
+            None          => repl::start(),
+            Some("run")   => run(&args[1..]),
+            Some("check") => check(&args[1..]),
+            Some("parse") => parse(&args[1..]),
+            Some(_)       => exit_with_usage(),
+        }
+    });
+
+    if finished.is_err() {
+        process::exit(101);
+    }
+}
+
+fn run(args: &[String]) {
+    let env = Env::new();
+    let mut path = None;
+
+    if let Some(dirs) = env::var_os("SIMIA_PATH") {
+        for dir in env::split_paths(&dirs) {
+            env.loader().borrow_mut().add_search_path(dir);
+        }
+    }
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-I" {
+            match args.next() {
+                Some(dir) => env.loader().borrow_mut().add_search_path(PathBuf::from(dir)),
+                None      => exit_with_usage(),
+            }
+        }
+        else if path.is_none() && !arg.starts_with("-") {
+            path = Some(arg.to_string());
+        }
+        else {
+            exit_with_usage();
+        }
+    }
+
+    let path = match path {
+        Some(value) => value,
+        None        => exit_with_usage(),
+    };
+
+    let mut env = Env::new_module_env(env.loader(), fs::canonicalize(&path).ok());
+    match module::run_file(Path::new(&path), &mut env) {
+        Object::Error { kind, msg, trace } => {
+            eprintln!("{}: {}", kind, msg);
+            for line in format_trace(&trace) {
+                eprintln!("{}", line);
+            }
+            process::exit(1);
+        },
+        _ => (),
+    }
+
+    env.clear();
+    env.loader().borrow_mut().clear();
+}
+
+fn parse(args: &[String]) {
+    let mut emit = "json".to_string();
+    let mut path = None;
+
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--emit=") {
+            emit = value.to_string();
+        }
+        else if path.is_none() && !arg.starts_with("--") {
+            path = Some(arg.to_string());
+        }
+        else {
+            exit_with_usage();
+        }
+    }
+
+    let path = match path {
+        Some(value) => value,
+        None        => exit_with_usage(),
+    };
+
+    let program = read_program(&path);
+
+    match emit.as_ref() {
+        "json" => println!("{}", serialize::to_json_string(&program)),
+        "sexp" => println!("{}", serialize::to_sexp_string(&program)),
+        _      => exit_with_usage(),
+    }
+}
+
+fn check(args: &[String]) {
+    let (path, infer) = match args {
+        [path] if !path.starts_with("-")                       => (path, false),
+        [flag, path] if flag == "--infer" && !path.starts_with("-") => (path, true),
+        _                                                      => exit_with_usage(),
+    };
+
+    let program = read_program(path);
+    let errors = if infer {
+        let inference = infer::infer_types(&program);
+        for (name, signature) in inference.signatures {
+            println!("{}: {}", name, signature);
+        }
+        inference.errors
+    }
+    else {
+        let mut checker = checker::Checker::new();
+        let errors = checker.check(&program);
+        for warning in checker.warnings() {
+            eprintln!("{}:{}", path, warning);
+        }
+        errors
+    };
+
+    if errors.len() != 0 {
+        for error in errors {
+            eprintln!("{}:{}", path, error);
+        }
+        process::exit(1);
+    }
+}
+
+/// Parse the file at path, exiting with the parse errors if it is not a valid program
+fn read_program(path: &str) -> Ast {
+    let input = match fs::read_to_string(path) {
+        Ok(value) => value,
+        Err(error) => {
+            eprintln!("error: {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+
+    let mut parser = Parser::new(Lexier::new(input));
+    let program = parser.parse_program().unwrap();
+
+    if parser.errors.len() != 0 {
+        for error in parser.errors {
+            eprintln!("{}", error);
+        }
+        process::exit(1);
+    }
+
+    program
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
 }
@@ -0,0 +1,205 @@
+//! Loading of source files with `import`.
+//!
+//! An imported file is evaluated once in a fresh top-level `Env` and the names declared
+//! with `export` become the members of an `Object::Module`. Modules are cached by
+//! canonical path, and importing a file that is still being evaluated is an error.
+//! User-defined operators declared in a module are not visible to the importing file.
+//! A file with type errors is not evaluated. The `imports` limit of the context can
+//! confine imports to a directory or disable them for sandboxed programs.
+
+use crate::ast::{ Ast };
+use crate::checker::{ check_types };
+use crate::context::{ Imports };
+use crate::env::{ Env };
+use crate::eval::{ eval };
+use crate::fold::{ fold_constants };
+use crate::lexier::{ Lexier };
+use crate::object::{ Object, new_error_with_kind };
+use crate::parser::{ Parser };
+use std::collections::{ HashMap };
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+#[derive(Debug)]
+pub struct Loader {
+    search_path: Vec<PathBuf>,
+    cache: HashMap<PathBuf, Object>,    // Object::Module by canonical path
+    scopes: Vec<Env>,                   // top-level scopes of the cached modules
+    loading: Vec<PathBuf>,              // files being evaluated, innermost last
+}
+
+impl Loader {
+    pub fn new() -> Loader {
+        Loader { search_path: Vec::new(), cache: HashMap::new(), scopes: Vec::new(), loading: Vec::new() }
+    }
+
+    /// Drop the cached modules along with the bindings of their scopes, which the functions
+    /// they define would otherwise keep alive; modules imported afterwards are loaded again
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        for scope in self.scopes.drain(..) {
+            scope.clear();
+        }
+    }
+
+    /// Add directory searched for imports that are not found next to the importing file
+    pub fn add_search_path(&mut self, dir: PathBuf) {
+        self.search_path.push(dir);
+    }
+
+    /// Find the file an import refers to. Relative paths are tried against the directory
+    /// of the importing file (or the working directory), then against the search path.
+    pub fn resolve(&self, path: &str, from: Option<&Path>) -> Option<PathBuf> {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return if path.is_file() { Some(path.to_path_buf()) } else { None };
+        }
+
+        let base = match from.and_then(|file| file.parent()) {
+            Some(dir) => dir.to_path_buf(),
+            None      => PathBuf::from("."),
+        };
+
+        let mut candidates = vec![base.join(path)];
+        candidates.extend(self.search_path.iter().map(|dir| dir.join(path)));
+
+        candidates.into_iter().find(|candidate| candidate.is_file())
+    }
+}
+
+impl Default for Loader {
+    fn default() -> Loader {
+        Loader::new()
+    }
+}
+
+/// Import the module at path from the file env belongs to
+pub fn import(path: &str, env: &Env) -> Object {
+    let loader = env.loader();
+    let imports = env.context().borrow().imports();
+    if let Imports::Disabled = imports {
+        return new_error_with_kind("ImportError", format!("import is disabled: {}", path));
+    }
+
+    let resolved = match loader.borrow().resolve(path, env.file().as_ref().map(|file| file.as_path())) {
+        Some(value) => value,
+        None        => return new_error_with_kind("ImportError", format!("module not found: {}", path)),
+    };
+
+    let canonical = match fs::canonicalize(&resolved) {
+        Ok(value)  => value,
+        Err(error) => return new_error_with_kind("ImportError", format!("{}: {}", resolved.display(), error)),
+    };
+
+    // compared after resolving `..` and symbolic links
+    if let Imports::Within(ref root) = imports {
+        match fs::canonicalize(root) {
+            Ok(ref dir) if canonical.starts_with(dir) => (),
+            _ => return new_error_with_kind("ImportError", format!("import outside of {}: {}", root.display(), path)),
+        }
+    }
+
+    if let Some(module) = loader.borrow().cache.get(&canonical) {
+        return module.clone();
+    }
+
+    let input = match fs::read_to_string(&canonical) {
+        Ok(value)  => value,
+        Err(error) => return new_error_with_kind("ImportError", format!("{}: {}", canonical.display(), error)),
+    };
+
+    let mut module_env = Env::new_import_env(env, Some(canonical.clone()));
+    let program = match load(&canonical, input, &mut module_env) {
+        Ok((program, _)) => program,
+        Err(error)       => return error,
+    };
+
+    let module = Object::Module { path: canonical.display().to_string(), exports: exports(&program, &module_env) };
+    loader.borrow_mut().cache.insert(canonical, module.clone());
+    loader.borrow_mut().scopes.push(module_env);
+
+    module
+}
+
+/// Evaluate a source file as the main program in env
+pub fn run_file(path: &Path, env: &mut Env) -> Object {
+    let input = match fs::read_to_string(path) {
+        Ok(value)  => value,
+        Err(error) => return new_error_with_kind("IOError", format!("{}: {}", path.display(), error)),
+    };
+
+    match load(path, input, env) {
+        Ok((_, value)) => value,
+        Err(error)     => error,
+    }
+}
+
+/// Parse and evaluate input, the source of the file at path. The file is marked as loading
+/// meanwhile, so that importing it again from inside is reported as a cycle.
+fn load(path: &Path, input: String, env: &mut Env) -> Result<(Ast, Object), Object> {
+    let loader = env.loader();
+
+    let mut parser = Parser::new(Lexier::new(input));
+    let program = parser.parse_program().unwrap();
+
+    if parser.errors.len() != 0 {
+        return Err(new_error_with_kind("SyntaxError", format!("{}: {}", path.display(), parser.errors.join("; "))));
+    }
+
+    let errors = check_types(&program);
+    if errors.len() != 0 {
+        let errors: Vec<String> = errors.iter().map(|error| format!("{}:{}", path.display(), error)).collect();
+        return Err(new_error_with_kind("TypeError", errors.join("; ")));
+    }
+
+    let key = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    if loader.borrow().loading.contains(&key) {
+        let mut cycle: Vec<String> = loader.borrow().loading.iter()
+            .skip_while(|file| **file != key)
+            .map(|file| file.display().to_string())
+            .collect();
+        cycle.push(key.display().to_string());
+
+        return Err(new_error_with_kind("ImportError", format!("import cycle: {}", cycle.join(" -> "))));
+    }
+
+    loader.borrow_mut().loading.push(key);
+    let evaluated = eval(&fold_constants(program.clone()), env);
+    loader.borrow_mut().loading.pop();
+
+    let value = match evaluated {
+        Some(value) => value,
+        None        => Object::Null,
+    };
+
+    match value {
+        Object::Error { .. } => Err(value),
+        _                    => Ok((program, value)),
+    }
+}
+
+/// Values of the names declared by top-level export statements
+fn exports(program: &Ast, env: &Env) -> Vec<(String, Object)> {
+    let mut exports = Vec::new();
+
+    if let Ast::Program { statements } = program {
+        for statement in statements {
+            let declaration = match **statement {
+                Ast::ExportStatement { ref statement, .. } => statement,
+                _                                          => continue,
+            };
+
+            let name = match **declaration {
+                Ast::LetStatement { ref ident, .. }     => ident.to_string(),
+                Ast::StructDeclaration { ref name, .. } |
+                Ast::EnumDeclaration { ref name, .. }   |
+                Ast::ClassDeclaration { ref name, .. }  => name.to_string(),
+                _                                       => continue,
+            };
+
+            exports.push((name.clone(), env.get(name)));
+        }
+    }
+
+    exports
+}
@@ -1,5 +1,82 @@
 use crate::ast::{ Ast };
+use crate::context::{ Context };
 use crate::env::{ Env };
+use crate::generator::{ Generator };
+use crate::range::{ Range };
+use std::cell::{ RefCell };
+use std::path::{ PathBuf };
+use std::rc::{ Rc };
+
+/// Class created by a class declaration
+#[derive(Debug)]
+pub struct Class {
+    pub name: String,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: Vec<(String, Object)>,     // Object::Function, not bound to an instance
+}
+
+/// Location of a call in source
+#[derive(Debug, Clone)]
+pub struct CallSite {
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Function call an error has passed through on its way out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,       // name the function was bound to, or `fn` if it is anonymous
+    pub location: String,       // call site, `file:line:column`
+}
+
+impl CallSite {
+    pub fn to_string(&self) -> String {
+        match self.file {
+            Some(ref file) => format!("{}:{}:{}", file.display(), self.line, self.column),
+            None           => format!("{}:{}", self.line, self.column),
+        }
+    }
+}
+
+impl Frame {
+    pub fn to_string(&self) -> String {
+        format!("in {}, called at {}", self.function, self.location)
+    }
+}
+
+/// Lines of the traceback of an error, innermost call first. Runs of the same call, as
+/// in deep recursion, are shortened to their first frame and a count.
+pub fn format_trace(trace: &[Frame]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < trace.len() {
+        let mut repeated = 0;
+        while i + repeated + 1 < trace.len() && trace[i + repeated + 1] == trace[i] {
+            repeated += 1;
+        }
+
+        lines.push(format!("  {}", trace[i].to_string()));
+        if repeated > 0 {
+            lines.push(format!("  ... repeated {} more times", repeated));
+        }
+        i += repeated + 1;
+    }
+
+    lines
+}
+
+/// Find a method in class or its ancestors, together with the class that defines it
+pub fn find_method(class: &Rc<Class>, name: &str) -> Option<(Rc<Class>, Object)> {
+    if let Some((_, method)) = class.methods.iter().find(|(k, _)| k == name) {
+        return Some((class.clone(), method.clone()));
+    }
+
+    match class.superclass {
+        Some(ref superclass) => find_method(superclass, name),
+        None                 => None,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum Object{
@@ -17,14 +94,29 @@ pub enum Object{
         value: Box<Object>,
     },
 
+    TailCall {                  // call in tail position, run by apply_function after the caller returns
+        function: Box<Object>,
+        arguments: Vec<Object>,
+        site: CallSite,
+    },
+
     Error {
+        kind: String,
+        msg: String,
+        trace: Vec<Frame>,      // calls the error has left, innermost first
+    },
+
+    Exception {                 // caught error bound by `catch`
+        kind: String,
         msg: String,
     },
 
     Function {
-        parameters: Vec<Box<Ast>>,
-        body: Box<Ast>,
-        env: Box<Env>,
+        parameters: Rc<Vec<Box<Ast>>>,
+        body: Rc<Ast>,          // shared with calls and bound methods instead of copied
+        generator: bool,        // body contains `yield`
+        env: Env,
+        name: Option<String>,   // of the `let` binding or method, for tracebacks
     },
 
     String {
@@ -32,23 +124,89 @@ pub enum Object{
     },
 
     Builtin {
-        function: fn(Vec<Object>) -> Object, 
+        function: fn(Vec<Object>, &RefCell<Context>) -> Object,
     },
 
     Array {
         elements: Vec<Object>,
     },
+
+    Generator {                 // returned by a call of a function containing `yield`
+        generator: Rc<RefCell<Generator>>,
+    },
+
+    Range {
+        range: Range,
+    },
+
+    StructType {                // constructor created by a struct declaration
+        name: String,
+        fields: Vec<String>,
+    },
+
+    Struct {
+        name: String,
+        fields: Vec<(String, Object)>,
+    },
+
+    Class {
+        class: Rc<Class>,
+    },
+
+    Instance {                  // fields are shared between copies of the instance
+        class: Rc<Class>,
+        fields: Rc<RefCell<Vec<(String, Object)>>>,
+    },
+
+    Super {                     // `super` inside a method: the instance seen from the superclass
+        instance: Box<Object>,
+        class: Rc<Class>,
+    },
+
+    Module {                    // names exported by an imported file
+        path: String,
+        exports: Vec<(String, Object)>,
+    },
+
+    EnumType {                  // created by an enum declaration
+        name: String,
+        variants: Vec<(String, Object)>,    // Object::VariantType, or Object::Variant for a unit variant
+    },
+
+    VariantType {               // constructor of an enum variant with fields
+        enum_name: String,
+        name: String,
+        fields: Vec<String>,
+    },
+
+    Variant {
+        enum_name: String,
+        name: String,
+        fields: Vec<(String, Object)>,
+    },
     
 }
 
+/// Names reported by `Object::kind` for built-in values; user types may not reuse them
+pub const BUILTIN_KINDS: [&str; 18] = ["Null", "Integer", "Boolean", "ReturnValue", "Error", "Exception",
+                                       "Function", "String", "Builtin", "Array", "StructType", "EnumType",
+                                       "VariantType", "Class", "Super", "Module", "Generator", "Range"];
+
 impl Object {
     pub fn inspect(&self) -> String {
+        self.inspect_within(&mut Vec::new())
+    }
+
+    /// `inspect` inside the instances in `visiting`; an instance that contains itself prints as `<Class ...>`
+    fn inspect_within(&self, visiting: &mut Vec<*const RefCell<Vec<(String, Object)>>>) -> String {
         match self {
             Object::Null              => format!("null"),
             Object::Integer { value } => format!("{}", value),
             Object::Boolean { value } => format!("{}", value),
-            Object::ReturnValue { value } => format!("{}", value.inspect()),
-            Object::Error { msg }       => format!("Error: {}", msg),
+            Object::ReturnValue { value } => format!("{}", value.inspect_within(visiting)),
+            Object::TailCall { function, .. } => function.inspect(),
+            Object::Error { msg, .. }   => format!("Error: {}", msg),
+            Object::Exception { kind, msg } => format!("{}: {}", kind, msg),
             Object::Function { parameters, body, ..} => {
                 let mut string = String::new();
                 string = format!("fn(");
@@ -68,14 +226,51 @@ impl Object {
                 string = format!("[");
                 for (i, element) in elements.iter().enumerate() {
                     if i == 0 {
-                        string = format!("{}{}", string, (*element).inspect());
+                        string = format!("{}{}", string, (*element).inspect_within(visiting));
                     }
                     else {
-                        string = format!("{}, {}", string, (*element).inspect());
+                        string = format!("{}, {}", string, (*element).inspect_within(visiting));
                     }
                 }
                 return  format!("{}]", string);
-            }
+            },
+            Object::Generator { .. } => "generator".to_string(),
+            Object::Range { range } => range.inspect(),
+            Object::StructType { name, fields } if fields.len() == 0 => format!("struct {} {{}}", name),
+            Object::StructType { name, fields } => format!("struct {} {{ {} }}", name, fields.join(", ")),
+            Object::Struct { name, fields } if fields.len() == 0 => format!("{} {{}}", name),
+            Object::Struct { name, fields } => {
+                let fields: Vec<String> = fields.iter().map(|(k, v)| format!("{}: {}", k, v.inspect_within(visiting))).collect();
+                format!("{} {{ {} }}", name, fields.join(", "))
+            },
+            Object::Class { class } => format!("class {}", class.name),
+            Object::Instance { class, fields } => {
+                if visiting.contains(&Rc::as_ptr(fields)) {
+                    return format!("<{} ...>", class.name);
+                }
+                visiting.push(Rc::as_ptr(fields));
+                let fields: Vec<String> = fields.borrow().iter().map(|(k, v)| format!("{}: {}", k, v.inspect_within(visiting))).collect();
+                visiting.pop();
+                if fields.len() == 0 {
+                    return format!("{} {{}}", class.name);
+                }
+                format!("{} {{ {} }}", class.name, fields.join(", "))
+            },
+            Object::Super { class, .. } => format!("super {}", class.name),
+            Object::Module { path, .. } => format!("module {}", path),
+            Object::EnumType { name, variants } => {
+                let variants: Vec<String> = variants.iter().map(|(variant, v)| match v {
+                    Object::VariantType { fields, .. } => format!("{}({})", variant, fields.join(", ")),
+                    _                                  => variant.to_string(),
+                }).collect();
+                format!("enum {} {{ {} }}", name, variants.join(", "))
+            },
+            Object::VariantType { enum_name, name, fields } => format!("{}.{}({})", enum_name, name, fields.join(", ")),
+            Object::Variant { enum_name, name, fields } if fields.len() == 0 => format!("{}.{}", enum_name, name),
+            Object::Variant { enum_name, name, fields } => {
+                let fields: Vec<String> = fields.iter().map(|(_, v)| v.inspect_within(visiting)).collect();
+                format!("{}.{}({})", enum_name, name, fields.join(", "))
+            },
         }
     }
 
@@ -85,15 +280,36 @@ impl Object {
             Object::Integer { .. }  => "Integer".to_string(),
             Object::Boolean { .. }  => "Boolean".to_string(),
             Object::ReturnValue { .. } => "ReturnValue".to_string(),
+            Object::TailCall { .. } => "TailCall".to_string(),
             Object::Error { .. }    => "Error".to_string(),
+            Object::Exception { .. } => "Exception".to_string(),
             Object::Function { .. } => "Function".to_string(),
             Object::String { .. }   => "String".to_string(),
             Object::Builtin { .. }  => "Builtin".to_string(),
             Object::Array { .. }    => "Array".to_string(),
+            Object::Generator { .. } => "Generator".to_string(),
+            Object::Range { .. }    => "Range".to_string(),
+            Object::StructType { .. } => "StructType".to_string(),
+            Object::Struct { name, .. } => name.to_string(),
+            Object::Class { .. }    => "Class".to_string(),
+            Object::Instance { class, .. } => class.name.to_string(),
+            Object::Super { .. }    => "Super".to_string(),
+            Object::Module { .. }   => "Module".to_string(),
+            Object::EnumType { .. } => "EnumType".to_string(),
+            Object::VariantType { .. } => "VariantType".to_string(),
+            Object::Variant { enum_name, .. } => enum_name.to_string(),
         }
     }
 }
 
 pub fn new_error(msg: String) -> Object {
-    Object::Error { msg: msg }
+    new_error_with_kind("RuntimeError", msg)
+}
+
+/// Create error with kind.
+/// Built-in kinds are TypeError, OperatorError, NameError, ArgumentError, IndexError, ValueError,
+/// AssignmentError, ImportError, SyntaxError, IOError, RecursionError, ArithmeticError and RuntimeError. `throw "msg"` raises kind Error.
+/// The trace is filled in by the calls the error leaves.
+pub fn new_error_with_kind(kind: &str, msg: String) -> Object {
+    Object::Error { kind: kind.to_string(), msg: msg, trace: Vec::new() }
 }
@@ -1,53 +1,226 @@
 use crate::ast:: { Ast };
+use crate::generator:: { misplaced_yield };
 use crate::lexier:: { Lexier };
 use crate::token:: { TokenKind, Token};
+use std::collections::HashMap;
+use std::rc::{ Rc };
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Precedence {
     Lowest,
+    Assign,
+    Coalesce,
     Equals,
     LessGreater,
+    Range,
     Sum,
     Product,
     Prefix,
     Call,
     Index,
+    Member,
 }
 
-pub fn precedences (kind: TokenKind) -> Precedence {
-    match kind {
-        TokenKind::Eq       => Precedence::Equals,
-        TokenKind::NotEq    => Precedence::Equals,
-        TokenKind::Lt       => Precedence::LessGreater,
-        TokenKind::Gt       => Precedence::LessGreater,
-        TokenKind::Plus     => Precedence::Sum,
-        TokenKind::Minus    => Precedence::Sum,
-        TokenKind::Slash    => Precedence::Product,
-        TokenKind::Asterisk => Precedence::Product,
-        TokenKind::Lparen   => Precedence::Call,
-        TokenKind::Lbracket => Precedence::Index,
-        _                   => Precedence::Lowest
+impl Precedence {
+    /// Look up a binary operator precedence level by the name used in `infix` declarations.
+    pub fn from_name(name: &str) -> Option<Precedence> {
+        match name {
+            "equals"      => Some(Precedence::Equals),
+            "lessgreater" => Some(Precedence::LessGreater),
+            "sum"         => Some(Precedence::Sum),
+            "product"     => Some(Precedence::Product),
+            _             => None,
+        }
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+impl Associativity {
+    pub fn from_name(name: &str) -> Option<Associativity> {
+        match name {
+            "left"  => Some(Associativity::Left),
+            "right" => Some(Associativity::Right),
+            _       => None,
+        }
+    }
+}
+
+pub type PrefixParseFn = fn(&mut Parser) -> Option<Ast>;
+pub type InfixParseFn = fn(&mut Parser, Box<Ast>) -> Option<Ast>;
+
+#[derive(Debug, Copy, Clone)]
+pub struct InfixRule {
+    pub precedence: Precedence,
+    pub associativity: Associativity,
+    pub parse: InfixParseFn,
+}
+
+/// Pratt parsing table.
+/// Prefix and infix parse functions are registered per token kind.
+/// User-defined operators (TokenKind::Operator) are registered per symbol.
+#[derive(Debug, Clone)]
+pub struct ParseTable {
+    prefix: HashMap<TokenKind, PrefixParseFn>,
+    infix: HashMap<TokenKind, InfixRule>,
+    operators: HashMap<String, InfixRule>,
+}
+
+impl ParseTable {
+    pub fn new() -> ParseTable {
+        let mut table = ParseTable {
+            prefix: HashMap::new(),
+            infix: HashMap::new(),
+            operators: HashMap::new(),
+        };
+
+        table.register_prefix(TokenKind::Identifier, Parser::parse_identifier);
+        table.register_prefix(TokenKind::Integer, Parser::parse_integer_literal);
+        table.register_prefix(TokenKind::Bang, Parser::parse_prefix_expression);
+        table.register_prefix(TokenKind::Minus, Parser::parse_prefix_expression);
+        table.register_prefix(TokenKind::True, Parser::parse_boolean);
+        table.register_prefix(TokenKind::False, Parser::parse_boolean);
+        table.register_prefix(TokenKind::Null, Parser::parse_null_literal);
+        table.register_prefix(TokenKind::Lparen, Parser::parse_grouped_expression);
+        table.register_prefix(TokenKind::If, Parser::parse_if_expression);
+        table.register_prefix(TokenKind::Try, Parser::parse_try_expression);
+        table.register_prefix(TokenKind::For, Parser::parse_for_expression);
+        table.register_prefix(TokenKind::Import, Parser::parse_import_expression);
+        table.register_prefix(TokenKind::Function, Parser::parse_function_literal);
+        table.register_prefix(TokenKind::Pipe, Parser::parse_lambda_literal);
+        table.register_prefix(TokenKind::String, Parser::parse_string_literal);
+        table.register_prefix(TokenKind::Lbracket, Parser::parse_array_literal);
+
+        table.register_infix(TokenKind::Assign, Precedence::Assign, Associativity::Right, Parser::parse_assign_expression);
+        table.register_infix(TokenKind::PlusAssign, Precedence::Assign, Associativity::Right, Parser::parse_assign_expression);
+        table.register_infix(TokenKind::MinusAssign, Precedence::Assign, Associativity::Right, Parser::parse_assign_expression);
+        table.register_infix(TokenKind::AsteriskAssign, Precedence::Assign, Associativity::Right, Parser::parse_assign_expression);
+        table.register_infix(TokenKind::SlashAssign, Precedence::Assign, Associativity::Right, Parser::parse_assign_expression);
+        table.register_infix(TokenKind::Eq, Precedence::Equals, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::NotEq, Precedence::Equals, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::Lt, Precedence::LessGreater, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::Gt, Precedence::LessGreater, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::QuestionQuestion, Precedence::Coalesce, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::In, Precedence::LessGreater, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::DotDot, Precedence::Range, Associativity::Left, Parser::parse_range_expression);
+        table.register_infix(TokenKind::DotDotEq, Precedence::Range, Associativity::Left, Parser::parse_range_expression);
+        table.register_infix(TokenKind::Plus, Precedence::Sum, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::Minus, Precedence::Sum, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::Slash, Precedence::Product, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::Asterisk, Precedence::Product, Associativity::Left, Parser::parse_infix_expression);
+        table.register_infix(TokenKind::Lparen, Precedence::Call, Associativity::Left, Parser::parse_call_expression);
+        table.register_infix(TokenKind::Lbracket, Precedence::Index, Associativity::Left, Parser::parse_index_expression);
+        table.register_infix(TokenKind::QuestionLbracket, Precedence::Index, Associativity::Left, Parser::parse_index_expression);
+        table.register_infix(TokenKind::Dot, Precedence::Member, Associativity::Left, Parser::parse_member_expression);
+        table.register_infix(TokenKind::QuestionDot, Precedence::Member, Associativity::Left, Parser::parse_member_expression);
+
+        table
+    }
+
+    pub fn register_prefix(&mut self, kind: TokenKind, parse: PrefixParseFn) {
+        self.prefix.insert(kind, parse);
+    }
+
+    pub fn register_infix(&mut self, kind: TokenKind, precedence: Precedence, associativity: Associativity, parse: InfixParseFn) {
+        self.infix.insert(kind, InfixRule { precedence: precedence, associativity: associativity, parse: parse });
+    }
+
+    /// Register a user-defined infix operator symbol such as `<>`.
+    /// It is parsed into Ast::InfixExpression and evaluated by calling the function bound to the symbol.
+    pub fn register_operator(&mut self, symbol: &str, precedence: Precedence, associativity: Associativity) -> Result<(), String> {
+        if symbol.len() == 0 || !symbol.chars().all(is_operator_char) {
+            return Err(format!("invalid operator symbol: {}", symbol));
+        }
+
+        if BUILTIN_OPERATORS.contains(&symbol) {
+            return Err(format!("cannot redefine built-in operator: {}", symbol));
+        }
+
+        if precedence as u8 <= Precedence::Assign as u8 || precedence as u8 >= Precedence::Prefix as u8 {
+            return Err(format!("invalid precedence for operator {}: {:?}", symbol, precedence));
+        }
+
+        self.operators.insert(symbol.to_string(), InfixRule {
+            precedence: precedence,
+            associativity: associativity,
+            parse: Parser::parse_infix_expression,
+        });
+
+        Ok(())
+    }
+
+    /// Symbols of user-defined operators, for the lexer.
+    pub fn operator_symbols(&self) -> Vec<String> {
+        self.operators.keys().cloned().collect()
+    }
+
+    fn prefix_rule(&self, token: &Token) -> Option<PrefixParseFn> {
+        self.prefix.get(&token.kind).cloned()
+    }
+
+    fn infix_rule(&self, token: &Token) -> Option<InfixRule> {
+        match token.kind {
+            TokenKind::Operator => self.operators.get(&token.literal).cloned(),
+            _                   => self.infix.get(&token.kind).cloned(),
+        }
+    }
+}
+
+impl Default for ParseTable {
+    fn default() -> ParseTable {
+        ParseTable::new()
+    }
+}
+
+/// Expressions and types that may be nested in one another, a limit the stack of a
+/// default thread holds
+const MAX_NESTING_DEPTH: usize = 200;
+
+const BUILTIN_OPERATORS: [&str; 25] = ["=", "+", "-", "!", "*", "/", "<", ">", "|", ".", ":", "==", "!=", "=>",
+                                       "->", "+=", "-=", "*=", "/=", "..", "..=", "??", "?.", "?[", "..."];
+
+fn is_operator_char(ch: char) -> bool {
+    "+-*/<>=!&|^%~?.:@$#".contains(ch)
+}
+
+/// Placeholder for an omitted expression or type annotation
+fn empty_expression() -> Ast {
+    Ast::Expression { token: Token::new(TokenKind::Illegal, "".to_string()) }
+}
+
 #[derive(Debug, Clone)]
 pub struct Parser {
     pub lexier: Lexier,
     cur_token: Token,
     peek_token: Token,
+    table: ParseTable,
+    depth: usize,           // expressions and types being parsed
+    too_deep: bool,         // whether the input was skipped after nesting too deeply
     pub errors: Vec<String>,
 }
 
 impl Parser {
     pub fn new(lexier: Lexier) -> Parser {
+        Parser::new_with_table(lexier, ParseTable::new())
+    }
+
+    /// Create parser with a customized Pratt table. Operator symbols in the table are passed to the lexier.
+    pub fn new_with_table(mut lexier: Lexier, table: ParseTable) -> Parser {
+        for symbol in table.operator_symbols() {
+            lexier.add_operator(symbol);
+        }
+
         let mut parser = Parser { lexier: lexier,
-                                  cur_token:  Token {
-                                      kind: TokenKind::Illegal,
-                                      literal: "".to_string()},
-                                  peek_token: Token {
-                                      kind: TokenKind::Illegal,
-                                      literal: "".to_string()},
+                                  cur_token:  Token::new(TokenKind::Illegal, "".to_string()),
+                                  peek_token: Token::new(TokenKind::Illegal, "".to_string()),
+                                  table: table,
+                                  depth: 0,
+                                  too_deep: false,
                                   errors: Vec::new(),
         };
 
@@ -61,6 +234,33 @@ impl Parser {
         self.cur_token = self.peek_token.clone();
         self.peek_token = self.lexier.next_token();
     }
+
+    pub fn cur_token(&self) -> &Token {
+        &self.cur_token
+    }
+
+    pub fn peek_token(&self) -> &Token {
+        &self.peek_token
+    }
+
+    pub fn table(&self) -> &ParseTable {
+        &self.table
+    }
+
+    pub fn register_prefix(&mut self, kind: TokenKind, parse: PrefixParseFn) {
+        self.table.register_prefix(kind, parse);
+    }
+
+    pub fn register_infix(&mut self, kind: TokenKind, precedence: Precedence, associativity: Associativity, parse: InfixParseFn) {
+        self.table.register_infix(kind, precedence, associativity, parse);
+    }
+
+    pub fn register_operator(&mut self, symbol: &str, precedence: Precedence, associativity: Associativity) -> Result<(), String> {
+        self.table.register_operator(symbol, precedence, associativity)?;
+        self.lexier.add_operator(symbol.to_string());
+
+        Ok(())
+    }
     
     pub fn parse_program(&mut self) -> Option<Ast> {
         let mut program = Ast::Program { statements: Vec::new() };
@@ -86,7 +286,8 @@ impl Parser {
 
     fn parse_statement(&mut self) -> Option<Ast>{
         match self.cur_token.kind {
-            TokenKind::Let    => {
+            TokenKind::Let    |
+            TokenKind::Const  => {
                 match self.parse_let_statement() {
                     Some(value) => Some(value),
                     None        => return None,
@@ -98,6 +299,54 @@ impl Parser {
                     None        => return None,
                 }
             }
+            TokenKind::Throw  => {
+                match self.parse_throw_statement() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
+            TokenKind::Yield  => {
+                match self.parse_yield_statement() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
+            TokenKind::Struct => {
+                match self.parse_struct_declaration() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
+            TokenKind::Enum   => {
+                match self.parse_enum_declaration() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
+            TokenKind::Class  => {
+                match self.parse_class_declaration() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
+            TokenKind::Import if self.peek_token.kind == TokenKind::String => {
+                match self.parse_import_statement() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
+            TokenKind::Export => {
+                match self.parse_export_statement() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
+            TokenKind::Infix  => {
+                match self.parse_infix_declaration() {
+                    Some(value) => Some(value),
+                    None        => return None,
+                }
+            }
             _ => {
                 match self.parse_expression_statement() {
                     Some(value) => Some(value),
@@ -119,6 +368,15 @@ impl Parser {
             value: self.cur_token.literal.clone(),
         });
 
+        let mut annotation = Box::new(empty_expression());
+        if self.peek_token_is(TokenKind::Colon) {
+            self.next_token();
+            annotation = match self.parse_type() {
+                Some(value) => Box::new(value),
+                None        => return None,
+            };
+        }
+
         if !self.expect_peek(TokenKind::Assign) {
             return None
         }
@@ -137,6 +395,7 @@ impl Parser {
         Some( Ast::LetStatement {
             token: token,
             ident: ident,
+            annotation: annotation,
             value: value,
         })
     }
@@ -162,6 +421,420 @@ impl Parser {
     }
         
 
+    fn parse_throw_statement(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        self.next_token();
+
+        let value = match self.parse_expression(Precedence::Lowest) {
+            Some(value) => Box::new(value),
+            _           => return None,
+        };
+
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some( Ast::ThrowStatement {
+            token: token,
+            value: value,
+        })
+    }
+
+    /// Parse `yield value;`, which makes the enclosing function a generator
+    fn parse_yield_statement(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        self.next_token();
+
+        let value = match self.parse_expression(Precedence::Lowest) {
+            Some(value) => Box::new(value),
+            _           => return None,
+        };
+
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some( Ast::YieldStatement {
+            token: token,
+            value: value,
+        })
+    }
+
+    /// Parse `struct Point { x, y }`
+    fn parse_struct_declaration(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::Identifier) {
+            return None;
+        }
+
+        let name = Ast::Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+        };
+
+        if !self.expect_peek(TokenKind::Lbrace) {
+            return None;
+        }
+
+        let mut fields: Vec<Box<Ast>> = Vec::new();
+
+        while !self.peek_token_is(TokenKind::Rbrace) {
+            if !self.expect_peek(TokenKind::Identifier) {
+                return None;
+            }
+
+            let field = self.cur_token.literal.clone();
+            if fields.iter().any(|f| f.to_string() == field) {
+                self.errors.push(format!("duplicate field {} in struct {}", field, name.to_string()));
+                return None;
+            }
+            fields.push(Box::new(Ast::Identifier { token: self.cur_token.clone(), value: field }));
+
+            if self.peek_token_is(TokenKind::Comma) {
+                self.next_token();
+            }
+            else if !self.peek_token_is(TokenKind::Rbrace) {
+                self.peek_error(TokenKind::Rbrace);
+                return None;
+            }
+        }
+        self.next_token();
+
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(Ast::StructDeclaration {
+            token: token,
+            name: Box::new(name),
+            fields: fields,
+        })
+    }
+
+    /// Parse `enum Shape { Circle(r), Rect(w, h), Empty }`
+    fn parse_enum_declaration(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::Identifier) {
+            return None;
+        }
+
+        let name = Ast::Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+        };
+
+        if !self.expect_peek(TokenKind::Lbrace) {
+            return None;
+        }
+
+        let mut variants: Vec<Box<Ast>> = Vec::new();
+
+        while !self.peek_token_is(TokenKind::Rbrace) {
+            if !self.expect_peek(TokenKind::Identifier) {
+                return None;
+            }
+
+            let variant_token = self.cur_token.clone();
+            let variant_name = self.cur_token.literal.clone();
+            if variants.iter().any(|v| match **v {
+                Ast::EnumVariant { ref name, .. } => *name == variant_name,
+                _                                 => false,
+            }) {
+                self.errors.push(format!("duplicate variant {} in enum {}", variant_name, name.to_string()));
+                return None;
+            }
+
+            let mut fields = Vec::new();
+            if self.peek_token_is(TokenKind::Lparen) {
+                self.next_token();
+                fields = match self.parse_function_parameters(TokenKind::Rparen) {
+                    Some(value) => value,
+                    None        => return None,
+                };
+            }
+            variants.push(Box::new(Ast::EnumVariant { token: variant_token, name: variant_name, fields: fields }));
+
+            if self.peek_token_is(TokenKind::Comma) {
+                self.next_token();
+            }
+            else if !self.peek_token_is(TokenKind::Rbrace) {
+                self.peek_error(TokenKind::Rbrace);
+                return None;
+            }
+        }
+        self.next_token();
+
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(Ast::EnumDeclaration {
+            token: token,
+            name: Box::new(name),
+            variants: variants,
+        })
+    }
+
+    /// Parse `class Counter extends Base { init(n) { .. } inc() { .. } }`
+    fn parse_class_declaration(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::Identifier) {
+            return None;
+        }
+
+        let name = Ast::Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+        };
+
+        let mut superclass = Ast::Expression {
+            token: Token::new(TokenKind::Illegal, "".to_string())
+        };
+
+        if self.peek_token_is(TokenKind::Extends) {
+            self.next_token();
+
+            if !self.expect_peek(TokenKind::Identifier) {
+                return None;
+            }
+
+            superclass = Ast::Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+            };
+        }
+
+        if !self.expect_peek(TokenKind::Lbrace) {
+            return None;
+        }
+
+        let mut methods: Vec<Box<Ast>> = Vec::new();
+
+        while !self.peek_token_is(TokenKind::Rbrace) {
+            if !self.expect_peek(TokenKind::Identifier) {
+                return None;
+            }
+
+            let method_token = self.cur_token.clone();
+            let method_name = self.cur_token.literal.clone();
+            if methods.iter().any(|m| match **m {
+                Ast::MethodDefinition { ref name, .. } => *name == method_name,
+                _                                      => false,
+            }) {
+                self.errors.push(format!("duplicate method {} in class {}", method_name, name.to_string()));
+                return None;
+            }
+
+            if !self.expect_peek(TokenKind::Lparen) {
+                return None;
+            }
+
+            let (parameters, parameter_types) = match self.parse_typed_parameters(TokenKind::Rparen) {
+                Some(value) => value,
+                None        => return None,
+            };
+
+            let return_type = match self.parse_return_type() {
+                Some(value) => value,
+                None        => return None,
+            };
+
+            if !self.expect_peek(TokenKind::Lbrace) {
+                return None;
+            }
+
+            let body = match self.parse_block_statement().and_then(|body| self.check_yields(body)) {
+                Some(value) => value,
+                None        => return None,
+            };
+
+            let function = Ast::FunctionLiteral {
+                token: Token::new(TokenKind::Function, "fn".to_string()),
+                parameters: parameters,
+                parameter_types: parameter_types,
+                return_type: Box::new(return_type),
+                body: Rc::new(body),
+            };
+            methods.push(Box::new(Ast::MethodDefinition { token: method_token, name: method_name, function: Box::new(function) }));
+        }
+        self.next_token();
+
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(Ast::ClassDeclaration {
+            token: token,
+            name: Box::new(name),
+            superclass: Box::new(superclass),
+            methods: methods,
+        })
+    }
+
+    /// Parse `import "lib/math.mk" as math;`
+    /// Without `as`, the module is bound to the file name without extension.
+    fn parse_import_statement(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        self.next_token();
+
+        let path = self.cur_token.literal.clone();
+        let path_literal = Ast::StringLiteral { token: self.cur_token.clone(), value: path.clone() };
+
+        let alias = if self.peek_token_is(TokenKind::As) {
+            self.next_token();
+
+            if !self.expect_peek(TokenKind::Identifier) {
+                return None;
+            }
+
+            Ast::Identifier { token: self.cur_token.clone(), value: self.cur_token.literal.clone() }
+        }
+        else {
+            let name = match std::path::Path::new(&path).file_stem() {
+                Some(value) => value.to_string_lossy().to_string(),
+                None        => "".to_string(),
+            };
+
+            if name.len() == 0 || !name.chars().all(|ch| ch.is_alphabetic() || ch == '_') {
+                self.errors.push(format!("cannot name module \"{}\", use import .. as", path));
+                return None;
+            }
+
+            Ast::Identifier { token: Token::new(TokenKind::Identifier, name.clone()), value: name }
+        };
+
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(Ast::ImportStatement {
+            token: token,
+            path: Box::new(path_literal),
+            alias: Box::new(alias),
+        })
+    }
+
+    /// Parse `import(path)`
+    fn parse_import_expression(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::Lparen) {
+            return None;
+        }
+
+        self.next_token();
+
+        let path = match self.parse_expression(Precedence::Lowest) {
+            Some(value) => value,
+            None        => return None,
+        };
+
+        if !self.expect_peek(TokenKind::Rparen) {
+            return None;
+        }
+
+        Some(Ast::ImportExpression { token: token, path: Box::new(path) })
+    }
+
+    /// Parse `export` followed by a let, const, struct, enum or class declaration
+    fn parse_export_statement(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        self.next_token();
+
+        match self.cur_token.kind {
+            TokenKind::Let    |
+            TokenKind::Const  |
+            TokenKind::Struct |
+            TokenKind::Enum   |
+            TokenKind::Class  => (),
+            _ => {
+                self.errors.push(format!("export must be followed by a declaration, got {}", self.cur_token.get_kind_literal()));
+                return None;
+            }
+        }
+
+        let statement = match self.parse_statement() {
+            Some(value) => value,
+            None        => return None,
+        };
+
+        Some(Ast::ExportStatement { token: token, statement: Box::new(statement) })
+    }
+
+    /// Parse `infix "<>" sum left = fn(a, b) { ... };`
+    /// The operator is registered as soon as the declaration header is read.
+    fn parse_infix_declaration(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::String) {
+            return None;
+        }
+
+        let operator = self.cur_token.literal.clone();
+
+        if !self.expect_peek(TokenKind::Identifier) {
+            return None;
+        }
+
+        let precedence_name = self.cur_token.literal.clone();
+        let precedence = match Precedence::from_name(&precedence_name) {
+            Some(value) => value,
+            None        => {
+                self.errors.push(format!("unknown precedence: {}", precedence_name));
+                return None;
+            }
+        };
+
+        let mut associativity_name = "left".to_string();
+        if self.peek_token_is(TokenKind::Identifier) {
+            self.next_token();
+            associativity_name = self.cur_token.literal.clone();
+        }
+
+        let associativity = match Associativity::from_name(&associativity_name) {
+            Some(value) => value,
+            None        => {
+                self.errors.push(format!("unknown associativity: {}", associativity_name));
+                return None;
+            }
+        };
+
+        if let Err(msg) = self.register_operator(&operator, precedence, associativity) {
+            self.errors.push(msg);
+            return None;
+        }
+
+        if !self.expect_peek(TokenKind::Assign) {
+            return None;
+        }
+
+        self.next_token();
+
+        let value = match self.parse_expression(Precedence::Lowest) {
+            Some(value) => Box::new(value),
+            None        => return None,
+        };
+
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(Ast::InfixDeclaration {
+            token: token,
+            operator: operator,
+            precedence: precedence_name,
+            associativity: associativity_name,
+            value: value,
+        })
+    }
+
     fn parse_expression_statement(&mut self) -> Option<Ast> {
         let expression = match self.parse_expression(Precedence::Lowest) {
             Some(value) => Box::new(value),
@@ -173,125 +846,72 @@ impl Parser {
             expression: expression,
         };
 
-        if self.peek_token_is(TokenKind::Semicolon) {
-            self.next_token();
+        if self.peek_token_is(TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(statement)
+    }
+
+    pub fn parse_expression (&mut self, precedence: Precedence) -> Option<Ast> {
+        self.parse_expression_with_binding_power(precedence as u8)
+    }
+
+    fn parse_expression_with_binding_power(&mut self, binding_power: u8) -> Option<Ast> {
+        if !self.enter_nesting() {
+            return None;
         }
 
-        Some(statement)
+        let expression = self.parse_nested_expression(binding_power);
+        self.depth -= 1;
+        expression
     }
 
-    fn parse_expression (&mut self, precedence: Precedence) -> Option<Ast> {
-        let mut left_exp = Ast::Expression {
-            token : Token {
-                kind: TokenKind::Illegal,
-                literal: "".to_string()
+    fn parse_nested_expression(&mut self, binding_power: u8) -> Option<Ast> {
+        let prefix = match self.table.prefix_rule(&self.cur_token) {
+            Some(value) => value,
+            None        => {
+                self.no_prefix_parse_fn_error(self.cur_token.kind);
+                return None;
             }
         };
 
-        match self.cur_token.kind {
-            TokenKind::Identifier    {..}  => {
-                left_exp = match self.parse_identifier() {
-                    Some(value) => value,
-                    None        => return None,
-                };
-            },
-            TokenKind::Integer  {..}  => {
-                left_exp = match self.parse_integer_literal() {
-                    Some(value) => value,
-                    None        => return None,
-                };
-            }
-            TokenKind::Bang     {..} |
-            TokenKind::Minus    {..}  => {
-                left_exp = match self.parse_prefix_expression() {
-                    Some(value) => value,
-                    None        => return None,
-                };
-            }
-            TokenKind::True     {..} |
-            TokenKind::False    {..}  => {
-                left_exp = match self.parse_boolean() {
-                    Some(value) => value,
-                    None        => return None,
-                };
-            }
-            TokenKind::Lparen   {..}  => {
-                left_exp = match self.parse_grouped_expression() {
-                    Some(value) => value,
-                    None        => return None,
-                };
-            }
-            TokenKind::If       {..}  => {
-                left_exp = match self.parse_if_expression() {
-                    Some(value) => value,
-                    None        => return None,
-                };
-            }
-            TokenKind::Function {..}  => {
-                left_exp = match self.parse_function_literal() {
-                    Some(value) => value,
-                    None        => return None,
-                }
-            },
-            TokenKind::String {..} => {
-                left_exp = match self.parse_string_literal() {
-                    Some(value) => value,
-                    None        => return None,
-                }
-            }
-            TokenKind::Lbracket {..} => {
-                left_exp = match self.parse_array_literal() {
-                    Some(value) => value,
-                    None        => return None,
-                }
-            }
-            _ => {
-                self.no_prefix_parse_fn_error(self.cur_token.kind);
+        let mut left_exp = match prefix(self) {
+            Some(value) => value,
+            None        => return None,
+        };
+
+        // each operator nests the expression so far one level deeper
+        let depth = self.depth;
+        while !self.peek_token_is(TokenKind::Semicolon) && binding_power < (self.peek_precedence() as u8) {
+            let infix = match self.table.infix_rule(&self.peek_token) {
+                Some(value) => value,
+                None        => break,
+            };
+            if !self.enter_nesting() {
+                self.depth = depth;
                 return None;
             }
-        }
-        
-        while !self.peek_token_is(TokenKind::Semicolon) && (precedence.clone() as u8) < (self.peek_precedence() as u8) {
-            match self.peek_token.kind {
-                TokenKind::Plus     {..} |
-                TokenKind::Minus    {..} |
-                TokenKind::Slash    {..} |
-                TokenKind::Asterisk {..} |
-                TokenKind::Eq       {..} |
-                TokenKind::NotEq    {..} |
-                TokenKind::Lt       {..} |
-                TokenKind::Gt       {..} => {
-                    self.next_token();
-                    left_exp = match self.parse_infix_expression(Box::new(left_exp)) {
-                        Some(value) => value,
-                        None        => return None,
-                    };
-                },
-                TokenKind::Lparen   {..} => {
-                    self.next_token();
-                    left_exp = match self.parse_call_expression(Box::new(left_exp)) {
-                        Some(value) => value,
-                        None        => return None,
-                    };
-                },
-                TokenKind::Lbracket {..} => {
-                    self.next_token();
-                    left_exp = match self.parse_index_expression(Box::new(left_exp)) {
-                        Some(value) => value,
-                        None        => return None,
-                    };
-                },
-                _ => {
-                    return Some(left_exp);
-                }
-            }
 
+            self.next_token();
+            left_exp = match (infix.parse)(self, Box::new(left_exp)) {
+                Some(value) => value,
+                None        => {
+                    self.depth = depth;
+                    return None;
+                },
+            };
         }
+        self.depth = depth;
 
         Some(left_exp)
     }
 
     fn parse_identifier(&mut self) -> Option<Ast> {
+        if self.peek_token_is(TokenKind::Arrow) {
+            return self.parse_arrow_function();
+        }
+
         Some(Ast::Identifier { token: self.cur_token.clone(), value: self.cur_token.literal.clone() })
     }
 
@@ -326,6 +946,30 @@ impl Parser {
         })
     }
 
+    /// Parse `start..end` or `start..=end`, optionally followed by `step n`
+    fn parse_range_expression(&mut self, start: Box<Ast>) -> Option<Ast> {
+        let token = self.cur_token.clone();
+        self.next_token();
+
+        let end = match self.parse_expression(Precedence::Range) {
+            Some(value) => value,
+            None        => return None,
+        };
+
+        // `step` is only a keyword after the end of a range
+        let mut step = empty_expression();
+        if self.peek_token_is(TokenKind::Identifier) && self.peek_token.literal == "step" {
+            self.next_token();
+            self.next_token();
+            step = match self.parse_expression(Precedence::Range) {
+                Some(value) => value,
+                None        => return None,
+            };
+        }
+
+        Some(Ast::RangeExpression { token: token, start: start, end: Box::new(end), step: Box::new(step) })
+    }
+
     fn parse_infix_expression(&mut self, left: Box<Ast>) -> Option<Ast>{
         let mut expression = Ast::InfixExpression {
             token: self.cur_token.clone(),
@@ -334,19 +978,20 @@ impl Parser {
             right: Box::new(
                 Ast::Expression {
                     token:
-                    Token {
-                        kind: TokenKind::Illegal,
-                        literal: "".to_string(),
-                    }
+                    Token::new(TokenKind::Illegal, "".to_string())
                 }
             ),
         };
 
-        let precedence = self.cur_precedence();
+        let binding_power = match self.table.infix_rule(&self.cur_token) {
+            Some(InfixRule { precedence, associativity: Associativity::Right, .. }) => precedence as u8 - 1,
+            Some(InfixRule { precedence, .. })                                     => precedence as u8,
+            None                                                                   => Precedence::Lowest as u8,
+        };
         self.next_token();
 
         if let Ast::InfixExpression { ref mut right, ..} = expression {
-            *right = match self.parse_expression(precedence) {
+            *right = match self.parse_expression_with_binding_power(binding_power) {
                 Some(value) => Box::new(value),
                 _           => return None
             };
@@ -356,6 +1001,45 @@ impl Parser {
         
     }
 
+    /// Parse `target = value`. A compound assignment `target += value` is parsed as
+    /// `target = target + value`.
+    fn parse_assign_expression(&mut self, target: Box<Ast>) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        match *target {
+            Ast::Identifier { .. } => (),
+            Ast::MemberExpression { ref token, .. } if token.kind == TokenKind::Dot => (),
+            _ => {
+                self.errors.push(format!("invalid assignment target: {}", target.to_string()));
+                return None;
+            }
+        }
+
+        self.next_token();
+
+        let value = match self.parse_expression_with_binding_power(Precedence::Assign as u8 - 1) {
+            Some(value) => Box::new(value),
+            None        => return None,
+        };
+
+        let operator = match token.kind {
+            TokenKind::PlusAssign     => Token::new(TokenKind::Plus, "+".to_string()),
+            TokenKind::MinusAssign    => Token::new(TokenKind::Minus, "-".to_string()),
+            TokenKind::AsteriskAssign => Token::new(TokenKind::Asterisk, "*".to_string()),
+            TokenKind::SlashAssign    => Token::new(TokenKind::Slash, "/".to_string()),
+            _ => return Some(Ast::AssignExpression { token: token, target: target, value: value }),
+        };
+
+        let value = Box::new(Ast::InfixExpression {
+            token: operator.clone(),
+            left: target.clone(),
+            operator: operator.literal,
+            right: value,
+        });
+
+        Some(Ast::AssignExpression { token: token, target: target, value: value })
+    }
+
     fn parse_boolean(&mut self) -> Option<Ast> {
         Some(Ast::Boolean {
             token: self.cur_token.clone(),
@@ -363,6 +1047,10 @@ impl Parser {
         })
     }
 
+    fn parse_null_literal(&mut self) -> Option<Ast> {
+        Some(Ast::NullLiteral { token: self.cur_token.clone() })
+    }
+
     fn parse_grouped_expression(&mut self) -> Option<Ast> {
         self.next_token();
         let expression = match self.parse_expression(Precedence::Lowest) {
@@ -380,10 +1068,7 @@ impl Parser {
     fn parse_if_expression(&mut self) -> Option<Ast> {
         let empty_expression = Ast::Expression {
             token:
-            Token {
-                kind: TokenKind::Illegal,
-                literal: "".to_string(),
-            }
+            Token::new(TokenKind::Illegal, "".to_string())
         };
         let mut expression = Ast::IfExpression {
             token: self.cur_token.clone(),
@@ -438,12 +1123,135 @@ impl Parser {
         Some(expression)
     }
 
-    fn parse_block_statement(&mut self) -> Option<Ast> {
-        let mut block = Ast::BlockStatement {
+    /// Parse `for (x in iterable) { .. }`
+    fn parse_for_expression(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::Lparen) {
+            return None;
+        }
+
+        if !self.expect_peek(TokenKind::Identifier) {
+            return None;
+        }
+
+        let variable = Ast::Identifier {
             token: self.cur_token.clone(),
-            statements: Vec::new(),
+            value: self.cur_token.literal.clone(),
+        };
+
+        if !self.expect_peek(TokenKind::In) {
+            return None;
+        }
+
+        self.next_token();
+
+        let iterable = match self.parse_expression(Precedence::Lowest) {
+            Some(value) => value,
+            None        => return None,
+        };
+
+        if !self.expect_peek(TokenKind::Rparen) {
+            return None;
+        }
+
+        if !self.expect_peek(TokenKind::Lbrace) {
+            return None;
+        }
+
+        let body = match self.parse_block_statement() {
+            Some(value) => value,
+            None        => return None,
+        };
+
+        Some(Ast::ForExpression {
+            token: token,
+            variable: Box::new(variable),
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+        })
+    }
+
+    /// Parse `try { .. } catch (e) { .. } finally { .. }`
+    /// Either the catch or the finally clause may be omitted, but not both.
+    fn parse_try_expression(&mut self) -> Option<Ast> {
+        let empty_expression = Ast::Expression {
+            token:
+            Token::new(TokenKind::Illegal, "".to_string())
+        };
+        let token = self.cur_token.clone();
+        let mut parameter = empty_expression.clone();
+        let mut catch = empty_expression.clone();
+        let mut finally = empty_expression.clone();
+
+        if !self.expect_peek(TokenKind::Lbrace) {
+            return None;
+        }
+
+        let block = match self.parse_block_statement() {
+            Some(value) => value,
+            _           => return None,
         };
 
+        if self.peek_token_is(TokenKind::Catch) {
+            self.next_token();
+
+            if !self.expect_peek(TokenKind::Lparen) {
+                return None;
+            }
+            if !self.expect_peek(TokenKind::Identifier) {
+                return None;
+            }
+
+            parameter = Ast::Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+            };
+
+            if !self.expect_peek(TokenKind::Rparen) {
+                return None;
+            }
+            if !self.expect_peek(TokenKind::Lbrace) {
+                return None;
+            }
+
+            catch = match self.parse_block_statement() {
+                Some(value) => value,
+                _           => return None,
+            };
+        }
+
+        if self.peek_token_is(TokenKind::Finally) {
+            self.next_token();
+
+            if !self.expect_peek(TokenKind::Lbrace) {
+                return None;
+            }
+
+            finally = match self.parse_block_statement() {
+                Some(value) => value,
+                _           => return None,
+            };
+        }
+
+        if let (Ast::Expression { .. }, Ast::Expression { .. }) = (&catch, &finally) {
+            self.errors.push("try without catch or finally".to_string());
+            return None;
+        }
+
+        Some(Ast::TryExpression {
+            token: token,
+            block: Box::new(block),
+            parameter: Box::new(parameter),
+            catch: Box::new(catch),
+            finally: Box::new(finally),
+        })
+    }
+
+    fn parse_block_statement(&mut self) -> Option<Ast> {
+        let token = self.cur_token.clone();
+        let mut statements = Vec::new();
+
         self.next_token();
 
         while !self.cur_token_is(TokenKind::Rbrace) && !self.cur_token_is(TokenKind::Eof) {
@@ -453,31 +1261,26 @@ impl Parser {
             };
             
             match &statement {
-                Ast    => {
-                    if let Ast::BlockStatement { ref mut statements, ..} = block {
-                        statements.push(Box::new(statement));
-                    };
-                },
+                Ast    => statements.push(Box::new(statement)),
                 _ => return None,
             }
             
             self.next_token();
         }
 
-        Some(block)
+        Some(Ast::BlockStatement { token: token, statements: Rc::new(statements) })
     }
 
     fn parse_function_literal(&mut self) -> Option<Ast> {
         let mut literal = Ast::FunctionLiteral {
             token: self.cur_token.clone(),
             parameters: Vec::new(),
-            body: Box::new(
+            parameter_types: Vec::new(),
+            return_type: Box::new(empty_expression()),
+            body: Rc::new(
                 Ast::Expression {
                 token:
-                Token {
-                    kind: TokenKind::Illegal,
-                    literal: "".to_string(),
-                }
+                Token::new(TokenKind::Illegal, "".to_string())
             }),
         };
 
@@ -485,11 +1288,18 @@ impl Parser {
             return None;
         }
         
-        if let Ast::FunctionLiteral { ref mut parameters, .. } = literal {
-            *parameters = match self.parse_function_parameters() {
+        if let Ast::FunctionLiteral { ref mut parameters, ref mut parameter_types, ref mut return_type, .. } = literal {
+            let (names, types) = match self.parse_typed_parameters(TokenKind::Rparen) {
                 Some(value) => value,
                 _           => return None,
             };
+            *parameters = names;
+            *parameter_types = types;
+
+            *return_type = match self.parse_return_type() {
+                Some(value) => Box::new(value),
+                _           => return None,
+            };
         }
         
         if !self.expect_peek(TokenKind::Lbrace) {
@@ -497,8 +1307,8 @@ impl Parser {
         }
 
         if let Ast::FunctionLiteral { ref mut body, .. } = literal {
-            *body = match self.parse_block_statement() {
-                Some(value) => Box::new(value),
+            *body = match self.parse_block_statement().and_then(|body| self.check_yields(body)) {
+                Some(value) => Rc::new(value),
                 _           => return None,
             };
         }
@@ -506,10 +1316,92 @@ impl Parser {
         Some(literal)
     }
 
-    fn parse_function_parameters(&mut self) -> Option<Vec<Box<Ast>>> {
+    /// Reject a function body with a `yield` that its generator cannot stop at
+    fn check_yields(&mut self, body: Ast) -> Option<Ast> {
+        if misplaced_yield(&body) {
+            self.errors.push("yield must be a statement of the generator body, an if block or a for loop".to_string());
+            return None;
+        }
+
+        Some(body)
+    }
+
+    /// Parse concise lambda `|x, y| expr` into Ast::FunctionLiteral
+    fn parse_lambda_literal(&mut self) -> Option<Ast> {
+        let (parameters, parameter_types) = match self.parse_typed_parameters(TokenKind::Pipe) {
+            Some(value) => value,
+            _           => return None,
+        };
+
+        self.parse_lambda_body(parameters, parameter_types)
+    }
+
+    /// Parse concise lambda `x => expr` into Ast::FunctionLiteral
+    fn parse_arrow_function(&mut self) -> Option<Ast> {
+        let parameter = Ast::Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+        };
+
+        if !self.expect_peek(TokenKind::Arrow) {
+            return None;
+        }
+
+        self.parse_lambda_body(vec![Box::new(parameter)], vec![Box::new(empty_expression())])
+    }
+
+    /// Parse lambda body. An expression body is wrapped in an implicit Ast::BlockStatement.
+    fn parse_lambda_body(&mut self, parameters: Vec<Box<Ast>>, parameter_types: Vec<Box<Ast>>) -> Option<Ast> {
+        let token = Token::new(TokenKind::Function, "fn".to_string());
+
+        if self.peek_token_is(TokenKind::Lbrace) {
+            self.next_token();
+            let body = match self.parse_block_statement().and_then(|body| self.check_yields(body)) {
+                Some(value) => Rc::new(value),
+                _           => return None,
+            };
+
+            return Some(Ast::FunctionLiteral {
+                token: token,
+                parameters: parameters,
+                parameter_types: parameter_types,
+                return_type: Box::new(empty_expression()),
+                body: body,
+            });
+        }
+
+        self.next_token();
+
+        let block_token = self.cur_token.clone();
+        let expression = match self.parse_expression(Precedence::Lowest) {
+            Some(value) => Box::new(value),
+            _           => return None,
+        };
+
+        let body = match self.check_yields(Ast::BlockStatement {
+            token: block_token.clone(),
+            statements: Rc::new(vec![Box::new(Ast::ExpressionStatement {
+                token: block_token,
+                expression: expression,
+            })]),
+        }) {
+            Some(value) => Rc::new(value),
+            _           => return None,
+        };
+
+        Some(Ast::FunctionLiteral {
+            token: token,
+            parameters: parameters,
+            parameter_types: parameter_types,
+            return_type: Box::new(empty_expression()),
+            body: body,
+        })
+    }
+
+    fn parse_function_parameters(&mut self, end: TokenKind) -> Option<Vec<Box<Ast>>> {
         let mut identifiers = Vec::new();
 
-        if self.peek_token_is(TokenKind::Rparen) {
+        if self.peek_token_is(end) {
             self.next_token();
             return Some(identifiers);
         }
@@ -535,13 +1427,160 @@ impl Parser {
             identifiers.push(Box::new(identifier));
         }
 
-        if !self.expect_peek(TokenKind::Rparen) {
+        if !self.expect_peek(end) {
             return None;
         }
 
         Some(identifiers)
     }
 
+    /// Parse parameters with optional `: type` annotations.
+    /// Returns the identifiers and, for each of them, its annotation or an empty expression.
+    fn parse_typed_parameters(&mut self, end: TokenKind) -> Option<(Vec<Box<Ast>>, Vec<Box<Ast>>)> {
+        let mut identifiers = Vec::new();
+        let mut types = Vec::new();
+
+        if self.peek_token_is(end) {
+            self.next_token();
+            return Some((identifiers, types));
+        }
+
+        loop {
+            // a variadic parameter `...rest` collects the remaining arguments
+            let mut spread = None;
+            if self.peek_token_is(TokenKind::Ellipsis) {
+                self.next_token();
+                spread = Some(self.cur_token.clone());
+            }
+
+            if !self.expect_peek(TokenKind::Identifier) {
+                return None;
+            }
+
+            let identifier = Ast::Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+            };
+            match spread {
+                Some(ref token) => identifiers.push(Box::new(Ast::SpreadExpression { token: token.clone(), value: Box::new(identifier) })),
+                None            => identifiers.push(Box::new(identifier)),
+            }
+
+            if self.peek_token_is(TokenKind::Colon) {
+                self.next_token();
+                match self.parse_type() {
+                    Some(value) => types.push(Box::new(value)),
+                    None        => return None,
+                }
+            }
+            else {
+                types.push(Box::new(empty_expression()));
+            }
+
+            if !self.peek_token_is(TokenKind::Comma) {
+                break;
+            }
+            if spread.is_some() {
+                self.errors.push("variadic parameter must be the last parameter".to_string());
+                return None;
+            }
+            self.next_token();
+        }
+
+        if !self.expect_peek(end) {
+            return None;
+        }
+
+        Some((identifiers, types))
+    }
+
+    /// Parse optional `-> type` after function parameters
+    fn parse_return_type(&mut self) -> Option<Ast> {
+        if !self.peek_token_is(TokenKind::ThinArrow) {
+            return Some(empty_expression());
+        }
+        self.next_token();
+
+        self.parse_type()
+    }
+
+    /// Parse type annotation following the current token:
+    /// a type name, `[T]` or `fn(T, ..) -> R`
+    fn parse_type(&mut self) -> Option<Ast> {
+        if !self.enter_nesting() {
+            return None;
+        }
+
+        let typ = self.parse_nested_type();
+        self.depth -= 1;
+        typ
+    }
+
+    fn parse_nested_type(&mut self) -> Option<Ast> {
+        self.next_token();
+        let token = self.cur_token.clone();
+
+        match token.kind {
+            TokenKind::Identifier | TokenKind::Null => {
+                Some(Ast::TypeExpression { token: token.clone(), name: token.literal, arguments: Vec::new() })
+            },
+            TokenKind::Lbracket => {
+                let element = match self.parse_type() {
+                    Some(value) => value,
+                    None        => return None,
+                };
+
+                if !self.expect_peek(TokenKind::Rbracket) {
+                    return None;
+                }
+
+                Some(Ast::TypeExpression { token: token, name: "array".to_string(), arguments: vec![Box::new(element)] })
+            },
+            TokenKind::Function => {
+                if !self.expect_peek(TokenKind::Lparen) {
+                    return None;
+                }
+
+                let mut arguments = Vec::new();
+                if self.peek_token_is(TokenKind::Rparen) {
+                    self.next_token();
+                }
+                else {
+                    loop {
+                        match self.parse_type() {
+                            Some(value) => arguments.push(Box::new(value)),
+                            None        => return None,
+                        }
+
+                        if !self.peek_token_is(TokenKind::Comma) {
+                            break;
+                        }
+                        self.next_token();
+                    }
+
+                    if !self.expect_peek(TokenKind::Rparen) {
+                        return None;
+                    }
+                }
+
+                if !self.expect_peek(TokenKind::ThinArrow) {
+                    return None;
+                }
+
+                match self.parse_type() {
+                    Some(value) => arguments.push(Box::new(value)),
+                    None        => return None,
+                }
+
+                Some(Ast::TypeExpression { token: token, name: "fn".to_string(), arguments: arguments })
+            },
+            _ => {
+                self.errors.push(format!("expected type, got {}", token.get_kind_literal()));
+                None
+            },
+        }
+    }
+
     fn parse_call_expression(&mut self, function: Box<Ast> ) -> Option<Ast> {
         let expression = Ast::CallExpression {
             token: self.cur_token.clone(),
@@ -591,12 +1630,74 @@ impl Parser {
         Some(Ast::StringLiteral { token: self.cur_token.clone(), value: self.cur_token.literal.clone()})
     }
 
+    /// Parse an array literal, or a list comprehension if the first element is followed by `for`
     fn parse_array_literal(&mut self) -> Option<Ast> {
         let token = self.cur_token.clone();
-        let elements = self.parse_expression_list(TokenKind::Rbracket);
+        let mut elements = Vec::new();
+
+        if self.peek_token_is(TokenKind::Rbracket) {
+            self.next_token();
+            return Some(Ast::ArrayLiteral { token: token, elements: elements });
+        }
+
+        self.next_token();
+        if let Some(value) = self.parse_list_element() {
+            if self.peek_token_is(TokenKind::For) {
+                return self.parse_list_comprehension(token, value);
+            }
+            elements.push(Box::new(value));
+        }
+
+        let elements = self.parse_expression_list_rest(elements, TokenKind::Rbracket);
 
         Some(Ast::ArrayLiteral { token: token, elements: elements})
     }
+
+    /// Parse the clauses of `[element for x in xs if cond ..]` after the element.
+    /// Each `for` clause may be followed by more `for` and `if` clauses.
+    fn parse_list_comprehension(&mut self, token: Token, element: Ast) -> Option<Ast> {
+        let mut clauses = Vec::new();
+
+        while self.peek_token_is(TokenKind::For) || (clauses.len() != 0 && self.peek_token_is(TokenKind::If)) {
+            self.next_token();
+            let clause_token = self.cur_token.clone();
+
+            let variable = match clause_token.kind {
+                TokenKind::For => {
+                    if !self.expect_peek(TokenKind::Identifier) {
+                        return None;
+                    }
+                    let variable = Ast::Identifier {
+                        token: self.cur_token.clone(),
+                        value: self.cur_token.literal.clone(),
+                    };
+                    if !self.expect_peek(TokenKind::In) {
+                        return None;
+                    }
+                    variable
+                },
+                _ => empty_expression(),
+            };
+
+            self.next_token();
+            let value = match self.parse_expression(Precedence::Lowest) {
+                Some(value) => value,
+                None        => return None,
+            };
+
+            clauses.push(Box::new(Ast::ComprehensionClause {
+                token: clause_token,
+                variable: Box::new(variable),
+                value: Box::new(value),
+            }));
+        }
+
+        if !self.expect_peek(TokenKind::Rbracket) {
+            return None;
+        }
+
+        Some(Ast::ListComprehension { token: token, element: Box::new(element), clauses: clauses })
+    }
     
     fn parse_expression_list(&mut self, end: TokenKind) -> Vec<Box<Ast>> {
         let mut list = Vec::new();
@@ -607,14 +1708,19 @@ impl Parser {
         }
 
         self.next_token();
-        if let Some(value) = self.parse_expression(Precedence::Lowest) {
+        if let Some(value) = self.parse_list_element() {
             list.push(Box::new(value));
         }
 
+        self.parse_expression_list_rest(list, end)
+    }
+
+    /// Parse the remaining `, expr` items of a list and its end token
+    fn parse_expression_list_rest(&mut self, mut list: Vec<Box<Ast>>, end: TokenKind) -> Vec<Box<Ast>> {
         while self.peek_token_is(TokenKind::Comma) {
             self.next_token();
             self.next_token();
-            if let Some(value) = self.parse_expression(Precedence::Lowest) {
+            if let Some(value) = self.parse_list_element() {
                 list.push(Box::new(value));
             }
         }
@@ -626,31 +1732,112 @@ impl Parser {
         list
     }
 
-    fn parse_index_expression(&mut self, left: Box<Ast>) -> Option<Ast> {
-        let token = self.cur_token.clone();
+    /// Parse an element of an array literal or an argument of a call, which may be spread: `...xs`
+    fn parse_list_element(&mut self) -> Option<Ast> {
+        if !self.cur_token_is(TokenKind::Ellipsis) {
+            return self.parse_expression(Precedence::Lowest);
+        }
 
+        let token = self.cur_token.clone();
         self.next_token();
-        let index = match self.parse_expression(Precedence::Lowest) {
-            Some(value) => Box::new(value),
-            None        => return None,
+
+        match self.parse_expression(Precedence::Lowest) {
+            Some(value) => Some(Ast::SpreadExpression { token: token, value: Box::new(value) }),
+            None        => None,
+        }
+    }
+
+    fn parse_index_expression(&mut self, left: Box<Ast>) -> Option<Ast> {
+        let token = self.cur_token.clone();
+        let empty_expression = Ast::Expression {
+            token:
+            Token::new(TokenKind::Illegal, "".to_string())
         };
 
+        let mut index = Box::new(empty_expression.clone());
+        
+        if !self.peek_token_is(TokenKind::Colon) {
+            self.next_token();
+            index = match self.parse_expression(Precedence::Lowest) {
+                Some(value) => Box::new(value),
+                None        => return None,
+            };
+        }
+
+        if self.peek_token_is(TokenKind::Colon) {
+            self.next_token();
+            return self.parse_slice_expression(token, left, index);
+        }
+
         if !self.expect_peek(TokenKind::Rbracket) {
             return None;
         }
 
         Some(Ast::IndexExpression {token: token, left: left, index: index})
     }
+
+    fn parse_member_expression(&mut self, object: Box<Ast>) -> Option<Ast> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::Identifier) {
+            return None;
+        }
+
+        let member = Box::new(Ast::Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+        });
+
+        Some(Ast::MemberExpression { token: token, object: object, member: member })
+    }
+
+    /// Parse the rest of `left[start:end]`. Current token is ':'.
+    fn parse_slice_expression(&mut self, token: Token, left: Box<Ast>, start: Box<Ast>) -> Option<Ast> {
+        let mut end = Box::new(Ast::Expression {
+            token:
+            Token::new(TokenKind::Illegal, "".to_string())
+        });
+
+        if !self.peek_token_is(TokenKind::Rbracket) {
+            self.next_token();
+            end = match self.parse_expression(Precedence::Lowest) {
+                Some(value) => Box::new(value),
+                None        => return None,
+            };
+        }
+
+        if !self.expect_peek(TokenKind::Rbracket) {
+            return None;
+        }
+
+        Some(Ast::SliceExpression {token: token, left: left, start: start, end: end})
+    }
     
-    fn cur_token_is(&mut self, kind: TokenKind) -> bool {
+    pub fn cur_token_is(&mut self, kind: TokenKind) -> bool {
         self.cur_token.kind.clone() as u8  == kind as u8
     }
     
-    fn peek_token_is(&mut self, kind: TokenKind) -> bool {
+    pub fn peek_token_is(&mut self, kind: TokenKind) -> bool {
         self.peek_token.kind.clone() as u8 == kind as u8
     }
     
-    fn expect_peek(&mut self, kind: TokenKind) -> bool {
+    /// Start parsing a nested expression or type. Returns false if it would exceed
+    /// MAX_NESTING_DEPTH, and skips the rest of the input, which the stack might not hold.
+    fn enter_nesting(&mut self) -> bool {
+        if self.depth >= MAX_NESTING_DEPTH {
+            self.errors.push(format!("expression nested too deeply: more than {} levels", MAX_NESTING_DEPTH));
+            self.too_deep = true;
+            while !self.cur_token_is(TokenKind::Eof) {
+                self.next_token();
+            }
+            return false;
+        }
+
+        self.depth += 1;
+        true
+    }
+
+    pub fn expect_peek(&mut self, kind: TokenKind) -> bool {
         if self.peek_token_is(kind) {
             self.next_token();
             return true;
@@ -661,15 +1848,19 @@ impl Parser {
         false
     }
 
-    fn cur_precedence(&mut self) -> Precedence {
-        precedences(self.cur_token.kind)
-    }
-    
     fn peek_precedence(&mut self) -> Precedence {
-        precedences(self.peek_token.kind)
+        match self.table.infix_rule(&self.peek_token) {
+            Some(rule) => rule.precedence,
+            None       => Precedence::Lowest,
+        }
     }
     
     fn peek_error(&mut self, kind: TokenKind) {
+        // the unclosed brackets of skipped input are not reported
+        if self.too_deep {
+            return;
+        }
+
         let msg = format!("expeceted next token to be {}, got {} instead",
                           kind.get_kind_literal(), self.peek_token.get_kind_literal() );
 
@@ -874,7 +2065,21 @@ fn test_operator_precedence_parsing() {
         ("add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))", "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))"),
         ("add(a + b + c * d / f + g)", "add((((a + b) + ((c * d) / f)) + g))"),
         ("a * [1, 2, 3, 4][b * c] * d", "((a * ([1, 2, 3, 4][(b * c)])) * d)"),
-        ("add(a * b[2], b[1], 2 * [1, 2][1])", "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))")
+        ("add(a * b[2], b[1], 2 * [1, 2][1])", "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))"),
+        ("a[-1]", "(a[(-1)])"),
+        ("a[1:3]", "(a[1:3])"),
+        ("a[:n + 1]", "(a[:(n + 1)])"),
+        ("a[1:]", "(a[1:])"),
+        ("a[:]", "(a[:])"),
+        ("a[1:2][0]", "((a[1:2])[0])"),
+        ("a.b", "(a.b)"),
+        ("-a.b", "(-(a.b))"),
+        ("a.b.c", "((a.b).c)"),
+        ("a.push(1 + 2)", "(a.push)((1 + 2))"),
+        ("a[0].len() * 2", "(((a[0]).len)() * 2)"),
+        ("\"abc\".len()", "(abc.len)()"),
+        ("a = b = 1 + 2", "(a = (b = (1 + 2)))"),
+        ("a = b == c", "(a = (b == c))")
     ];
 
     for (_i, test) in tests.iter().enumerate() {
@@ -899,7 +2104,7 @@ pub mod tests {
     use crate::ast:: { Ast };
     use crate::lexier:: { Lexier };
     use crate::token:: { TokenKind, Token};
-    use crate::parser:: { Parser };
+    use crate::parser:: { Parser, ParseTable, Precedence, Associativity };
     
     #[derive(Clone)]
     enum Type {
@@ -1218,6 +2423,382 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_lambda_literal_parsing() {
+        let tests = [
+            ("|x| x * 2", "fn(x) {(x * 2)}"),
+            ("|x, y| x + y", "fn(x, y) {(x + y)}"),
+            ("|| 1", "fn() {1}"),
+            ("x => x * 2", "fn(x) {(x * 2)}"),
+            ("|x| { x; }", "fn(x) {x}"),
+            ("apply(|x| x * 2, 5)", "apply(fn(x) {(x * 2)}, 5)"),
+            ("apply(x => x + 1, y)", "apply(fn(x) {(x + 1)}, y)"),
+        ];
+
+        for test in tests.iter() {
+            let lexier = Lexier::new(test.0.to_string());
+            let mut parser = Parser::new(lexier);
+
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            if let Ast::Program { ref statements } = program {
+                if let Ast::ExpressionStatement { ref expression, .. } = *statements[0] {
+                    match **expression {
+                        Ast::FunctionLiteral { ref body, .. } => {
+                            if let Ast::BlockStatement { ref statements, .. } = **body {
+                                if statements.len() != 1 {
+                                    panic!("body is not 1 statement. got={}", statements.len());
+                                }
+                            }
+                        },
+                        Ast::CallExpression { .. } => (),
+                        _ => panic!("expression not Ast::FunctionLiteral. got={}", expression.get_kind_literal()),
+                    }
+                }
+            }
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_user_defined_operator_parsing() {
+        let tests = [
+            ("infix \"<>\" equals = fn(a, b) { a }; a <> b + c", "(a <> (b + c))"),
+            ("infix \"<+>\" product = fn(a, b) { a }; a + b <+> c", "(a + (b <+> c))"),
+            ("infix \"++\" sum left = fn(a, b) { a }; a ++ b ++ c", "((a ++ b) ++ c)"),
+            ("infix \"^^\" product right = fn(a, b) { a }; a ^^ b ^^ c", "(a ^^ (b ^^ c))"),
+            ("infix \"~>\" lessgreater right = fn(a, b) { a }; a ~> b - c", "(a ~> (b - c))"),
+        ];
+
+        for test in tests.iter() {
+            let lexier = Lexier::new(test.0.to_string());
+            let mut parser = Parser::new(lexier);
+
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            if let Ast::Program { ref statements } = program {
+                assert_eq!(statements[0].get_kind_literal(), "InfixDeclaration");
+                assert_eq!(statements[1].to_string(), test.1.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_infix_declaration() {
+        let tests = [
+            ("infix \"+\" sum = fn(a, b) { a };", "cannot redefine built-in operator: +"),
+            ("infix \"->\" sum = fn(a, b) { a };", "cannot redefine built-in operator: ->"),
+            ("infix \"??\" sum = fn(a, b) { a };", "cannot redefine built-in operator: ??"),
+            ("infix \"?.\" sum = fn(a, b) { a };", "cannot redefine built-in operator: ?."),
+            ("infix \"...\" sum = fn(a, b) { a };", "cannot redefine built-in operator: ..."),
+            ("infix \"ab\" sum = fn(a, b) { a };", "invalid operator symbol: ab"),
+            ("infix \"<>\" call = fn(a, b) { a };", "unknown precedence: call"),
+            ("infix \"<>\" sum up = fn(a, b) { a };", "unknown associativity: up"),
+        ];
+
+        for test in tests.iter() {
+            let lexier = Lexier::new(test.0.to_string());
+            let mut parser = Parser::new(lexier);
+            parser.parse_program().unwrap();
+
+            assert_eq!(parser.errors[0], test.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let tests = [
+            (format!("{}1{}", "(".repeat(199), ")".repeat(199)), true),
+            (format!("{}1{}", "[".repeat(199), "]".repeat(199)), true),
+            (format!("{}1", "1 + ".repeat(198)), true),
+            (format!("{}1{}", "(".repeat(200000), ")".repeat(200000)), false),
+            (format!("{}1", "-".repeat(200000)), false),
+            (format!("{}1", "1 + ".repeat(200000)), false),
+            (format!("let x: {}int{} = 1;", "[".repeat(200000), "]".repeat(200000)), false),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.clone()));
+            parser.parse_program().unwrap();
+
+            if test.1 {
+                parser.check_parser_errors();
+            }
+            else {
+                assert_eq!(parser.errors, vec!["expression nested too deeply: more than 200 levels".to_string()]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_misplaced_yield() {
+        let tests = [
+            ("fn() { try { yield 1; } catch (e) { 0 } }", false),
+            ("fn(c) { let x = if (c) { yield 1; 2 }; }", false),
+            ("fn(f) { f(if (true) { yield 1; }) }", false),
+            ("fn(x) { if (if (x) { yield x; }) { x } }", false),
+            ("fn(xs) { 1 + for (x in xs) { yield x; } }", false),
+            ("class A { each() { try { yield 1; } finally { 0 } } }", false),
+            ("|x| [if (x) { yield x; }]", false),
+            ("fn(xs) { for (x in xs) { if (x) { yield x; } else { yield 0; } } }", true),
+            ("fn() { try { fn() { yield 1; } } catch (e) { 0 } }", true),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            parser.parse_program().unwrap();
+
+            let misplaced = parser.errors.contains(&"yield must be a statement of the generator body, an if block or a for loop".to_string());
+            assert_eq!(misplaced, !test.1, "input: {}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_try_expression_parsing() {
+        let tests = [
+            ("try { a } catch (e) { b }", "try { a } catch (e) { b }"),
+            ("try { a } finally { c }", "try { a } finally { c }"),
+            ("try { a } catch (e) { b } finally { c }", "try { a } catch (e) { b } finally { c }"),
+            ("throw x + 1;", "throw (x + 1);"),
+        ];
+
+        for test in tests.iter() {
+            let lexier = Lexier::new(test.0.to_string());
+            let mut parser = Parser::new(lexier);
+
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+
+        let mut parser = Parser::new(Lexier::new("try { a }".to_string()));
+        parser.parse_program().unwrap();
+        assert_eq!(parser.errors[0], "try without catch or finally".to_string());
+    }
+
+    #[test]
+    fn test_type_declaration_parsing() {
+        let tests = [
+            ("struct Point { x, y }", "struct Point { x, y }"),
+            ("struct Point { x, y, };", "struct Point { x, y }"),
+            ("enum Shape { Circle(r), Rect(w, h), Empty }", "enum Shape { Circle(r), Rect(w, h), Empty }"),
+            ("class A { init(n) { self.n = n; } get() { self.n } }", "class A { init(n) {((self.n) = n)} get() {(self.n)} }"),
+            ("class B extends A { inc() { self.n += 1 } }", "class B extends A { inc() {((self.n) = ((self.n) + 1))} }"),
+            ("x *= 2 + 1", "(x = (x * (2 + 1)))"),
+            ("import \"lib/math.mk\" as m", "import \"lib/math.mk\" as m;"),
+            ("import \"lib/math.mk\"", "import \"lib/math.mk\" as math;"),
+            ("let m = import(dir + \"/m.mk\");", "let m = import((dir + /m.mk));"),
+            ("export const x = 1;", "export const x = 1;"),
+        ];
+
+        for test in tests.iter() {
+            let lexier = Lexier::new(test.0.to_string());
+            let mut parser = Parser::new(lexier);
+
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+
+        let tests = [
+            ("struct Point { x, x }", "duplicate field x in struct Point"),
+            ("enum Shape { Empty, Empty }", "duplicate variant Empty in enum Shape"),
+            ("class A { f() {} f() {} }", "duplicate method f in class A"),
+            ("1 += 2", "invalid assignment target: 1"),
+            ("import \"lib/1.mk\"", "cannot name module \"lib/1.mk\", use import .. as"),
+            ("export x + 1", "export must be followed by a declaration, got Identifier"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            parser.parse_program().unwrap();
+
+            assert_eq!(parser.errors[0], test.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_type_annotation_parsing() {
+        let tests = [
+            ("let x: int = 5;", "let x: int = 5;"),
+            ("let xs: [string] = [];", "let xs: [string] = [];"),
+            ("fn(a: int, b: string) -> bool { a }", "fn(a: int, b: string) -> bool {a}"),
+            ("fn(a, b: [int]) { a }", "fn(a, b: [int]) {a}"),
+            ("let f: fn(int, int) -> int = |a: int, b| a + b;", "let f: fn(int, int) -> int = fn(a: int, b) {(a + b)};"),
+            ("let g: fn() -> fn(Point) -> any = 1;", "let g: fn() -> fn(Point) -> any = 1;"),
+            ("class A { get(n: int) -> int { n } }", "class A { get(n: int) -> int {n} }"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+
+        let tests = [
+            ("let x: = 5;", "expected type, got Assign"),
+            ("fn(a: [int) { a }", "expeceted next token to be Rbracket, got Rparen instead"),
+            ("let f: fn(int) = 1;", "expeceted next token to be ThinArrow, got Assign instead"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            parser.parse_program().unwrap();
+
+            assert_eq!(parser.errors[0], test.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generator_parsing() {
+        let tests = [
+            ("fn(xs) { for (x in xs) { yield x * 2; } }", "fn(xs) {for (x in xs) { yield (x * 2); }}"),
+            ("for (c in \"ab\") { puts(c) }", "for (c in ab) { puts(c) }"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+
+        let tests = [
+            ("for x in xs { x }", "expeceted next token to be Lparen, got Identifier instead"),
+            ("for (x of xs) { x }", "expeceted next token to be In, got Identifier instead"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            parser.parse_program().unwrap();
+
+            assert_eq!(parser.errors[0], test.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_list_comprehension_parsing() {
+        let tests = [
+            ("[x * x for x in xs if x > 0]", "[(x * x) for x in xs if (x > 0)]"),
+            ("[[x, y] for x in xs for y in f(x)]", "[[x, y] for x in xs for y in f(x)]"),
+            ("[x for x in xs if x > 0 if x < 9 for y in ys]", "[x for x in xs if (x > 0) if (x < 9) for y in ys]"),
+            ("[x, y]", "[x, y]"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+
+        let tests = [
+            ("[x for 1 in xs]", "expeceted next token to be Identifier, got Integer instead"),
+            ("[x for x in xs, 1]", "expeceted next token to be Rbracket, got Comma instead"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            parser.parse_program().unwrap();
+
+            assert_eq!(parser.errors[0], test.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_range_parsing() {
+        let tests = [
+            ("0..10", "(0..10)"),
+            ("1..=n + 1", "(1..=(n + 1))"),
+            ("a * 2..b step -2", "((a * 2)..b step (-2))"),
+            ("x in 0..n == true", "((x in (0..n)) == true)"),
+            ("let step = 2; 0..9 step step", "let step = 2;(0..9 step step)"),
+            ("for (i in 0..3) { i }", "for (i in (0..3)) { i }"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+    }
+
+    #[test]
+    fn test_optional_chaining_parsing() {
+        let tests = [
+            ("null", "null"),
+            ("a?.b.c", "((a?.b).c)"),
+            ("a?[0]?[1:]", "((a?[0])?[1:])"),
+            ("a?.f(1)", "(a?.f)(1)"),
+            ("a ?? b ?? c", "((a ?? b) ?? c)"),
+            ("a ?? b == c", "(a ?? (b == c))"),
+            ("x = a ?? 1", "(x = (a ?? 1))"),
+            ("let n: null = null;", "let n: null = null;"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+
+        let mut parser = Parser::new(Lexier::new("a?.b = 1".to_string()));
+        parser.parse_program().unwrap();
+        assert_eq!(parser.errors[0], "invalid assignment target: (a?.b)".to_string());
+    }
+
+    #[test]
+    fn test_spread_parsing() {
+        let tests = [
+            ("[...a, 0, ...b]", "[...a, 0, ...b]"),
+            ("[...a]", "[...a]"),
+            ("f(...args, 1)", "f(...args, 1)"),
+            ("[...xs.map(g), ...0..3]", "[...(xs.map)(g), ...(0..3)]"),
+            ("fn(a, ...rest) { rest }", "fn(a, ...rest) {rest}"),
+            ("fn(...rest: [int]) -> int { 0 }", "fn(...rest: [int]) -> int {0}"),
+        ];
+
+        for test in tests.iter() {
+            let mut parser = Parser::new(Lexier::new(test.0.to_string()));
+            let program = parser.parse_program().unwrap();
+            parser.check_parser_errors();
+
+            assert_eq!(program.to_string(), test.1.to_string());
+        }
+
+        let mut parser = Parser::new(Lexier::new("fn(...rest, a) { a }".to_string()));
+        parser.parse_program();
+        assert_eq!(parser.errors[0], "variadic parameter must be the last parameter".to_string());
+    }
+
+    #[test]
+    fn test_embedder_registered_operator() {
+        let mut table = ParseTable::new();
+        table.register_operator("<=>", Precedence::LessGreater, Associativity::Left).unwrap();
+        assert!(table.register_operator("..", Precedence::LessGreater, Associativity::Left).is_err());
+        assert!(table.register_operator("..=", Precedence::LessGreater, Associativity::Left).is_err());
+
+        let lexier = Lexier::new("1 <=> 2 + 3 == x".to_string());
+        let mut parser = Parser::new_with_table(lexier, table);
+        let program = parser.parse_program().unwrap();
+        parser.check_parser_errors();
+
+        assert_eq!(program.to_string(), "((1 <=> (2 + 3)) == x)");
+    }
+
     #[test]
     fn test_string_literal_expression() {
         let input = "\"Hello World\"".to_string();
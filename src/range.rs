@@ -0,0 +1,103 @@
+//! Lazy integer ranges created by `start..end`, `start..=end` and `start..end step n`.
+//!
+//! A range stores its bounds only; length, indexing and membership are computed from them,
+//! so `0..1000000` costs no more than `0..1`.
+
+#[derive(Debug, Clone)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,          // never zero; a negative step counts down
+    pub inclusive: bool,    // whether end is part of the range
+}
+
+impl Range {
+    pub fn new(start: i64, end: i64, step: i64, inclusive: bool) -> Range {
+        Range { start: start, end: end, step: step, inclusive: inclusive }
+    }
+
+    pub fn len(&self) -> i64 {
+        let (start, end, step) = (self.start as i128, self.end as i128, self.step as i128);
+        let distance = if step > 0 { end - start } else { start - end };
+        let step = step.abs();
+
+        let len = if self.inclusive {
+            if distance < 0 { 0 } else { distance / step + 1 }
+        }
+        else {
+            if distance <= 0 { 0 } else { (distance + step - 1) / step }
+        };
+
+        len.min(i64::MAX as i128) as i64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Element at index, which must be in 0..len
+    pub fn get(&self, index: i64) -> i64 {
+        self.start + index * self.step
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let offset = value as i128 - self.start as i128;
+        if offset % self.step as i128 != 0 {
+            return false;
+        }
+
+        let index = offset / self.step as i128;
+        index >= 0 && index < self.len() as i128
+    }
+
+    /// Ranges are equal if they hold the same elements
+    pub fn same_elements(&self, other: &Range) -> bool {
+        let len = self.len();
+        if len != other.len() {
+            return false;
+        }
+
+        match len {
+            0 => true,
+            1 => self.start == other.start,
+            _ => self.start == other.start && self.step == other.step,
+        }
+    }
+
+    pub fn inspect(&self) -> String {
+        let operator = if self.inclusive { "..=" } else { ".." };
+        let mut string = format!("{}{}{}", self.start, operator, self.end);
+        if self.step != 1 {
+            string = format!("{} step {}", string, self.step);
+        }
+
+        string
+    }
+}
+
+#[test]
+fn test_range() {
+    let tests = [(Range::new(0, 5, 1, false), 5, vec![0, 1, 2, 3, 4]),
+                 (Range::new(1, 5, 1, true), 5, vec![1, 2, 3, 4, 5]),
+                 (Range::new(0, 10, 3, false), 4, vec![0, 3, 6, 9]),
+                 (Range::new(1, 10, 3, true), 4, vec![1, 4, 7, 10]),
+                 (Range::new(5, 0, -2, false), 3, vec![5, 3, 1]),
+                 (Range::new(4, 0, -2, true), 3, vec![4, 2, 0]),
+                 (Range::new(5, 5, 1, false), 0, vec![]),
+                 (Range::new(5, 0, 1, true), 0, vec![]),
+    ];
+
+    for test in tests.iter() {
+        assert_eq!(test.0.len(), test.1, "{}", test.0.inspect());
+        assert_eq!(test.0.is_empty(), test.1 == 0, "{}", test.0.inspect());
+        let elements: Vec<i64> = (0..test.0.len()).map(|i| test.0.get(i)).collect();
+        assert_eq!(elements, test.2, "{}", test.0.inspect());
+        for value in -1..12 {
+            assert_eq!(test.0.contains(value), test.2.contains(&value), "{} contains {}", test.0.inspect(), value);
+        }
+    }
+
+    assert_eq!(Range::new(i64::MIN, i64::MAX, 1, true).len(), i64::MAX);
+    assert!(Range::new(0, 4, 2, false).same_elements(&Range::new(0, 3, 2, true)));
+    assert_eq!(Range::new(0, 10, 2, true).inspect(), "0..=10 step 2");
+}
@@ -1,34 +1,67 @@
-use crate::ast:: { Ast };
+use crate::checker::{ Checker };
 use crate::env::*;
 use crate::eval::{ eval };
+use crate::fold::{ fold_constants };
 use crate::lexier::Lexier;
+use crate::object::{ Object, format_trace };
 use crate::token::{ TokenKind };
-use crate::parser:: { Parser };
+use crate::parser:: { Parser, ParseTable };
 use std::io::{ self, Write, stdin };
 
 
 pub fn start() {
     let mut env = Env::new();
+    let mut table = ParseTable::new();
+    let mut checker = Checker::new();
+    let mut input = String::new();
     loop {
-        print!(">> ");
+        if input.len() == 0 {
+            print!(">> ");
+        }
+        else {
+            print!(".. ");
+        }
         io::stdout().flush().unwrap();
-        let mut input = String::new();
+        let mut line = String::new();
         
-        match stdin().read_line(&mut input) {
+        match stdin().read_line(&mut line) {
+            Ok(0) => return,
             Ok(_) => {
-                let lexier = Lexier::new(input);
-                let mut parser = Parser::new(lexier);
+                // a blank continuation line forces evaluation of what has been typed so far
+                let force = input.len() != 0 && line.trim().len() == 0;
+                input.push_str(&line);
+
+                if !force && is_incomplete(&input, &table) {
+                    continue;
+                }
+
+                let lexier = Lexier::new(input.clone());
+                input.clear();
+                let mut parser = Parser::new_with_table(lexier, table.clone());
                 let program = parser.parse_program().unwrap();
+                table = parser.table().clone();
 
                 if parser.errors.len() != 0 {
-                    print_parse_errors(parser.errors);
+                    print_errors(parser.errors);
                     continue;
                 }
 
-                let evaluated = eval(program, &mut env);
+                let errors = checker.check(&program);
+                if errors.len() != 0 {
+                    print_errors(errors);
+                    continue;
+                }
+                print_errors(checker.warnings().to_vec());
+
+                let evaluated = eval(&fold_constants(program), &mut env);
                 match evaluated {
-                    Some(value) => println!("{}", value.inspect()),
-                    None        => println!("none"),
+                    Some(ref value) => println!("{}", value.inspect()),
+                    None            => println!("none"),
+                }
+                if let Some(Object::Error { ref trace, .. }) = evaluated {
+                    for line in format_trace(trace) {
+                        println!("{}", line);
+                    }
                 }
                 
             }
@@ -37,8 +70,112 @@ pub fn start() {
     }
 }
 
-fn print_parse_errors(errors: Vec<String>) {
+/// Check whether input needs more lines to be a complete statement:
+/// unbalanced brackets, an unterminated string or a trailing operator.
+pub fn is_incomplete(input: &str, table: &ParseTable) -> bool {
+    if input.chars().filter(|ch| *ch == '"').count() % 2 != 0 {
+        return true;
+    }
+
+    let mut lexier = Lexier::new(input.to_string());
+    for symbol in table.operator_symbols() {
+        lexier.add_operator(symbol);
+    }
+
+    let mut depth = 0;
+    let mut last = TokenKind::Semicolon;
+
+    loop {
+        let token = lexier.next_token();
+        match token.kind {
+            TokenKind::Eof      => break,
+            TokenKind::Lparen   |
+            TokenKind::Lbrace   |
+            TokenKind::Lbracket |
+            TokenKind::QuestionLbracket => depth += 1,
+            TokenKind::Rparen   |
+            TokenKind::Rbrace   |
+            TokenKind::Rbracket => depth -= 1,
+            _                   => (),
+        }
+        last = token.kind;
+    }
+
+    if depth > 0 {
+        return true;
+    }
+
+    match last {
+        TokenKind::Assign   |
+        TokenKind::PlusAssign |
+        TokenKind::MinusAssign |
+        TokenKind::AsteriskAssign |
+        TokenKind::SlashAssign |
+        TokenKind::As       |
+        TokenKind::Plus     |
+        TokenKind::Minus    |
+        TokenKind::Bang     |
+        TokenKind::Asterisk |
+        TokenKind::Slash    |
+        TokenKind::Lt       |
+        TokenKind::Gt       |
+        TokenKind::Eq       |
+        TokenKind::NotEq    |
+        TokenKind::Arrow    |
+        TokenKind::ThinArrow |
+        TokenKind::Operator |
+        TokenKind::Comma    |
+        TokenKind::Colon    |
+        TokenKind::DotDot   |
+        TokenKind::DotDotEq |
+        TokenKind::QuestionQuestion |
+        TokenKind::QuestionDot |
+        TokenKind::Ellipsis |
+        TokenKind::Dot      => true,
+        _                   => false,
+    }
+}
+
+fn print_errors(errors: Vec<String>) {
     for error in errors {
         println!("{}", error);
     }
 }
+
+#[test]
+fn test_is_incomplete() {
+    let table = ParseTable::new();
+    let tests = [("let x = 5;\n", false),
+                 ("let add = fn(x, y) {\n", true),
+                 ("let add = fn(x, y) {\n  x + y\n", true),
+                 ("let add = fn(x, y) {\n  x + y\n};\n", false),
+                 ("[1, 2,\n", true),
+                 ("[1, 2,\n 3]\n", false),
+                 ("add(1,\n", true),
+                 ("1 +\n", true),
+                 ("let x =\n", true),
+                 ("a.\n", true),
+                 ("let f = fn(x) ->\n", true),
+                 ("let r = 1..\n", true),
+                 ("let r = 1..=\n", true),
+                 ("let r = 1..=5\n", false),
+                 ("a ??\n", true),
+                 ("a?.\n", true),
+                 ("a?[\n", true),
+                 ("a?.b ?? c\n", false),
+                 ("f(1, ...\n", true),
+                 ("let ys = [...xs]\n", false),
+                 ("\"abc\n", true),
+                 ("\"abc\"\n", false),
+                 ("}\n", false),
+                 ("\n", false)
+    ];
+
+    for test in tests.iter() {
+        assert_eq!(is_incomplete(test.0, &table), test.1, "input: {:?}", test.0);
+    }
+
+    let mut table = ParseTable::new();
+    table.register_operator("<>", crate::parser::Precedence::Sum, crate::parser::Associativity::Left).unwrap();
+    assert!(is_incomplete("1 <>\n", &table));
+}
@@ -0,0 +1,575 @@
+//! Conversion between `ast::Ast` and JSON / S-expressions.
+//!
+//! # JSON schema
+//!
+//! Every node is an object whose `"type"` is the name of the `Ast` variant.
+//! Except for `Program`, every node has a `"token"` object holding the token it was
+//! parsed from: `{"kind": <TokenKind name, e.g. "Plus">, "literal": <source text>,
+//! "line": integer, "column": integer}`. The position is 1-based, 0 for a token that was not
+//! read from source, and may be omitted on input.
+//! The remaining members depend on the type:
+//!
+//! | type                  | members                                                         |
+//! |-----------------------|-----------------------------------------------------------------|
+//! | `Program`             | `statements`: [node]                                            |
+//! | `Expression`          | (none; with an `Illegal` token it marks an omitted expression)  |
+//! | `Identifier`          | `value`: string                                                 |
+//! | `LetStatement`        | `ident`: Identifier, `annotation`, `value`: node (token kind `Let` or `Const`) |
+//! | `ReturnStatement`     | `return_value`: node                                            |
+//! | `InfixDeclaration`    | `operator`, `precedence`, `associativity`: string, `value`: node |
+//! | `ExpressionStatement` | `expression`: node                                              |
+//! | `IntegerLiteral`      | `value`: integer                                                |
+//! | `Boolean`             | `value`: boolean                                                |
+//! | `NullLiteral`         | (none)                                                          |
+//! | `StringLiteral`       | `value`: string                                                 |
+//! | `PrefixExpression`    | `operator`: string, `right`: node                               |
+//! | `InfixExpression`     | `left`: node, `operator`: string, `right`: node                 |
+//! | `IfExpression`        | `condition`, `consequence`, `alternative`: node                 |
+//! | `BlockStatement`      | `statements`: [node]                                            |
+//! | `FunctionLiteral`     | `parameters`: [Identifier or SpreadExpression], `parameter_types`: [node], `return_type`: node, `body`: BlockStatement |
+//! | `CallExpression`      | `function`: node, `arguments`: [node]                           |
+//! | `ArrayLiteral`        | `elements`: [node]                                              |
+//! | `IndexExpression`     | `left`, `index`: node (token kind `QuestionLbracket` for `?[`)  |
+//! | `SliceExpression`     | `left`, `start`, `end`: node                                    |
+//! | `MemberExpression`    | `object`: node, `member`: Identifier (token kind `QuestionDot` for `?.`) |
+//! | `AssignExpression`    | `target`: Identifier or MemberExpression, `value`: node         |
+//! | `ThrowStatement`      | `value`: node                                                   |
+//! | `YieldStatement`      | `value`: node                                                   |
+//! | `ForExpression`       | `variable`: Identifier, `iterable`: node, `body`: BlockStatement |
+//! | `SpreadExpression`    | `value`: node; Identifier as a variadic parameter              |
+//! | `RangeExpression`     | `start`, `end`, `step`: node (token kind `DotDot` or `DotDotEq`) |
+//! | `ListComprehension`   | `element`: node, `clauses`: [ComprehensionClause]               |
+//! | `ComprehensionClause` | `variable`, `value`: node (token kind `For` or `If`)            |
+//! | `TryExpression`       | `block`, `parameter`, `catch`, `finally`: node                  |
+//! | `StructDeclaration`   | `name`: Identifier, `fields`: [Identifier]                      |
+//! | `EnumDeclaration`     | `name`: Identifier, `variants`: [EnumVariant]                   |
+//! | `EnumVariant`         | `name`: string, `fields`: [Identifier]                          |
+//! | `ClassDeclaration`    | `name`, `superclass`: node, `methods`: [MethodDefinition]       |
+//! | `MethodDefinition`    | `name`: string, `function`: FunctionLiteral                     |
+//! | `ImportStatement`     | `path`: StringLiteral, `alias`: Identifier                      |
+//! | `ImportExpression`    | `path`: node                                                    |
+//! | `ExportStatement`     | `statement`: node                                               |
+//! | `TypeExpression`      | `name`: string, `arguments`: [TypeExpression]                   |
+//!
+//! # S-expressions
+//!
+//! The S-expression form is the same tree written with `sexp::from_json`:
+//! `(InfixExpression :token (:kind "Plus" :literal "+" :line 1 :column 3) :left (...) :operator "+" :right (...))`.
+
+use crate::ast::{ Ast };
+use crate::json::{ self, Json };
+use crate::sexp;
+use crate::token::{ Token, TokenKind };
+use std::rc::{ Rc };
+
+pub fn to_json(node: &Ast) -> Json {
+    match node {
+        Ast::Program { statements } => object("Program", None, vec![
+            ("statements", list(statements)),
+        ]),
+        Ast::Expression { token } => object("Expression", Some(token), vec![]),
+        Ast::Identifier { token, value } => object("Identifier", Some(token), vec![
+            ("value", Json::String(value.to_string())),
+        ]),
+        Ast::LetStatement { token, ident, annotation, value } => object("LetStatement", Some(token), vec![
+            ("ident", to_json(ident)),
+            ("annotation", to_json(annotation)),
+            ("value", to_json(value)),
+        ]),
+        Ast::ReturnStatement { token, return_value } => object("ReturnStatement", Some(token), vec![
+            ("return_value", to_json(return_value)),
+        ]),
+        Ast::InfixDeclaration { token, operator, precedence, associativity, value } => object("InfixDeclaration", Some(token), vec![
+            ("operator", Json::String(operator.to_string())),
+            ("precedence", Json::String(precedence.to_string())),
+            ("associativity", Json::String(associativity.to_string())),
+            ("value", to_json(value)),
+        ]),
+        Ast::ExpressionStatement { token, expression } => object("ExpressionStatement", Some(token), vec![
+            ("expression", to_json(expression)),
+        ]),
+        Ast::IntegerLiteral { token, value } => object("IntegerLiteral", Some(token), vec![
+            ("value", Json::Integer(*value)),
+        ]),
+        Ast::Boolean { token, value } => object("Boolean", Some(token), vec![
+            ("value", Json::Boolean(*value)),
+        ]),
+        Ast::NullLiteral { token } => object("NullLiteral", Some(token), vec![]),
+        Ast::StringLiteral { token, value } => object("StringLiteral", Some(token), vec![
+            ("value", Json::String(value.to_string())),
+        ]),
+        Ast::PrefixExpression { token, operator, right } => object("PrefixExpression", Some(token), vec![
+            ("operator", Json::String(operator.to_string())),
+            ("right", to_json(right)),
+        ]),
+        Ast::InfixExpression { token, left, operator, right } => object("InfixExpression", Some(token), vec![
+            ("left", to_json(left)),
+            ("operator", Json::String(operator.to_string())),
+            ("right", to_json(right)),
+        ]),
+        Ast::IfExpression { token, condition, consequence, alternative } => object("IfExpression", Some(token), vec![
+            ("condition", to_json(condition)),
+            ("consequence", to_json(consequence)),
+            ("alternative", to_json(alternative)),
+        ]),
+        Ast::BlockStatement { token, statements } => object("BlockStatement", Some(token), vec![
+            ("statements", list(statements)),
+        ]),
+        Ast::FunctionLiteral { token, parameters, parameter_types, return_type, body } => object("FunctionLiteral", Some(token), vec![
+            ("parameters", list(parameters)),
+            ("parameter_types", list(parameter_types)),
+            ("return_type", to_json(return_type)),
+            ("body", to_json(body)),
+        ]),
+        Ast::CallExpression { token, function, arguments } => object("CallExpression", Some(token), vec![
+            ("function", to_json(function)),
+            ("arguments", list(arguments)),
+        ]),
+        Ast::ArrayLiteral { token, elements } => object("ArrayLiteral", Some(token), vec![
+            ("elements", list(elements)),
+        ]),
+        Ast::IndexExpression { token, left, index } => object("IndexExpression", Some(token), vec![
+            ("left", to_json(left)),
+            ("index", to_json(index)),
+        ]),
+        Ast::SliceExpression { token, left, start, end } => object("SliceExpression", Some(token), vec![
+            ("left", to_json(left)),
+            ("start", to_json(start)),
+            ("end", to_json(end)),
+        ]),
+        Ast::MemberExpression { token, object: obj, member } => object("MemberExpression", Some(token), vec![
+            ("object", to_json(obj)),
+            ("member", to_json(member)),
+        ]),
+        Ast::AssignExpression { token, target, value } => object("AssignExpression", Some(token), vec![
+            ("target", to_json(target)),
+            ("value", to_json(value)),
+        ]),
+        Ast::ThrowStatement { token, value } => object("ThrowStatement", Some(token), vec![
+            ("value", to_json(value)),
+        ]),
+        Ast::YieldStatement { token, value } => object("YieldStatement", Some(token), vec![
+            ("value", to_json(value)),
+        ]),
+        Ast::ForExpression { token, variable, iterable, body } => object("ForExpression", Some(token), vec![
+            ("variable", to_json(variable)),
+            ("iterable", to_json(iterable)),
+            ("body", to_json(body)),
+        ]),
+        Ast::SpreadExpression { token, value } => object("SpreadExpression", Some(token), vec![
+            ("value", to_json(value)),
+        ]),
+        Ast::RangeExpression { token, start, end, step } => object("RangeExpression", Some(token), vec![
+            ("start", to_json(start)),
+            ("end", to_json(end)),
+            ("step", to_json(step)),
+        ]),
+        Ast::ListComprehension { token, element, clauses } => object("ListComprehension", Some(token), vec![
+            ("element", to_json(element)),
+            ("clauses", list(clauses)),
+        ]),
+        Ast::ComprehensionClause { token, variable, value } => object("ComprehensionClause", Some(token), vec![
+            ("variable", to_json(variable)),
+            ("value", to_json(value)),
+        ]),
+        Ast::TryExpression { token, block, parameter, catch, finally } => object("TryExpression", Some(token), vec![
+            ("block", to_json(block)),
+            ("parameter", to_json(parameter)),
+            ("catch", to_json(catch)),
+            ("finally", to_json(finally)),
+        ]),
+        Ast::StructDeclaration { token, name, fields } => object("StructDeclaration", Some(token), vec![
+            ("name", to_json(name)),
+            ("fields", list(fields)),
+        ]),
+        Ast::EnumDeclaration { token, name, variants } => object("EnumDeclaration", Some(token), vec![
+            ("name", to_json(name)),
+            ("variants", list(variants)),
+        ]),
+        Ast::EnumVariant { token, name, fields } => object("EnumVariant", Some(token), vec![
+            ("name", Json::String(name.to_string())),
+            ("fields", list(fields)),
+        ]),
+        Ast::ClassDeclaration { token, name, superclass, methods } => object("ClassDeclaration", Some(token), vec![
+            ("name", to_json(name)),
+            ("superclass", to_json(superclass)),
+            ("methods", list(methods)),
+        ]),
+        Ast::MethodDefinition { token, name, function } => object("MethodDefinition", Some(token), vec![
+            ("name", Json::String(name.to_string())),
+            ("function", to_json(function)),
+        ]),
+        Ast::ImportStatement { token, path, alias } => object("ImportStatement", Some(token), vec![
+            ("path", to_json(path)),
+            ("alias", to_json(alias)),
+        ]),
+        Ast::ImportExpression { token, path } => object("ImportExpression", Some(token), vec![
+            ("path", to_json(path)),
+        ]),
+        Ast::ExportStatement { token, statement } => object("ExportStatement", Some(token), vec![
+            ("statement", to_json(statement)),
+        ]),
+        Ast::TypeExpression { token, name, arguments } => object("TypeExpression", Some(token), vec![
+            ("name", Json::String(name.to_string())),
+            ("arguments", list(arguments)),
+        ]),
+    }
+}
+
+pub fn from_json(json: &Json) -> Result<Ast, String> {
+    let kind = string(json, "type")?;
+
+    let node = match kind.as_ref() {
+        "Program" => Ast::Program { statements: nodes(json, "statements")? },
+        "Expression" => Ast::Expression { token: token(json)? },
+        "Identifier" => Ast::Identifier { token: token(json)?, value: string(json, "value")? },
+        "LetStatement" => Ast::LetStatement {
+            token: token(json)?,
+            ident: node(json, "ident")?,
+            annotation: node(json, "annotation")?,
+            value: node(json, "value")?,
+        },
+        "ReturnStatement" => Ast::ReturnStatement {
+            token: token(json)?,
+            return_value: node(json, "return_value")?,
+        },
+        "InfixDeclaration" => Ast::InfixDeclaration {
+            token: token(json)?,
+            operator: string(json, "operator")?,
+            precedence: string(json, "precedence")?,
+            associativity: string(json, "associativity")?,
+            value: node(json, "value")?,
+        },
+        "ExpressionStatement" => Ast::ExpressionStatement {
+            token: token(json)?,
+            expression: node(json, "expression")?,
+        },
+        "IntegerLiteral" => Ast::IntegerLiteral { token: token(json)?, value: integer(json, "value")? },
+        "Boolean" => Ast::Boolean { token: token(json)?, value: boolean(json, "value")? },
+        "NullLiteral" => Ast::NullLiteral { token: token(json)? },
+        "StringLiteral" => Ast::StringLiteral { token: token(json)?, value: string(json, "value")? },
+        "PrefixExpression" => Ast::PrefixExpression {
+            token: token(json)?,
+            operator: string(json, "operator")?,
+            right: node(json, "right")?,
+        },
+        "InfixExpression" => Ast::InfixExpression {
+            token: token(json)?,
+            left: node(json, "left")?,
+            operator: string(json, "operator")?,
+            right: node(json, "right")?,
+        },
+        "IfExpression" => Ast::IfExpression {
+            token: token(json)?,
+            condition: node(json, "condition")?,
+            consequence: node(json, "consequence")?,
+            alternative: node(json, "alternative")?,
+        },
+        "BlockStatement" => Ast::BlockStatement { token: token(json)?, statements: Rc::new(nodes(json, "statements")?) },
+        "FunctionLiteral" => Ast::FunctionLiteral {
+            token: token(json)?,
+            parameters: nodes(json, "parameters")?,
+            parameter_types: nodes(json, "parameter_types")?,
+            return_type: node(json, "return_type")?,
+            body: Rc::new(*node(json, "body")?),
+        },
+        "CallExpression" => Ast::CallExpression {
+            token: token(json)?,
+            function: node(json, "function")?,
+            arguments: nodes(json, "arguments")?,
+        },
+        "ArrayLiteral" => Ast::ArrayLiteral { token: token(json)?, elements: nodes(json, "elements")? },
+        "IndexExpression" => Ast::IndexExpression {
+            token: token(json)?,
+            left: node(json, "left")?,
+            index: node(json, "index")?,
+        },
+        "SliceExpression" => Ast::SliceExpression {
+            token: token(json)?,
+            left: node(json, "left")?,
+            start: node(json, "start")?,
+            end: node(json, "end")?,
+        },
+        "MemberExpression" => Ast::MemberExpression {
+            token: token(json)?,
+            object: node(json, "object")?,
+            member: node(json, "member")?,
+        },
+        "AssignExpression" => Ast::AssignExpression {
+            token: token(json)?,
+            target: node(json, "target")?,
+            value: node(json, "value")?,
+        },
+        "ThrowStatement" => Ast::ThrowStatement { token: token(json)?, value: node(json, "value")? },
+        "YieldStatement" => Ast::YieldStatement { token: token(json)?, value: node(json, "value")? },
+        "ForExpression" => Ast::ForExpression {
+            token: token(json)?,
+            variable: node(json, "variable")?,
+            iterable: node(json, "iterable")?,
+            body: node(json, "body")?,
+        },
+        "SpreadExpression" => Ast::SpreadExpression { token: token(json)?, value: node(json, "value")? },
+        "RangeExpression" => Ast::RangeExpression {
+            token: token(json)?,
+            start: node(json, "start")?,
+            end: node(json, "end")?,
+            step: node(json, "step")?,
+        },
+        "ListComprehension" => Ast::ListComprehension {
+            token: token(json)?,
+            element: node(json, "element")?,
+            clauses: nodes(json, "clauses")?,
+        },
+        "ComprehensionClause" => Ast::ComprehensionClause {
+            token: token(json)?,
+            variable: node(json, "variable")?,
+            value: node(json, "value")?,
+        },
+        "TryExpression" => Ast::TryExpression {
+            token: token(json)?,
+            block: node(json, "block")?,
+            parameter: node(json, "parameter")?,
+            catch: node(json, "catch")?,
+            finally: node(json, "finally")?,
+        },
+        "StructDeclaration" => Ast::StructDeclaration {
+            token: token(json)?,
+            name: node(json, "name")?,
+            fields: nodes(json, "fields")?,
+        },
+        "EnumDeclaration" => Ast::EnumDeclaration {
+            token: token(json)?,
+            name: node(json, "name")?,
+            variants: nodes(json, "variants")?,
+        },
+        "EnumVariant" => Ast::EnumVariant {
+            token: token(json)?,
+            name: string(json, "name")?,
+            fields: nodes(json, "fields")?,
+        },
+        "ClassDeclaration" => Ast::ClassDeclaration {
+            token: token(json)?,
+            name: node(json, "name")?,
+            superclass: node(json, "superclass")?,
+            methods: nodes(json, "methods")?,
+        },
+        "TypeExpression" => {
+            let name = string(json, "name")?;
+            let arguments = nodes(json, "arguments")?;
+            match (name.as_ref(), arguments.len()) {
+                ("array", 1) => (),
+                ("array", _) => return Err(format!("array type needs 1 argument, got {}", arguments.len())),
+                ("fn", 0)    => return Err("function type needs a return type".to_string()),
+                _            => (),
+            }
+
+            Ast::TypeExpression { token: token(json)?, name: name, arguments: arguments }
+        },
+        "MethodDefinition" => Ast::MethodDefinition {
+            token: token(json)?,
+            name: string(json, "name")?,
+            function: node(json, "function")?,
+        },
+        "ImportStatement" => Ast::ImportStatement {
+            token: token(json)?,
+            path: node(json, "path")?,
+            alias: node(json, "alias")?,
+        },
+        "ImportExpression" => Ast::ImportExpression { token: token(json)?, path: node(json, "path")? },
+        "ExportStatement" => Ast::ExportStatement { token: token(json)?, statement: node(json, "statement")? },
+        _ => return Err(format!("unknown node type: {}", kind)),
+    };
+
+    Ok(node)
+}
+
+pub fn to_json_string(node: &Ast) -> String {
+    to_json(node).to_pretty_string()
+}
+
+pub fn from_json_string(input: &str) -> Result<Ast, String> {
+    from_json(&json::parse(input)?)
+}
+
+pub fn to_sexp_string(node: &Ast) -> String {
+    sexp::from_json(&to_json(node)).to_string()
+}
+
+pub fn from_sexp_string(input: &str) -> Result<Ast, String> {
+    from_json(&sexp::to_json(&sexp::parse(input)?)?)
+}
+
+fn object(kind: &str, token: Option<&Token>, fields: Vec<(&str, Json)>) -> Json {
+    let mut members = vec![("type".to_string(), Json::String(kind.to_string()))];
+
+    if let Some(token) = token {
+        members.push(("token".to_string(), Json::Object(vec![
+            ("kind".to_string(), Json::String(token.kind.get_kind_literal())),
+            ("literal".to_string(), Json::String(token.literal.to_string())),
+            ("line".to_string(), Json::Integer(token.line as i64)),
+            ("column".to_string(), Json::Integer(token.column as i64)),
+        ])));
+    }
+
+    for (name, value) in fields {
+        members.push((name.to_string(), value));
+    }
+
+    Json::Object(members)
+}
+
+fn list(nodes: &[Box<Ast>]) -> Json {
+    Json::Array(nodes.iter().map(|node| to_json(node)).collect())
+}
+
+fn field<'a>(json: &'a Json, name: &str) -> Result<&'a Json, String> {
+    match json.get(name) {
+        Some(value) => Ok(value),
+        None        => Err(format!("missing member \"{}\" in {}", name, json.to_string())),
+    }
+}
+
+fn string(json: &Json, name: &str) -> Result<String, String> {
+    match field(json, name)? {
+        Json::String(value) => Ok(value.to_string()),
+        other               => Err(format!("member \"{}\" is not a string: {}", name, other.to_string())),
+    }
+}
+
+fn integer(json: &Json, name: &str) -> Result<i64, String> {
+    match field(json, name)? {
+        Json::Integer(value) => Ok(*value),
+        other                => Err(format!("member \"{}\" is not an integer: {}", name, other.to_string())),
+    }
+}
+
+fn boolean(json: &Json, name: &str) -> Result<bool, String> {
+    match field(json, name)? {
+        Json::Boolean(value) => Ok(*value),
+        other                => Err(format!("member \"{}\" is not a boolean: {}", name, other.to_string())),
+    }
+}
+
+fn token(json: &Json) -> Result<Token, String> {
+    let token = field(json, "token")?;
+    let kind = string(token, "kind")?;
+
+    match TokenKind::from_kind_literal(&kind) {
+        Some(value) => {
+            let mut parsed = Token::new(value, string(token, "literal")?);
+            parsed.line = position(token, "line")?;
+            parsed.column = position(token, "column")?;
+            Ok(parsed)
+        },
+        None        => Err(format!("unknown token kind: {}", kind)),
+    }
+}
+
+/// Line or column of a token; 0 if it is omitted
+fn position(json: &Json, name: &str) -> Result<usize, String> {
+    match json.get(name) {
+        Some(Json::Integer(value)) if *value >= 0 => Ok(*value as usize),
+        Some(other) => Err(format!("member \"{}\" is not a position: {}", name, other.to_string())),
+        None        => Ok(0),
+    }
+}
+
+fn node(json: &Json, name: &str) -> Result<Box<Ast>, String> {
+    Ok(Box::new(from_json(field(json, name)?)?))
+}
+
+fn nodes(json: &Json, name: &str) -> Result<Vec<Box<Ast>>, String> {
+    match field(json, name)? {
+        Json::Array(elements) => {
+            let mut nodes = Vec::new();
+            for element in elements {
+                nodes.push(Box::new(from_json(element)?));
+            }
+            Ok(nodes)
+        },
+        other => Err(format!("member \"{}\" is not an array: {}", name, other.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexier::{ Lexier };
+    use crate::parser::{ Parser };
+    use super::*;
+
+    const INPUT: &str = "\
+let add = fn(x, y) { return x + y; };\
+let r = if (add(1, 2) > 2) { \"big\" } else { [1, -2][0:1] };\
+infix \"<>\" equals = |a, b| !(a == b);\
+const c = 1;\
+r = r.len() <> true;\
+try { throw error(\"E\", \"m\"); } catch (e) { e.kind } finally { 1 };\
+struct Point { x, y }\
+enum Shape { Circle(r), Rect(w, h), Empty }\
+class C extends B { init(n) { self.n = n; } inc() { self.n += 1 } }\
+import \"lib/math.mk\" as math;\
+export let m = import(\"m\" + \".mk\");\
+let n: [int] = [];\
+let f = fn(a: int, b) -> fn(string) -> bool { b };\
+let g = fn(xs) { for (x in xs) { yield x * 2; } };\
+let sq = [x * y for x in [1, 2] for y in g([3]) if x > 1];\
+let rs = [0..n, 1..=n + 1 step 2, 1 in 0..3];\
+let o: null = a?.b?[0]?[1:] ?? null;\
+let v = fn(a, ...rest: [int]) { f(...rest, [...a, 0]) };\
+";
+
+    fn parse(input: &str) -> Ast {
+        let mut parser = Parser::new(Lexier::new(input.to_string()));
+        let program = parser.parse_program().unwrap();
+        parser.check_parser_errors();
+
+        program
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let program = parse(INPUT);
+        let json = to_json_string(&program);
+        let restored = from_json_string(&json).unwrap();
+
+        assert_eq!(restored.to_string(), program.to_string());
+        assert_eq!(to_json_string(&restored), json);
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let json = to_json(&parse("-5"));
+
+        assert_eq!(json.to_string(), "{\"type\":\"Program\",\"statements\":[\
+{\"type\":\"ExpressionStatement\",\"token\":{\"kind\":\"Integer\",\"literal\":\"5\",\"line\":1,\"column\":2},\"expression\":\
+{\"type\":\"PrefixExpression\",\"token\":{\"kind\":\"Minus\",\"literal\":\"-\",\"line\":1,\"column\":1},\"operator\":\"-\",\"right\":\
+{\"type\":\"IntegerLiteral\",\"token\":{\"kind\":\"Integer\",\"literal\":\"5\",\"line\":1,\"column\":2},\"value\":5}}}]}");
+    }
+
+    #[test]
+    fn test_sexp_round_trip() {
+        let program = parse(INPUT);
+        let string = to_sexp_string(&program);
+        let restored = from_sexp_string(&string).unwrap();
+
+        assert_eq!(restored.to_string(), program.to_string());
+        assert_eq!(to_sexp_string(&restored), string);
+        assert_eq!(to_sexp_string(&parse("x")),
+                   "(Program :statements [(ExpressionStatement :token (:kind \"Identifier\" :literal \"x\" :line 1 :column 1) \
+:expression (Identifier :token (:kind \"Identifier\" :literal \"x\" :line 1 :column 1) :value \"x\"))])");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(from_json_string("{\"type\": \"Unknown\"}").is_err());
+        assert!(from_json_string("{\"type\": \"Identifier\", \"value\": \"x\"}").is_err());
+        assert!(from_sexp_string("(IntegerLiteral :token (:kind \"Integer\" :literal \"1\") :value \"1\")").is_err());
+        assert!(from_sexp_string("(IntegerLiteral :token (:kind \"Integer\" :literal \"1\" :line -1) :value 1)").is_err());
+
+        // type annotations without the arguments their type needs
+        let json = to_json_string(&parse("let x: int = 1;"));
+        assert!(from_json_string(&json).is_ok());
+        assert!(from_json_string(&json.replace("\"name\": \"int\"", "\"name\": \"fn\"")).is_err());
+        assert!(from_json_string(&json.replace("\"name\": \"int\"", "\"name\": \"array\"")).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::json::{ Json };
+
+/// S-expression value.
+/// Json maps onto it as follows:
+///   {"type": "T", "k": v, ..}  <=>  (T :k v ..)
+///   {"k": v, ..}               <=>  (:k v ..)
+///   [v, ..]                    <=>  [v ..]
+///   "s", 1, true, null         <=>  "s", 1, true, nil
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sexp {
+    Symbol(String),
+    Keyword(String),
+    String(String),
+    Integer(i64),
+    List(Vec<Sexp>),
+    Vector(Vec<Sexp>),
+}
+
+impl Sexp {
+    pub fn to_string(&self) -> String {
+        match self {
+            Sexp::Symbol(value)  => value.to_string(),
+            Sexp::Keyword(value) => format!(":{}", value),
+            Sexp::String(value)  => Json::String(value.to_string()).to_string(),
+            Sexp::Integer(value) => value.to_string(),
+            Sexp::List(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                format!("({})", elements.join(" "))
+            },
+            Sexp::Vector(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                format!("[{}]", elements.join(" "))
+            },
+        }
+    }
+}
+
+pub fn from_json(json: &Json) -> Sexp {
+    match json {
+        Json::Null           => Sexp::Symbol("nil".to_string()),
+        Json::Boolean(value) => Sexp::Symbol(value.to_string()),
+        Json::Integer(value) => Sexp::Integer(*value),
+        Json::String(value)  => Sexp::String(value.to_string()),
+        Json::Array(elements) => Sexp::Vector(elements.iter().map(from_json).collect()),
+        Json::Object(members) => {
+            let mut list = Vec::new();
+            for (key, value) in members {
+                match (key.as_ref(), value) {
+                    ("type", Json::String(kind)) if list.len() == 0 => list.push(Sexp::Symbol(kind.to_string())),
+                    _ => {
+                        list.push(Sexp::Keyword(key.to_string()));
+                        list.push(from_json(value));
+                    },
+                }
+            }
+            Sexp::List(list)
+        },
+    }
+}
+
+pub fn to_json(sexp: &Sexp) -> Result<Json, String> {
+    match sexp {
+        Sexp::Symbol(value) => match value.as_ref() {
+            "nil"   => Ok(Json::Null),
+            "true"  => Ok(Json::Boolean(true)),
+            "false" => Ok(Json::Boolean(false)),
+            _       => Err(format!("unexpected symbol: {}", value)),
+        },
+        Sexp::Keyword(value) => Err(format!("unexpected keyword: :{}", value)),
+        Sexp::String(value)  => Ok(Json::String(value.to_string())),
+        Sexp::Integer(value) => Ok(Json::Integer(*value)),
+        Sexp::Vector(elements) => {
+            let mut array = Vec::new();
+            for element in elements {
+                array.push(to_json(element)?);
+            }
+            Ok(Json::Array(array))
+        },
+        Sexp::List(elements) => {
+            let mut members = Vec::new();
+            let mut rest = &elements[..];
+
+            if let Some(Sexp::Symbol(kind)) = elements.first() {
+                members.push(("type".to_string(), Json::String(kind.to_string())));
+                rest = &elements[1..];
+            }
+
+            if rest.len() % 2 != 0 {
+                return Err(format!("odd number of elements in {}", sexp.to_string()));
+            }
+
+            for pair in rest.chunks(2) {
+                match &pair[0] {
+                    Sexp::Keyword(key) => members.push((key.to_string(), to_json(&pair[1])?)),
+                    other              => return Err(format!("expected keyword, got {}", other.to_string())),
+                }
+            }
+            Ok(Json::Object(members))
+        },
+    }
+}
+
+/// Parse S-expression text
+pub fn parse(input: &str) -> Result<Sexp, String> {
+    let mut reader = Reader { input: input.chars().collect(), position: 0 };
+    let value = reader.read_value()?;
+
+    reader.skip();
+    if reader.position < reader.input.len() {
+        return Err(format!("unexpected character '{}' at {}", reader.input[reader.position], reader.position));
+    }
+
+    Ok(value)
+}
+
+struct Reader {
+    input: Vec<char>,
+    position: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn skip(&mut self) {
+        while let Some(ch) = self.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            self.position += 1;
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Sexp, String> {
+        self.skip();
+        match self.peek() {
+            Some('(') => Ok(Sexp::List(self.read_sequence(')')?)),
+            Some('[') => Ok(Sexp::Vector(self.read_sequence(']')?)),
+            Some('"') => self.read_string(),
+            Some(ch) if ch == ')' || ch == ']' => Err(format!("unexpected '{}' at {}", ch, self.position)),
+            Some(_)   => Ok(self.read_atom()),
+            None      => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn read_sequence(&mut self, end: char) -> Result<Vec<Sexp>, String> {
+        self.position += 1;
+        let mut elements = Vec::new();
+
+        loop {
+            self.skip();
+            match self.peek() {
+                Some(ch) if ch == end => {
+                    self.position += 1;
+                    return Ok(elements);
+                },
+                Some(_) => elements.push(self.read_value()?),
+                None    => return Err(format!("expected '{}', got end of input", end)),
+            }
+        }
+    }
+
+    fn read_string(&mut self) -> Result<Sexp, String> {
+        let start = self.position;
+        self.position += 1;
+
+        loop {
+            match self.peek() {
+                Some('"')  => break,
+                Some('\\') => self.position += 2,
+                Some(_)    => self.position += 1,
+                None       => return Err("unterminated string".to_string()),
+            }
+        }
+        self.position += 1;
+
+        let literal: String = self.input[start..self.position].iter().collect();
+        match crate::json::parse(&literal)? {
+            Json::String(value) => Ok(Sexp::String(value)),
+            _                   => Err(format!("invalid string {}", literal)),
+        }
+    }
+
+    fn read_atom(&mut self) -> Sexp {
+        let start = self.position;
+        while let Some(ch) = self.peek() {
+            if ch.is_whitespace() || "()[]\"".contains(ch) {
+                break;
+            }
+            self.position += 1;
+        }
+
+        let atom: String = self.input[start..self.position].iter().collect();
+        if let Ok(value) = atom.parse::<i64>() {
+            return Sexp::Integer(value);
+        }
+        if let Some(keyword) = atom.strip_prefix(':') {
+            return Sexp::Keyword(keyword.to_string());
+        }
+
+        Sexp::Symbol(atom)
+    }
+}
+
+#[test]
+fn test_sexp_json_mapping() {
+    let json = crate::json::parse(r#"{"type": "Node", "a": [1, "two", true, null], "b": {"c": -3}, "d": {}}"#).unwrap();
+    let sexp = from_json(&json);
+
+    assert_eq!(sexp.to_string(), r#"(Node :a [1 "two" true nil] :b (:c -3) :d ())"#);
+    assert_eq!(parse(&sexp.to_string()).unwrap(), sexp);
+    assert_eq!(to_json(&sexp).unwrap(), json);
+
+    assert!(parse("(a :b").is_err());
+    assert!(to_json(&parse("(Node :a)").unwrap()).is_err());
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TokenKind {
     Illegal,
     Eof,
@@ -9,6 +9,10 @@ pub enum TokenKind {
 
     // operator
     Assign,     // '='
+    PlusAssign,     // '+='
+    MinusAssign,    // '-='
+    AsteriskAssign, // '*='
+    SlashAssign,    // '/='
     Plus,       // '+'
     Minus,      // '-'
     Bang,       // '!'
@@ -18,10 +22,22 @@ pub enum TokenKind {
     Gt,         // '>'
     Eq,         // '=='
     NotEq,     // '!='
+    Pipe,       // '|'
+    Arrow,      // '=>'
+    ThinArrow,  // '->'
+    Operator,   // user-defined infix operator
 
     // delimeter
     Comma,      // ','
     Semicolon,  // ';'
+    Colon,      // ':'
+    Dot,        // '.'
+    DotDot,     // '..'
+    DotDotEq,   // '..='
+    Ellipsis,   // '...'
+    QuestionDot,        // '?.'
+    QuestionLbracket,   // '?['
+    QuestionQuestion,   // '??'
 
     Lparen,     // '('
     Rparen,     // ')'
@@ -33,11 +49,28 @@ pub enum TokenKind {
     // keyword
     Function,   // 'fn'
     Let,        // 'let'
+    Const,      // 'const'
     True ,      // 'true'
     False,      // 'false'
     If,         // 'if'
     Else,       // 'else'
     Return,     // 'return'
+    Infix,      // 'infix'
+    Throw,      // 'throw'
+    Try,        // 'try'
+    Catch,      // 'catch'
+    Finally,    // 'finally'
+    Struct,     // 'struct'
+    Enum,       // 'enum'
+    Class,      // 'class'
+    Extends,    // 'extends'
+    Import,     // 'import'
+    Export,     // 'export'
+    As,         // 'as'
+    Yield,      // 'yield'
+    For,        // 'for'
+    In,         // 'in'
+    Null,       // 'null'
 }
 
 impl TokenKind {
@@ -48,6 +81,10 @@ impl TokenKind {
             TokenKind::Identifier => "Identifier".to_string(),
             TokenKind::Integer    => "Integer".to_string(),
             TokenKind::Assign     => "Assign".to_string(),
+            TokenKind::PlusAssign => "PlusAssign".to_string(),
+            TokenKind::MinusAssign => "MinusAssign".to_string(),
+            TokenKind::AsteriskAssign => "AsteriskAssign".to_string(),
+            TokenKind::SlashAssign => "SlashAssign".to_string(),
             TokenKind::Plus       => "Plus".to_string(),
             TokenKind::Minus      => "Minus".to_string(),
             TokenKind::Bang       => "Bang".to_string(),
@@ -55,36 +92,146 @@ impl TokenKind {
             TokenKind::Slash      => "Slash".to_string(),
             TokenKind::Lt         => "Lt".to_string(),
             TokenKind::Gt         => "Gt".to_string(),
-            TokenKind::Eq         => "Illegal".to_string(),
+            TokenKind::Eq         => "Eq".to_string(),
             TokenKind::NotEq      => "NotEq".to_string(),
+            TokenKind::Pipe       => "Pipe".to_string(),
+            TokenKind::Arrow      => "Arrow".to_string(),
+            TokenKind::ThinArrow  => "ThinArrow".to_string(),
+            TokenKind::Operator   => "Operator".to_string(),
             TokenKind::Comma      => "Comma".to_string(),
             TokenKind::Semicolon  => "Semicolon".to_string(),
+            TokenKind::Colon      => "Colon".to_string(),
+            TokenKind::Dot        => "Dot".to_string(),
+            TokenKind::DotDot     => "DotDot".to_string(),
+            TokenKind::DotDotEq   => "DotDotEq".to_string(),
+            TokenKind::Ellipsis   => "Ellipsis".to_string(),
+            TokenKind::QuestionDot      => "QuestionDot".to_string(),
+            TokenKind::QuestionLbracket => "QuestionLbracket".to_string(),
+            TokenKind::QuestionQuestion => "QuestionQuestion".to_string(),
             TokenKind::Lparen     => "Lparen".to_string(),
             TokenKind::Rparen     => "Rparen".to_string(),
             TokenKind::Lbrace     => "Lbrace".to_string(),
             TokenKind::Rbrace     => "Rbrace".to_string(),
             TokenKind::Function   => "Function".to_string(),
             TokenKind::Let        => "Let".to_string(),
+            TokenKind::Const      => "Const".to_string(),
             TokenKind::True       => "True".to_string(),
             TokenKind::False      => "False".to_string(),
             TokenKind::If         => "If".to_string(),
             TokenKind::Else       => "Else".to_string(),
             TokenKind::Return     => "Return".to_string(),
+            TokenKind::Infix      => "Infix".to_string(),
+            TokenKind::Throw      => "Throw".to_string(),
+            TokenKind::Try        => "Try".to_string(),
+            TokenKind::Catch      => "Catch".to_string(),
+            TokenKind::Finally    => "Finally".to_string(),
+            TokenKind::Struct     => "Struct".to_string(),
+            TokenKind::Enum       => "Enum".to_string(),
+            TokenKind::Class      => "Class".to_string(),
+            TokenKind::Extends    => "Extends".to_string(),
+            TokenKind::Import     => "Import".to_string(),
+            TokenKind::Export     => "Export".to_string(),
+            TokenKind::As         => "As".to_string(),
+            TokenKind::Yield      => "Yield".to_string(),
+            TokenKind::For        => "For".to_string(),
+            TokenKind::In         => "In".to_string(),
+            TokenKind::Null       => "Null".to_string(),
             TokenKind::String     => "String".to_string(),
             TokenKind::Lbracket   => "Lbracket".to_string(),
             TokenKind::Rbracket   => "Rbracket".to_string(),
         }
         
     }
+
+    /// Inverse of get_kind_literal
+    pub fn from_kind_literal(literal: &str) -> Option<TokenKind> {
+        match literal {
+            "Illegal"     => Some(TokenKind::Illegal),
+            "Eof"         => Some(TokenKind::Eof),
+            "Identifier"  => Some(TokenKind::Identifier),
+            "Integer"     => Some(TokenKind::Integer),
+            "Assign"      => Some(TokenKind::Assign),
+            "PlusAssign"  => Some(TokenKind::PlusAssign),
+            "MinusAssign" => Some(TokenKind::MinusAssign),
+            "AsteriskAssign" => Some(TokenKind::AsteriskAssign),
+            "SlashAssign" => Some(TokenKind::SlashAssign),
+            "Plus"        => Some(TokenKind::Plus),
+            "Minus"       => Some(TokenKind::Minus),
+            "Bang"        => Some(TokenKind::Bang),
+            "Asterisk"    => Some(TokenKind::Asterisk),
+            "Slash"       => Some(TokenKind::Slash),
+            "Lt"          => Some(TokenKind::Lt),
+            "Gt"          => Some(TokenKind::Gt),
+            "Eq"          => Some(TokenKind::Eq),
+            "NotEq"       => Some(TokenKind::NotEq),
+            "Pipe"        => Some(TokenKind::Pipe),
+            "Arrow"       => Some(TokenKind::Arrow),
+            "ThinArrow"   => Some(TokenKind::ThinArrow),
+            "Operator"    => Some(TokenKind::Operator),
+            "Comma"       => Some(TokenKind::Comma),
+            "Semicolon"   => Some(TokenKind::Semicolon),
+            "Colon"       => Some(TokenKind::Colon),
+            "Dot"         => Some(TokenKind::Dot),
+            "DotDot"      => Some(TokenKind::DotDot),
+            "DotDotEq"    => Some(TokenKind::DotDotEq),
+            "Ellipsis"    => Some(TokenKind::Ellipsis),
+            "QuestionDot"      => Some(TokenKind::QuestionDot),
+            "QuestionLbracket" => Some(TokenKind::QuestionLbracket),
+            "QuestionQuestion" => Some(TokenKind::QuestionQuestion),
+            "Lparen"      => Some(TokenKind::Lparen),
+            "Rparen"      => Some(TokenKind::Rparen),
+            "Lbrace"      => Some(TokenKind::Lbrace),
+            "Rbrace"      => Some(TokenKind::Rbrace),
+            "Function"    => Some(TokenKind::Function),
+            "Let"         => Some(TokenKind::Let),
+            "Const"       => Some(TokenKind::Const),
+            "True"        => Some(TokenKind::True),
+            "False"       => Some(TokenKind::False),
+            "If"          => Some(TokenKind::If),
+            "Else"        => Some(TokenKind::Else),
+            "Return"      => Some(TokenKind::Return),
+            "Infix"       => Some(TokenKind::Infix),
+            "Throw"       => Some(TokenKind::Throw),
+            "Try"         => Some(TokenKind::Try),
+            "Catch"       => Some(TokenKind::Catch),
+            "Finally"     => Some(TokenKind::Finally),
+            "Struct"      => Some(TokenKind::Struct),
+            "Enum"        => Some(TokenKind::Enum),
+            "Class"       => Some(TokenKind::Class),
+            "Extends"     => Some(TokenKind::Extends),
+            "Import"      => Some(TokenKind::Import),
+            "Export"      => Some(TokenKind::Export),
+            "As"          => Some(TokenKind::As),
+            "Yield"       => Some(TokenKind::Yield),
+            "For"         => Some(TokenKind::For),
+            "In"          => Some(TokenKind::In),
+            "Null"        => Some(TokenKind::Null),
+            "String"      => Some(TokenKind::String),
+            "Lbracket"    => Some(TokenKind::Lbracket),
+            "Rbracket"    => Some(TokenKind::Rbracket),
+            _             => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub literal: String,
+    pub line: usize,        // 1-based position in source, 0 if the token was not read from source
+    pub column: usize,
 }
 
 impl Token {
+    pub fn new(kind: TokenKind, literal: String) -> Token {
+        Token { kind: kind, literal: literal, line: 0, column: 0 }
+    }
+
+    /// Source position as "line:column"
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.line, self.column)
+    }
+
     pub fn get_kind_literal(&self) -> String{
         match self.kind {
             TokenKind::Illegal    => "Illegal".to_string(),
@@ -92,6 +239,10 @@ impl Token {
             TokenKind::Identifier => "Identifier".to_string(),
             TokenKind::Integer    => "Integer".to_string(),
             TokenKind::Assign     => "Assign".to_string(),
+            TokenKind::PlusAssign => "PlusAssign".to_string(),
+            TokenKind::MinusAssign => "MinusAssign".to_string(),
+            TokenKind::AsteriskAssign => "AsteriskAssign".to_string(),
+            TokenKind::SlashAssign => "SlashAssign".to_string(),
             TokenKind::Plus       => "Plus".to_string(),
             TokenKind::Minus      => "Minus".to_string(),
             TokenKind::Bang       => "Bang".to_string(),
@@ -99,21 +250,50 @@ impl Token {
             TokenKind::Slash      => "Slash".to_string(),
             TokenKind::Lt         => "Lt".to_string(),
             TokenKind::Gt         => "Gt".to_string(),
-            TokenKind::Eq         => "Illegal".to_string(),
+            TokenKind::Eq         => "Eq".to_string(),
             TokenKind::NotEq      => "NotEq".to_string(),
+            TokenKind::Pipe       => "Pipe".to_string(),
+            TokenKind::Arrow      => "Arrow".to_string(),
+            TokenKind::ThinArrow  => "ThinArrow".to_string(),
+            TokenKind::Operator   => "Operator".to_string(),
             TokenKind::Comma      => "Comma".to_string(),
             TokenKind::Semicolon  => "Semicolon".to_string(),
+            TokenKind::Colon      => "Colon".to_string(),
+            TokenKind::Dot        => "Dot".to_string(),
+            TokenKind::DotDot     => "DotDot".to_string(),
+            TokenKind::DotDotEq   => "DotDotEq".to_string(),
+            TokenKind::Ellipsis   => "Ellipsis".to_string(),
+            TokenKind::QuestionDot      => "QuestionDot".to_string(),
+            TokenKind::QuestionLbracket => "QuestionLbracket".to_string(),
+            TokenKind::QuestionQuestion => "QuestionQuestion".to_string(),
             TokenKind::Lparen     => "Lparen".to_string(),
             TokenKind::Rparen     => "Rparen".to_string(),
             TokenKind::Lbrace     => "Lbrace".to_string(),
             TokenKind::Rbrace     => "Rbrace".to_string(),
             TokenKind::Function   => "Function".to_string(),
             TokenKind::Let        => "Let".to_string(),
+            TokenKind::Const      => "Const".to_string(),
             TokenKind::True       => "True".to_string(),
             TokenKind::False      => "False".to_string(),
             TokenKind::If         => "If".to_string(),
             TokenKind::Else       => "Else".to_string(),
             TokenKind::Return     => "Return".to_string(),
+            TokenKind::Infix      => "Infix".to_string(),
+            TokenKind::Throw      => "Throw".to_string(),
+            TokenKind::Try        => "Try".to_string(),
+            TokenKind::Catch      => "Catch".to_string(),
+            TokenKind::Finally    => "Finally".to_string(),
+            TokenKind::Struct     => "Struct".to_string(),
+            TokenKind::Enum       => "Enum".to_string(),
+            TokenKind::Class      => "Class".to_string(),
+            TokenKind::Extends    => "Extends".to_string(),
+            TokenKind::Import     => "Import".to_string(),
+            TokenKind::Export     => "Export".to_string(),
+            TokenKind::As         => "As".to_string(),
+            TokenKind::Yield      => "Yield".to_string(),
+            TokenKind::For        => "For".to_string(),
+            TokenKind::In         => "In".to_string(),
+            TokenKind::Null       => "Null".to_string(),
             TokenKind::String     => "String".to_string(),
             TokenKind::Lbracket   => "Lbracket".to_string(),
             TokenKind::Rbracket   => "Rbracket".to_string(),
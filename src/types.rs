@@ -0,0 +1,91 @@
+//! Static types named by annotations, used by the type checker and by type inference.
+
+use crate::ast::{ Ast };
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Any,                            // unannotated or unknown; consistent with every type
+    Int,
+    String,
+    Bool,
+    Null,
+    Range,
+    Array(Box<Type>),
+    Function(Vec<Type>, Box<Type>), // parameter types, return type
+    Variadic(Vec<Type>, Box<Type>), // parameter types ending with the array of the rest parameter, return type
+    Named(String),                  // struct, enum or class declared by the program
+    Var(usize),                     // type variable of type inference
+}
+
+impl Type {
+    /// Type written by an annotation; an omitted annotation, or a missing argument of one, is Any
+    pub fn from_annotation(annotation: &Ast) -> Type {
+        match annotation {
+            Ast::TypeExpression { name, arguments, .. } => match name.as_ref() {
+                "any"    => Type::Any,
+                "int"    => Type::Int,
+                "string" => Type::String,
+                "bool"   => Type::Bool,
+                "null"   => Type::Null,
+                "range"  => Type::Range,
+                "array"  => match arguments.first() {
+                    Some(element) => Type::Array(Box::new(Type::from_annotation(element))),
+                    None          => Type::Array(Box::new(Type::Any)),
+                },
+                "fn"     => match arguments.split_last() {
+                    Some((result, parameters)) =>
+                        Type::Function(parameters.iter().map(|p| Type::from_annotation(p)).collect(),
+                                       Box::new(Type::from_annotation(result))),
+                    None => Type::Function(Vec::new(), Box::new(Type::Any)),
+                },
+                _        => Type::Named(name.to_string()),
+            },
+            _ => Type::Any,
+        }
+    }
+
+    /// Gradual consistency: types are consistent if they are equal where both are known
+    pub fn is_consistent(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Array(l), Type::Array(r)) => l.is_consistent(r),
+            (Type::Function(lp, lr), Type::Function(rp, rr)) | (Type::Variadic(lp, lr), Type::Variadic(rp, rr)) =>
+                lp.len() == rp.len() && lp.iter().zip(rp).all(|(l, r)| l.is_consistent(r)) && lr.is_consistent(rr),
+            _ => self == other,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        *self != Type::Any
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            Type::Any    => "any".to_string(),
+            Type::Int    => "int".to_string(),
+            Type::String => "string".to_string(),
+            Type::Bool   => "bool".to_string(),
+            Type::Null   => "null".to_string(),
+            Type::Range  => "range".to_string(),
+            Type::Array(element) => format!("[{}]", element.to_string()),
+            Type::Function(parameters, result) => {
+                let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+                format!("fn({}) -> {}", parameters.join(", "), result.to_string())
+            },
+            Type::Variadic(parameters, result) => {
+                let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+                format!("fn({}) -> {}", variadic_parameters(parameters), result.to_string())
+            },
+            Type::Named(name) => name.to_string(),
+            Type::Var(id)     => format!("t{}", id),
+        }
+    }
+}
+
+/// Parameter list of a variadic function type, with the last parameter marked as the rest
+pub fn variadic_parameters(mut parameters: Vec<String>) -> String {
+    if let Some(rest) = parameters.last_mut() {
+        *rest = format!("...{}", rest);
+    }
+    parameters.join(", ")
+}
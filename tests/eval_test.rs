@@ -1,10 +1,16 @@
 extern crate simia;
 use simia::ast::{ Ast };
+use simia::context::{ Context, Imports, Limits, with_stack };
 use simia::env::*;
 use simia::eval::{ eval };
 use simia::lexier::{ Lexier };
-use simia::object::{ Object };
-use simia::parser::{ Parser };
+use simia::object::{ Object, format_trace };
+use simia::module;
+use simia::parser::{ Parser, ParseTable, Precedence, Associativity };
+use std::cell::{ RefCell };
+use std::path::{ Path, PathBuf };
+use std::rc::{ Rc };
+use std::time::{ Duration, Instant };
 
 fn test_eval(input: String) -> Object {
     let lexier = Lexier::new(input);
@@ -12,7 +18,7 @@ fn test_eval(input: String) -> Object {
     let program = parser.parse_program().unwrap();
     let mut env = Env::new();
 
-    eval(program, &mut env).unwrap()
+    eval(&program, &mut env).unwrap()
 }
 
 fn test_integer_object(obj: Object, expected: i64) -> bool {
@@ -150,7 +156,10 @@ fn test_null_expression() {
     let tests = ["if (false) { 10 }",
                  "if (1 > 2) { 10 }",
                  "[1, 2, 3][3]",
-                 "[1, 2, 3][-1]",
+                 "[1, 2, 3][-4]",
+                 "[][0]",
+                 "[][-1]",
+                 "\"\"[0]",
                  "first([])",
                  "first(\"\")",
                  "last([])",
@@ -213,7 +222,7 @@ if (10 > 1) {\
         let evaluated = test_eval(test.0.to_string());
 
         match evaluated {
-            Object::Error { msg } => {
+            Object::Error { msg, .. } => {
                 if msg != test.1.to_string() {
                     panic!("wrond error message. expected={}, got={}", test.1.to_string(), msg);
                 }
@@ -265,6 +274,15 @@ fn test_function_object() {
         }
         _ => panic!("object is not function. got={}", evaluated.kind()),
     }
+
+    // functions evaluated from one literal share its body
+    match test_eval("[x => x + 1 for i in [1, 2]]".to_string()) {
+        Object::Array { elements } => match (&elements[0], &elements[1]) {
+            (Object::Function { body: first, .. }, Object::Function { body: second, .. }) => assert!(Rc::ptr_eq(first, second)),
+            _ => panic!("elements are not functions. got={:?}", elements),
+        },
+        other => panic!("object is not array. got={}", other.kind()),
+    }
 }
 
 #[test]
@@ -301,6 +319,222 @@ addTwo(2);
     
 }
 
+#[test]
+fn test_scope_chain() {
+    let tests = [("let a = 1; let f = fn() { fn() { fn() { a } } }; f()()()", "1"),
+                 ("let f = fn(x) { fn(y) { fn(z) { x + y + z } } }; f(1)(2)(3)", "6"),
+                 ("let f = fn() { later }; let later = 5; f()", "5"),
+                 ("let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(12)", "144"),
+                 ("let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } }; \
+                   let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } }; [even(10), odd(7), even(3)]", "[true, true, false]"),
+                 ("let counter = fn() { let n = 0; fn() { n += 1; n } }; let c = counter(); c(); c(); [c(), counter()()]", "[3, 1]"),
+                 ("let n = 1; let f = fn() { fn() { n = n + 1 } }; f()(); f()(); n", "3"),
+                 ("let x = 1; let f = fn() { let x = 2; x }; [f(), x]", "[2, 1]"),
+                 ("let f = fn() { x }; f()", "Error: identifier not found: x"),
+                 ("const c = 1; let f = fn() { fn() { c = 2 } }; f()()", "Error: cannot assign to constant: c"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_tail_calls() {
+    let tests = [("let sum = fn(n, acc) { if (n == 0) { acc } else { sum(n - 1, acc + n) } }; sum(10000, 0)", "50005000"),
+                 ("let count = fn(n) { if (n == 0) { return \"done\"; } return count(n - 1); }; count(10000)", "done"),
+                 ("let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } }; \
+                   let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } }; even(10001)", "false"),
+                 ("let down = |n| if (n == 0) { 0 } else { down(n - 1) }; down(10000)", "0"),
+                 ("class C { loop(n) { if (n == 0) { \"end\" } else { self.loop(n - 1) } } } C().loop(10000)", "end"),
+                 ("let f = fn(n) { n + 1 }; let g = fn(n) { return f(f(n)); }; g(1)", "3"),
+                 ("let g = fn() { throw \"boom\" }; let f = fn() { try { return g(); } catch (e) { \"caught\" } }; f()", "caught"),
+                 ("let f = fn() { 1 }; return f();", "1"),
+                 ("let f = fn(a) { a }; let g = fn() { f() }; g()", "Error: wrong number of arguments. got=0, want=1"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+fn test_eval_with_max_call_depth(input: &str, max_depth: usize) -> Object {
+    let program = Parser::new(Lexier::new(input.to_string())).parse_program().unwrap();
+    let mut env = Env::new();
+    env.context().borrow_mut().set_max_call_depth(max_depth);
+
+    let evaluated = eval(&program, &mut env).unwrap();
+    assert_eq!(env.context().borrow().depth(), 0, "{}", input);
+    evaluated
+}
+
+#[test]
+fn test_call_depth_limit() {
+    let tests = [("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(19)", "19"),
+                 ("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(20)", "Error: maximum recursion depth exceeded"),
+                 ("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; try { f(100) } catch (e) { e.kind }", "RecursionError"),
+                 ("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; try { f(100) } catch (e) { 0 }; f(10)", "10"),
+                 ("let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(1000)", "0"),
+                 ("class A { init(n) { if (n > 0) { A(n - 1) } } } A(50)", "Error: maximum recursion depth exceeded"),
+                 ("let g = fn(n) { yield next(g(n + 1)); }; next(g(0))", "Error: maximum recursion depth exceeded"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval_with_max_call_depth(test.0, 20);
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+
+    // the default limit fails before the stack embedders evaluate on overflows
+    let deep = with_stack(|| {
+        test_eval("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; [f(900), f(100000)]".to_string()).inspect()
+    });
+    assert_eq!(deep.unwrap(), "Error: maximum recursion depth exceeded");
+}
+
+fn test_eval_with_limits(input: &str, limits: Limits) -> Object {
+    let program = Parser::new(Lexier::new(input.to_string())).parse_program().unwrap();
+    let mut env = Env::new();
+    env.context().borrow_mut().set_limits(limits);
+
+    eval(&program, &mut env).unwrap()
+}
+
+#[test]
+fn test_sandbox_limits() {
+    let steps = |max| Limits { max_steps: Some(max), ..Limits::default() };
+    let bytes = |max| Limits { max_allocation: Some(max), ..Limits::default() };
+    let tests = [("let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(10)", steps(1000), "0"),
+                 ("let f = fn(n) { f(n + 1) }; f(0)", steps(1000), "Error: step limit exceeded: 1000 steps"),
+                 ("let s = 0; for (i in 0..1000000) { s += i; }; s", steps(10000), "Error: step limit exceeded: 10000 steps"),
+                 ("let f = fn(n) { f(n + 1) }; try { f(0) } catch (e) { \"caught\" }", steps(1000), "Error: step limit exceeded: 1000 steps"),
+                 ("len(0..1000000000000)", steps(10), "1000000000000"),
+                 ("collect(0..1000000000000)", steps(1000), "Error: step limit exceeded: 1000 steps"),
+                 ("take(0..1000000000000, 3)", steps(1000), "[0, 1, 2]"),
+                 ("let s = \"ab\"; s + s + s", bytes(100), "ababab"),
+                 ("let s = \"ab\"; for (i in 0..100) { s = s + s; }; len(s)", bytes(1000), "Error: allocation limit exceeded: 1000 bytes"),
+                 ("let xs = []; for (i in 0..1000) { xs = push(xs, i); }; len(xs)", bytes(10000), "Error: allocation limit exceeded: 10000 bytes"),
+                 ("[...0..1000000000000]", bytes(10000), "Error: allocation limit exceeded: 10000 bytes"),
+                 ("[x for x in 0..1000]", bytes(1000), "Error: allocation limit exceeded: 1000 bytes"),
+                 ("let g = fn() { for (i in 0..1000000000000) { yield i; } }; collect(g())", bytes(10000), "Error: allocation limit exceeded: 10000 bytes"),
+                 ("let g = fn() { for (i in 0..1000000000000) { yield i; } }; take(g(), 1000000)", bytes(10000), "Error: allocation limit exceeded: 10000 bytes"),
+                 ("let g = fn() { for (i in 0..1000000000000) { yield i; } }; collect(g())", steps(1000), "Error: step limit exceeded: 1000 steps"),
+                 ("let a = [0, 1, 2, 3, 4, 5, 6, 7]; let b = [a, a, a, a, a, a, a, a]; len(b)", bytes(10000), "8"),
+                 ("let a = [0, 1, 2, 3, 4, 5, 6, 7]; \
+                   let b = [a, a, a, a, a, a, a, a]; \
+                   let c = [b, b, b, b, b, b, b, b]; \
+                   let d = [c, c, c, c, c, c, c, c]; \
+                   let e = [d, d, d, d, d, d, d, d]; \
+                   let f = [e, e, e, e, e, e, e, e]; \
+                   let g = [f, f, f, f, f, f, f, f]; \
+                   let h = [g, g, g, g, g, g, g, g]; \
+                   let i = [h, h, h, h, h, h, h, h]; \
+                   let j = [i, i, i, i, i, i, i, i]; \
+                   len(j)", bytes(1000000), "Error: allocation limit exceeded: 1000000 bytes"),
+                 ("let a = [0, 1, 2, 3, 4, 5, 6, 7]; [a, a, a, a, a, a, a, a]", bytes(1000), "Error: allocation limit exceeded: 1000 bytes"),
+                 ("let s = \"abcdefghij\"; let xs = [s, s, s, s, s, s, s, s, s, s]; [xs, xs, xs, xs]", bytes(2000), "Error: allocation limit exceeded: 2000 bytes"),
+                 ("struct P { x, y } let a = [0, 1, 2, 3]; let p = P(a, a); let q = P(p, p); let r = P(q, q); P(r, r)", bytes(2000), "Error: allocation limit exceeded: 2000 bytes"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval_with_limits(test.0, test.1.clone());
+        if evaluated.inspect() != test.2 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.2, evaluated.inspect());
+        }
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let evaluated = test_eval_with_limits("let f = fn(n) { f(n + 1) }; f(0)", Limits { deadline: Some(deadline), ..Limits::default() });
+    assert_eq!(evaluated.inspect(), "Error: deadline exceeded");
+    match evaluated {
+        Object::Error { kind, .. } => assert_eq!(kind, "TimeoutError"),
+        other                      => panic!("no error object returned. got={}", other.inspect()),
+    }
+}
+
+#[test]
+fn test_sandbox_imports() {
+    let within = |dir: &str| Limits { imports: Imports::Within(PathBuf::from(dir)), ..Limits::default() };
+    let disabled = Limits { imports: Imports::Disabled, ..Limits::default() };
+    let tests = [("import \"tests/modules/lib/math.mk\"; math.square(3)", Limits::default(), "9"),
+                 ("import \"tests/modules/lib/math.mk\"; math.square(3)", within("tests/modules"), "9"),
+                 ("import(\"tests/modules/lib/math.mk\").pi", within("tests/modules/lib"), "3"),
+                 ("import \"tests/modules/main.mk\"", within("tests/modules/lib"), "Error: import outside of tests/modules/lib: tests/modules/main.mk"),
+                 ("import \"tests/modules/lib/../main.mk\"", within("tests/modules/lib"), "Error: import outside of tests/modules/lib: tests/modules/lib/../main.mk"),
+                 ("import(\"Cargo.toml\")", within("tests/modules"), "Error: import outside of tests/modules: Cargo.toml"),
+                 ("import(\"/etc/hostname\")", disabled.clone(), "Error: import is disabled: /etc/hostname"),
+                 ("import \"tests/modules/lib/math.mk\"", disabled.clone(), "Error: import is disabled: tests/modules/lib/math.mk"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval_with_limits(test.0, test.1.clone());
+        if evaluated.inspect() != test.2 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.2, evaluated.inspect());
+        }
+    }
+}
+
+fn test_trace(input: &str) -> Vec<String> {
+    match test_eval(input.to_string()) {
+        Object::Error { trace, .. } => format_trace(&trace),
+        other                       => panic!("no error object returned for {}. got={}", input, other.inspect()),
+    }
+}
+
+#[test]
+fn test_error_traces() {
+    let tests = [("1 + true", vec![]),
+                 ("let inner = fn(x) { x + true };\nlet outer = fn(x) { let y = inner(x); y };\nouter(1)",
+                  vec!["  in inner, called at 2:34", "  in outer, called at 3:6"]),
+                 ("let f = fn(n) { if (n == 0) { n + true } else { 1 + f(n - 1) } }; f(3)",
+                  vec!["  in f, called at 1:54", "  ... repeated 2 more times", "  in f, called at 1:68"]),
+                 ("infix \"<>\" sum left = fn(a, b) { a + b }; 1 <> true", vec!["  in <>, called at 1:45"]),
+                 ("let f = fn() { [0][\"a\"] }; let g = f; fn() { let x = g(); x }()",
+                  vec!["  in f, called at 1:55", "  in fn, called at 1:62"]),
+                 ("let f = fn() { [0][\"a\"] }; fn() { f() }()", vec!["  in f, called at 1:36"]),
+                 ("class A { init() { self.run(); self.ok = true } run() { throw \"boom\" } } A()",
+                  vec!["  in A.run, called at 1:28", "  in A.init, called at 1:75"]),
+                 ("let f = fn() { try { 1 + true } catch (e) { throw e } }; f()",
+                  vec!["  in f, called at 1:59"]),
+    ];
+
+    for test in tests.iter() {
+        assert_eq!(test_trace(test.0), test.1, "{}", test.0);
+    }
+
+    let trace = test_trace("import \"tests/modules/lib/fail.mk\" as m; m.fail(1 == 1)");
+    assert_eq!(trace.len(), 2);
+    assert!(trace[0].starts_with("  in check, called at ") && trace[0].ends_with("fail.mk:2:40"), "{}", trace[0]);
+    assert_eq!(trace[1], "  in fail, called at 1:48");
+}
+
+#[test]
+fn test_lambda_application() {
+    let tests = [("let double = |x| x * 2; double(5);", 10),
+                 ("let add = |x, y| x + y; add(2, 3);", 5),
+                 ("let one = || 1; one();", 1),
+                 ("let inc = x => x + 1; inc(4);", 5),
+                 ("let apply = fn(f, x) { f(x) }; apply(|x| x * 3, 4);", 12),
+                 ("let apply = fn(f, x) { f(x) }; apply(x => x - 1, 4);", 3),
+                 ("let adder = |x| |y| x + y; adder(2)(3);", 5),
+                 ("|x| { let y = x * 2; y + 1 }(3)", 7)
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if !test_integer_object(evaluated, test.1) {
+            panic!();
+        }
+    }
+}
+
 #[test]
 fn test_string_literal() {
     let input = "\"Hello World\"".to_string();
@@ -371,7 +605,7 @@ fn test_builtin_functions() {
             },
             Type::String(value)  => {
                 match evaluated {
-                    Object::Error { msg } => {
+                    Object::Error { msg, .. } => {
                         if msg != *value {
                             panic!("msg is not '{}', got='{}'", *value, msg);
                         }
@@ -413,7 +647,9 @@ fn test_array_index_expressions() {
                  ("[1, 2, 3][1 + 1]", 3),
                  ("let myArray = [1, 2, 3]; myArray[2];", 3),
                  ("let myArray = [1, 2, 3]; myArray[0] + myArray[1] + myArray[2];", 6),
-                 ("let myArray = [1, 2, 3]; let i = myArray[0]; myArray[i]", 2)
+                 ("let myArray = [1, 2, 3]; let i = myArray[0]; myArray[i]", 2),
+                 ("[1, 2, 3][-1]", 3),
+                 ("[1, 2, 3][-3]", 1)
     ];
 
     for test in &tests {
@@ -423,3 +659,475 @@ fn test_array_index_expressions() {
         }
     }        
 }
+
+#[test]
+fn test_slice_expressions() {
+    let tests = [("[1, 2, 3, 4][1:3]", "[2, 3]"),
+                 ("[1, 2, 3, 4][:2]", "[1, 2]"),
+                 ("[1, 2, 3, 4][2:]", "[3, 4]"),
+                 ("[1, 2, 3, 4][:]", "[1, 2, 3, 4]"),
+                 ("[1, 2, 3, 4][-2:]", "[3, 4]"),
+                 ("[1, 2, 3, 4][:-1]", "[1, 2, 3]"),
+                 ("[1, 2, 3, 4][3:1]", "[]"),
+                 ("[1, 2, 3, 4][-10:10]", "[1, 2, 3, 4]"),
+                 ("let n = 2; [1, 2, 3, 4][:n]", "[1, 2]"),
+                 ("\"Hello\"[1:3]", "el"),
+                 ("\"Hello\"[-3:]", "llo"),
+                 ("\"Hello\"[-1]", "o"),
+                 ("\"héllo\"[1]", "é"),
+                 ("\"héllo\"[:2]", "hé"),
+                 ("len(\"héllo\")", "5"),
+                 ("let s = \"héllo\"; s[len(s) - 1]", "o"),
+                 ("[first(\"éa\"), last(\"aé\"), rest(\"éa\")]", "[é, é, a]"),
+                 ("[][1:]", "[]"),
+                 ("[1, 2][\"a\":]", "Error: slice index must be Integer. got=String"),
+                 ("1[0:1]", "Error: slice operator not supported: Integer")
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_method_call_syntax() {
+    let tests = [("[1, 2, 3].push(4)", "[1, 2, 3, 4]"),
+                 ("\"abc\".len()", "3"),
+                 ("[1, 2, 3].push(4).len()", "4"),
+                 ("[1, 2, 3].rest().first()", "2"),
+                 ("let a = [1]; a.push(2)[1]", "2"),
+                 ("let double = fn(x) { x * 2 }; 5.double()", "10"),
+                 ("let add = |x, y| x + y; 1.add(2).add(3)", "6"),
+                 ("[1].nothing()", "Error: undefined method: nothing for Array"),
+                 ("[1].len", "Error: unknown member: Array.len"),
+                 ("\"abc\".len(1)", "Error: wrong number of arguments. got=2, want=1")
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_user_defined_operators() {
+    let tests = [("infix \"<>\" equals = fn(a, b) { !(a == b) }; 1 <> 2", "true"),
+                 ("infix \"<>\" equals = fn(a, b) { !(a == b) }; 1 + 1 <> 2", "false"),
+                 ("infix \"++\" sum = |a, b| push(a, b); [1] ++ 2 ++ 3", "[1, 2, 3]"),
+                 ("infix \"**\" product right = |a, b| a * 10 + b; 1 ** 2 ** 3", "33"),
+                 ("infix \"|>\" lessgreater = |x, f| f(x); 5 |> |x| x * 2", "10"),
+                 ("infix \"<>\" equals = 5; 1 <> 2", "Error: operator <> must be bound to a function. got=Integer")
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_embedder_defined_operator() {
+    fn concat(args: Vec<Object>, _context: &RefCell<Context>) -> Object {
+        Object::String { value: format!("{}{}", args[0].inspect(), args[1].inspect()) }
+    }
+
+    let mut table = ParseTable::new();
+    table.register_operator("<>", Precedence::Sum, Associativity::Left).unwrap();
+
+    let lexier = Lexier::new("1 <> 2 <> \"a\"".to_string());
+    let mut parser = Parser::new_with_table(lexier, table);
+    let program = parser.parse_program().unwrap();
+    let mut env = Env::new();
+    env.set("<>".to_string(), Object::Builtin { function: concat });
+
+    assert_eq!(eval(&program, &mut env).unwrap().inspect(), "12a");
+}
+
+#[test]
+fn test_const_statements() {
+    let tests = [("const a = 5; a;", "5"),
+                 ("const a = 5; let b = a * 2; b;", "10"),
+                 ("let a = 1; a = a + 1; a;", "2"),
+                 ("let a = 1; let b = 2; a = b = 3; a + b;", "6"),
+                 ("const a = 5; a = 6;", "Error: cannot assign to constant: a"),
+                 ("const a = 5; let a = 6;", "Error: cannot redeclare constant: a"),
+                 ("const a = 5; const a = 6;", "Error: cannot redeclare constant: a"),
+                 ("const a = 5; let f = fn(a) { a * 2 }; f(2);", "4"),
+                 ("const a = 5; let f = fn() { let a = 1; a }; f();", "1"),
+                 ("b = 1;", "Error: identifier not found: b")
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_try_catch_finally() {
+    let tests = [("try { 1 } catch (e) { 2 }", "1"),
+                 ("try { 5 + true } catch (e) { e.kind }", "TypeError"),
+                 ("try { 5 + true } catch (e) { e.message }", "type mismatch: Integer + Boolean"),
+                 ("try { throw \"boom\"; } catch (e) { e.kind + \": \" + e.message }", "Error: boom"),
+                 ("try { throw error(\"ValueError\", \"bad\"); } catch (e) { e }", "ValueError: bad"),
+                 ("try { throw 42; 1 } catch (e) { e.message }", "42"),
+                 ("try { try { throw \"inner\"; } catch (e) { throw e; } } catch (e) { e.message }", "inner"),
+                 ("let x = 0; try { 1 } finally { x = 5; }; x", "5"),
+                 ("let x = 0; try { throw \"a\"; } catch (e) { x = 1; } finally { x = x + 1; }; x", "2"),
+                 ("try { throw \"a\"; } finally { 1 }", "Error: a"),
+                 ("try { 1 } finally { throw \"b\"; }", "Error: b"),
+                 ("try { missing } catch (e) { e.kind }", "NameError"),
+                 ("throw error(\"ValueError\", \"bad\");", "Error: bad"),
+                 ("try { throw \"a\"; } catch (e) { e.other }", "Error: unknown member: Exception.other"),
+                 ("const e = 1; try { throw \"x\"; } catch (e) { e.message }", "x"),
+                 ("const e = 1; try { throw \"x\"; } catch (e) { 2 }; e", "1"),
+                 ("try { throw \"x\"; } catch (e) { 2 }; e", "Error: identifier not found: e"),
+                 ("let x = 0; try { throw \"x\"; } catch (e) { x = 1; }; x", "1"),
+                 ("try { 1 / 0 } catch (e) { e.kind }", "ArithmeticError"),
+                 ("try { 1 / 0 } catch (e) { e.message }", "division by zero: 1 / 0"),
+                 ("try { 9223372036854775807 + 1 } catch (e) { e.kind }", "ArithmeticError"),
+                 ("let n = 9223372036854775807; try { n * 2 } catch (e) { e.message }", "integer overflow: 9223372036854775807 * 2"),
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_struct_declarations() {
+    let tests = [("struct Point { x, y } Point(1, 2)", "Point { x: 1, y: 2 }"),
+                 ("struct Point { x, y } let p = Point(1, 2); p.x + p.y", "3"),
+                 ("struct Point { x, y } Point", "struct Point { x, y }"),
+                 ("struct Point { x, y } Point(1, 2) == Point(1, 2)", "true"),
+                 ("struct Point { x, y } Point(1, 2) != Point(2, 1)", "true"),
+                 ("struct Line { a, b } struct Point { x, y } Line(Point(0, 0), [1]) == Line(Point(0, 0), [1])", "true"),
+                 ("struct P { x } struct Q { x } P(1) == Q(1)", "Error: type mismatch: P == Q"),
+                 ("struct Point { x, y } Point(1)", "Error: wrong number of arguments to Point. got=1, want=2"),
+                 ("struct Point { x, y } Point(1, 2).z", "Error: unknown member: Point.z"),
+                 ("struct Box { f } let b = Box(|x| x * 2); b.f(4)", "8"),
+                 ("struct Point { x, y } let norm = fn(p) { p.x * p.x + p.y * p.y }; Point(3, 4).norm()", "25"),
+                 ("struct Error { msg }", "Error: cannot redefine built-in type: Error"),
+                 ("struct Empty {} Empty()", "Empty {}"),
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_enum_declarations() {
+    let tests = [("enum Shape { Circle(r), Rect(w, h), Empty } Shape", "enum Shape { Circle(r), Rect(w, h), Empty }"),
+                 ("enum Shape { Circle(r), Empty } Shape.Circle(2)", "Shape.Circle(2)"),
+                 ("enum Shape { Circle(r), Empty } Shape.Empty", "Shape.Empty"),
+                 ("enum Shape { Circle(r), Empty } Shape.Circle", "Shape.Circle(r)"),
+                 ("enum Shape { Rect(w, h) } let s = Shape.Rect(2, 3); s.w * s.h", "6"),
+                 ("enum Shape { Circle(r), Empty } variant(Shape.Circle(1))", "Circle"),
+                 ("enum Shape { Circle(r), Empty } Shape.Empty.variant()", "Empty"),
+                 ("enum Shape { Circle(r), Empty } Shape.Circle(1) == Shape.Circle(1)", "true"),
+                 ("enum Shape { Circle(r), Empty } Shape.Circle(1) == Shape.Empty", "false"),
+                 ("enum Shape { Circle(r), Empty } Shape.Circle(1) != Shape.Circle(2)", "true"),
+                 ("enum Shape { Circle(r), Rect(w, h) }\
+                   let area = fn(s) { if (variant(s) == \"Circle\") { 3 * s.r * s.r } else { s.w * s.h } };\
+                   area(Shape.Circle(2)) + area(Shape.Rect(2, 3))", "18"),
+                 ("enum Shape { Circle(r) } Shape.Circle(1).kind", "Error: unknown member: Shape.kind"),
+                 ("enum Shape { Circle(r) } Shape.Square", "Error: unknown member: EnumType.Square"),
+                 ("enum Shape { Circle(r) } Shape.Circle()", "Error: wrong number of arguments to Shape.Circle. got=0, want=1"),
+                 ("enum A { X } enum B { X } A.X == B.X", "Error: type mismatch: A == B"),
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_classes() {
+    let tests = [("class Counter { init(n) { self.n = n } inc() { self.n += 1 } } let c = Counter(1); c.inc(); c.inc(); c.n", "3"),
+                 ("class Counter { init(n) { self.n = n } } Counter(5)", "Counter { n: 5 }"),
+                 ("class Counter { init(n) { self.n = n } } Counter", "class Counter"),
+                 ("class Empty { } Empty()", "Empty {}"),
+                 ("class P { init(x) { self.x = x } get() { self.x } } let p = P(1); let q = p; q.x = 7; p.get()", "7"),
+                 ("class P { init(x) { self.x = x } } let p = P(1); p == p", "true"),
+                 ("class P { init(x) { self.x = x } } P(1) == P(1)", "false"),
+                 ("class A { init(n) { self.n = n } get() { self.n } twice() { self.get() * 2 } }\
+                   class B extends A { get() { 10 } }\
+                   B(1).twice()", "20"),
+                 ("class A { init(n) { self.n = n } }\
+                   class B extends A { init(n) { super.init(n * 2); self.m = 1 } }\
+                   B(3)", "B { n: 6, m: 1 }"),
+                 ("class A { init() { self.me = self } } let a = A(); a", "A { me: <A ...> }"),
+                 ("class A { init() { self.all = [self, self] } } A()", "A { all: [<A ...>, <A ...>] }"),
+                 ("class P { init(x) { self.x = x } } let p = P(1); [p, p]", "[P { x: 1 }, P { x: 1 }]"),
+                 ("class A { name() { \"a\" } } class B extends A { name() { super.name() + \"b\" } } B().name()", "ab"),
+                 ("class A { init(n) { self.n = n } } A()", "Error: wrong number of arguments. got=0, want=1"),
+                 ("class A { } A(1)", "Error: wrong number of arguments to A. got=1, want=0"),
+                 ("class A { } A().x", "Error: unknown member: A.x"),
+                 ("class B extends Z { }", "Error: identifier not found: Z"),
+                 ("let Z = 1; class B extends Z { }", "Error: superclass must be a Class. got=Integer"),
+                 ("struct P { x } let p = P(1); p.x = 2", "Error: cannot assign to member x of P"),
+                 ("let x = 1; x += 2; x *= 3; x -= 1; x /= 2; x", "4"),
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+fn test_run_file(path: &str, search_path: Vec<&str>) -> Object {
+    let env = Env::new();
+    for dir in search_path {
+        env.loader().borrow_mut().add_search_path(PathBuf::from(dir));
+    }
+    let mut env = Env::new_module_env(env.loader(), Some(PathBuf::from(path)));
+
+    module::run_file(Path::new(path), &mut env)
+}
+
+#[test]
+fn test_modules() {
+    let tests = [("tests/modules/main.mk", vec![], "[16, 3, 2]"),
+                 ("tests/modules/search.mk", vec!["tests/modules/lib"], "9"),
+                 ("tests/modules/search.mk", vec![], "Error: module not found: math.mk"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_run_file(test.0, test.1.clone());
+        if evaluated.inspect() != test.2 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.2, evaluated.inspect());
+        }
+    }
+
+    let tests = [("tests/modules/syntax_error.mk", "SyntaxError", "tests/modules/syntax_error.mk: "),
+                 ("tests/modules/missing.mk", "IOError", "tests/modules/missing.mk: "),
+    ];
+
+    for test in tests.iter() {
+        match test_run_file(test.0, vec![]) {
+            Object::Error { kind, msg, .. } => {
+                assert_eq!(kind, test.1);
+                assert!(msg.starts_with(test.2), "{}", msg);
+            },
+            other => panic!("no error object returned. got={}", other.inspect()),
+        }
+    }
+
+    match test_run_file("tests/modules/cycle_a.mk", vec![]) {
+        Object::Error { kind, msg, .. } => {
+            assert_eq!(kind, "ImportError");
+            assert!(msg.starts_with("import cycle: "), "{}", msg);
+            assert!(msg.ends_with("cycle_a.mk"), "{}", msg);
+        },
+        other => panic!("no error object returned. got={}", other.inspect()),
+    }
+
+    let tests = [("import \"tests/modules/lib/math.mk\" as m; m.hidden", "Error: hidden is not exported by "),
+                 ("import \"tests/modules/lib/math.mk\" as m; m.square(5)", "25"),
+                 ("import(1)", "Error: import path must be String. got=Integer"),
+                 ("try { import(\"missing.mk\") } catch (e) { e.kind }", "ImportError"),
+                 ("try { import(\"tests/modules/syntax_error.mk\") } catch (e) { e.kind }", "SyntaxError"),
+    ];
+
+    for test in &tests {
+        let evaluated = test_eval(test.0.to_string());
+        if !evaluated.inspect().starts_with(test.1) {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_type_annotations() {
+    let tests = [("let x: int = 5; x * 2", "10"),
+                 ("let f = fn(a: int, b: string) -> bool { a == 1 }; f(1, \"b\")", "true"),
+                 ("let xs: [string] = [\"a\"]; let g: fn() -> [string] = fn() { xs }; g()", "[a]"),
+                 ("class A { get(n: int) -> int { n + 1 } } A().get(1)", "2"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+
+    let evaluated = test_run_file("tests/modules/typed.mk", vec![]);
+    assert_eq!(evaluated.inspect(), "[norm, [5, 25]]");
+
+    // a mismatch between literal types alone does not stop the program
+    let evaluated = test_run_file("tests/modules/untyped.mk", vec![]);
+    assert_eq!(evaluated.inspect(), "caught TypeError");
+
+    match test_run_file("tests/modules/ill_typed.mk", vec![]) {
+        Object::Error { kind, msg, .. } => {
+            assert_eq!(kind, "TypeError");
+            assert_eq!(msg, "tests/modules/ill_typed.mk:3:6: argument 1 must be string, got int");
+        },
+        other => panic!("no error object returned. got={}", other.inspect()),
+    }
+}
+
+#[test]
+fn test_generators() {
+    let tests = [("let g = fn() { yield 1; yield 2; }; let it = g(); [next(it), next(it), next(it)]", "[1, 2, null]"),
+                 ("let g = fn(xs) { for (x in xs) { yield x * 2; } }; collect(g([1, 2, 3]))", "[2, 4, 6]"),
+                 ("let g = fn(n) { if (n > 0) { yield n; } else { yield 0; } }; collect(g(5))", "[5]"),
+                 ("let g = fn() { yield 1; return 0; yield 2; }; collect(g())", "[1]"),
+                 ("let g = fn(xs) { for (x in xs) { yield x; } }; take(g(\"abc\"), 2)", "[a, b]"),
+                 ("let g = fn() { yield 1; }; g()", "generator"),
+                 ("let g = fn() { yield 1; }; g().next()", "1"),
+                 ("let s = 0; for (x in [1, 2, 3]) { s += x; }; s", "6"),
+                 ("let g = fn() { yield 1; yield 2; }; let s = 0; for (x in g()) { s += x; }; s", "3"),
+                 ("let xs = []; for (c in \"hi\") { xs = push(xs, c); }; xs", "[h, i]"),
+                 ("let f = fn(xs) { for (x in xs) { if (x > 1) { return x; } } }; f([1, 2, 3])", "2"),
+                 ("take([1, 2, 3], 5)", "[1, 2, 3]"),
+                 ("let g = fn() { yield 1 + true; yield 2; }; let it = g(); [next(it), next(it)]", "Error: type mismatch: Integer + Boolean"),
+                 ("let g = fn() { yield 1 + true; yield 2; }; let it = g(); try { next(it) } catch (e) { next(it) }", "null"),
+                 ("yield 1;", "Error: yield outside of a generator"),
+                 ("for (x in 5) { x }", "Error: Integer is not iterable"),
+                 ("const x = 1; for (x in [1]) { x }", "Error: cannot redeclare constant: x"),
+                 ("let g = fn() { const x = 1; for (x in [1]) { yield x; } }; next(g())", "Error: cannot redeclare constant: x"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_list_comprehensions() {
+    let tests = [("[x * x for x in [3, -1, 4, 0] if x > 0]", "[9, 16]"),
+                 ("[[x, y] for x in [1, 2] for y in \"ab\"]", "[[1, a], [1, b], [2, a], [2, b]]"),
+                 ("[x + y for x in [1, 2, 3] if x != 2 for y in [10, 20] if y > x * 5]", "[11, 21, 23]"),
+                 ("let g = fn() { yield 1; yield 2; }; [v * 10 for v in g()]", "[10, 20]"),
+                 ("let k = 3; [x * k for x in [1, 2]]", "[3, 6]"),
+                 ("let x = 99; [x for x in [1]]; x", "99"),
+                 ("[x for x in []]", "[]"),
+                 ("[x for x in [1, 2] if false]", "[]"),
+                 ("[x for x in 5]", "Error: Integer is not iterable"),
+                 ("[x + true for x in [1]]", "Error: type mismatch: Integer + Boolean"),
+                 ("[y for x in [1]]", "Error: identifier not found: y"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_ranges() {
+    let tests = [("1..10", "1..10"),
+                 ("0..=10 step 5", "0..=10 step 5"),
+                 ("collect(1..5)", "[1, 2, 3, 4]"),
+                 ("collect(1..=5)", "[1, 2, 3, 4, 5]"),
+                 ("collect(0..10 step 3)", "[0, 3, 6, 9]"),
+                 ("collect(5..=1 step -2)", "[5, 3, 1]"),
+                 ("collect(5..1)", "[]"),
+                 ("len(0..1000000000000)", "1000000000000"),
+                 ("len(1..=10 step 3)", "4"),
+                 ("let r = 10..20; [r[0], r[-1], r[10]]", "[10, 19, null]"),
+                 ("(0..100 step 7)[3]", "21"),
+                 ("[3 in 1..5, 5 in 1..5, 5 in 1..=5, 4 in 0..9 step 2, 3 in 0..9 step 2, \"a\" in 0..9]", "[true, false, true, true, false, false]"),
+                 ("[2 in [1, 2], 3 in [1, 2], \"ell\" in \"hello\", \"z\" in \"hello\"]", "[true, false, true, false]"),
+                 ("let s = 0; for (i in 1..=100) { s += i; }; s", "5050"),
+                 ("[x * x for x in 0..5 if x > 1]", "[4, 9, 16]"),
+                 ("take(0..1000000000000, 3)", "[0, 1, 2]"),
+                 ("[first(0..1000000000000), last(0..1000000000000), rest(0..1000000000000)]", "[0, 999999999999, 1..1000000000000]"),
+                 ("[first(10..=1 step -3), last(10..=1 step -3), rest(10..=1 step -3)]", "[10, 1, 7..=1 step -3]"),
+                 ("[first(1..1), last(1..1), rest(1..1), rest(9223372036854775807..=9223372036854775807)]", "[null, null, null, 9223372036854775807..9223372036854775807]"),
+                 ("let n = 3; 1..n + 1", "1..4"),
+                 ("[0..4 step 2 == 0..=3 step 2, 0..2 == 0..3, 1..1 != 2..2]", "[true, false, false]"),
+                 ("0..3 step 0", "Error: range step must not be zero"),
+                 ("0..\"a\"", "Error: range bound must be Integer. got=String"),
+                 ("(0..3)[\"a\"]", "Error: index operator not supported: Range"),
+                 ("1 in \"abc\"", "Error: type mismatch: Integer in String"),
+                 ("1 in 2", "Error: unknown operator: Integer in Integer"),
+                 ("struct Range { a }", "Error: cannot redefine built-in type: Range"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_null_and_optional_chaining() {
+    let tests = [("null", "null"),
+                 ("let x = null; x", "null"),
+                 ("[null == null, 1 == null, null != \"a\", !null]", "[true, false, true, true]"),
+                 ("let p = null; [p?.x, p?[0], p?[1:], p?.len()]", "[null, null, null, null]"),
+                 ("struct P { x } let p = P(3); [p?.x, [1, 2]?[1], \"abc\"?[1:], \"ab\"?.len()]", "[3, 2, bc, 2]"),
+                 ("[null ?? 1, 0 ?? 1, false ?? 1, null ?? null ?? \"d\"]", "[1, 0, false, d]"),
+                 ("let xs = [1]; xs[5] ?? -1", "-1"),
+                 ("let n = 0; let f = fn() { n = 1; 2 }; [5 ?? f(), n]", "[5, 0]"),
+                 ("null ?? 1 + true", "Error: type mismatch: Integer + Boolean"),
+                 ("let a = null; [a?.b.c, a?[0].x, a?.b[0](1).c, a?.f().g, a?[1:][0]]", "[null, null, null, null, null]"),
+                 ("let n = 0; let f = fn() { n = 1; 0 }; let a = null; a?[0][f()]; a?.g(f()); n", "0"),
+                 ("struct P { x } let a = P(null); a?.x.y", "Error: unknown member: Null.y"),
+                 ("let p = null; (p?.x ?? 1) + 1", "2"),
+                 ("let p = null; p.x", "Error: unknown member: Null.x"),
+                 ("let p = null; p[0]", "Error: index operator not supported: Null"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
+
+#[test]
+fn test_spread_and_variadic() {
+    let tests = [("let a = [1, 2]; [...a, 0, ...a]", "[1, 2, 0, 1, 2]"),
+                 ("[...[], ...1..4, ...\"ab\"]", "[1, 2, 3, a, b]"),
+                 ("let g = fn() { yield 1; yield 2; }; [0, ...g()]", "[0, 1, 2]"),
+                 ("let add = fn(a, b) { a + b }; add(...[1, 2])", "3"),
+                 ("let add = fn(a, b, c) { a + b + c }; add(1, ...[2, 3])", "6"),
+                 ("let f = fn(a, ...rest) { [a, rest] }; [f(1), f(1, 2, 3)]", "[[1, []], [1, [2, 3]]]"),
+                 ("let sum = fn(...xs) { let t = 0; for (x in xs) { t += x; }; t }; sum(...1..=4, 10)", "20"),
+                 ("let f = fn(a, ...rest) { rest }; f", "fn(a, ...rest) {rest}"),
+                 ("let f = fn(a, b, ...rest) { rest }; f(1)", "Error: wrong number of arguments. got=1, want=2 or more"),
+                 ("let add = fn(a, b) { a + b }; add(...[1, 2, 3])", "Error: wrong number of arguments. got=3, want=2"),
+                 ("[...5]", "Error: Integer is not iterable"),
+                 ("[...undefined]", "Error: identifier not found: undefined"),
+    ];
+
+    for test in tests.iter() {
+        let evaluated = test_eval(test.0.to_string());
+        if evaluated.inspect() != test.1 {
+            panic!("wrong result for {}. expected={}, got={}", test.0, test.1, evaluated.inspect());
+        }
+    }
+}
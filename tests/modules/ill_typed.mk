@@ -0,0 +1,3 @@
+let greet = fn(name: string) -> string { "hello " + name };
+puts("never printed");
+greet(42)
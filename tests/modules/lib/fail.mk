@@ -0,0 +1,2 @@
+let check = fn(x) { x + 1 };
+export let fail = fn(x) { let r = check(x); r };
@@ -0,0 +1,3 @@
+export let square = fn(x) { x * x };
+export const pi = 3;
+let hidden = 1;
@@ -0,0 +1,2 @@
+class Counter { init() { self.n = 0 } inc() { self.n += 1 } }
+export let counter = Counter();
@@ -0,0 +1,6 @@
+import "lib/math.mk";
+import "lib/state.mk" as a;
+let b = import("lib/state.mk");
+a.counter.inc();
+b.counter.inc();
+[math.square(4), math.pi, a.counter.n]
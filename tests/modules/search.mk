@@ -0,0 +1,2 @@
+import "math.mk";
+math.square(3)
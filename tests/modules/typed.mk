@@ -0,0 +1,7 @@
+struct Point { x, y }
+
+let norm = fn(p: Point) -> int { p.x * p.x + p.y * p.y };
+let label: string = "norm";
+let values: [int] = [norm(Point(1, 2)), norm(Point(3, 4))];
+
+[label, values]
@@ -0,0 +1,2 @@
+let r = try { 1 + "a" } catch (e) { "caught " + e.kind };
+r